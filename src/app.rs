@@ -1,25 +1,42 @@
+use log::warn;
+use std::mem;
 use std::path::PathBuf;
 use std::sync::mpsc::Sender;
 use std::sync::Mutex;
+use std::time::Duration;
 
-use crate::{forge, minecraft, routes, Instances, IoEvent, Opt, Paths, Route};
-
-
+use crate::{
+    endpoints, forge, jobs::Jobs, minecraft, mods::ModStore, routes, snapshots::Snapshots,
+    theme::Theme, util, watcher::Watcher, Instances, IoEvent, Opt, Paths, Route, Settings,
+};
 
 pub struct App {
     // We need a mutex so we can send the app into the io thread - even if the io_tx is never actually used there
     io_tx: Mutex<Sender<IoEvent>>,
     route_stack: Vec<Route>,
+    watcher: Mutex<Watcher>,
 
     pub state: routes::State,
+    pub jobs: Jobs,
 
     pub should_quit: bool,
-    pub hide_cursor: bool,
+    /// Where to show the cursor after drawing, hidden if None
+    pub cursor: Option<(u16, u16)>,
 
     pub instances: Instances,
+    pub settings: Settings,
+    /// The theme named in the settings
+    pub theme: Theme,
     pub paths: Paths,
     pub launcher: minecraft::Launcher,
+    pub java_runtimes: minecraft::JavaRuntimes,
+    pub snapshots: Snapshots,
+    pub mod_store: ModStore,
     pub java_home_overwrite: Option<PathBuf>,
+    /// Started with --offline
+    pub offline: bool,
+    /// Shown in place of the help line until the next key press
+    pub status: Option<String>,
 
     pub minecraft_version_manifest: Option<minecraft::VersionManifest>,
     pub forge_version_manifest: Option<forge::VersionManifest>,
@@ -28,34 +45,175 @@ pub struct App {
 impl App {
     pub fn new(opt: &Opt, io_tx: Sender<IoEvent>) -> ::anyhow::Result<Self> {
         let paths = Paths::new(opt)?;
-        let instances = Instances::from_file(&paths.file.instances, &paths.directory.instances)?;
+        let settings = Settings::from_file(&paths.file.config)?;
+        util::download::set_concurrency(settings.download_concurrency);
+        util::download::set_offline(opt.offline);
+
+        let mut endpoints = settings.endpoints.clone().with_env();
+        if let Some(java_runtime_manifest) = &opt.java_runtime_manifest {
+            endpoints.java_runtime_manifest = java_runtime_manifest.clone();
+        }
+        endpoints::set(endpoints);
 
+        Self::with_settings(opt, paths, settings, io_tx)
+    }
+
+    fn with_settings(
+        opt: &Opt,
+        paths: Paths,
+        settings: Settings,
+        io_tx: Sender<IoEvent>,
+    ) -> ::anyhow::Result<Self> {
         let launcher = minecraft::Launcher::new(
             &paths.directory.launcher_work,
             &paths.directory.launcher_cache,
             opt.launcher.as_ref(),
         )?;
 
+        let instances =
+            match Instances::from_file(&paths.file.instances, &paths.directory.instances) {
+                Ok(instances) => instances,
+                Err(e) if Instances::is_corrupt(&e) => {
+                    warn!("Failed to read instances: {:?}", e);
+                    Instances::recover(
+                        &paths.file.instances,
+                        &paths.directory.instances,
+                        &launcher,
+                    )?
+                }
+                Err(e) => return Err(e.context("Failed to read instances")),
+            };
+
+        let java_runtimes =
+            minecraft::JavaRuntimes::new(&paths.directory.java_runtimes, opt.offline);
+
+        let snapshots = Snapshots::new(&paths.directory.snapshots);
+        let mod_store = ModStore::new(&paths.directory.mod_store);
+
+        let theme = Theme::load(&paths.directory.themes, &settings.theme).unwrap_or_else(|e| {
+            warn!("Failed to load theme {}: {:?}", settings.theme, e);
+            Theme::default()
+        });
+
+        let mut watcher = Watcher::new(&paths, io_tx.clone())?;
+        watcher.sync(&instances);
+
         Ok(Self {
             io_tx: Mutex::new(io_tx),
+            watcher: Mutex::new(watcher),
             route_stack: vec![Route::Home],
             state: Default::default(),
+            jobs: Default::default(),
             should_quit: false,
             paths,
             instances,
+            settings,
+            theme,
             launcher,
+            java_runtimes,
+            snapshots,
+            mod_store,
             java_home_overwrite: opt.java_home.clone(),
-            hide_cursor: true,
+            offline: opt.offline,
+            status: None,
+            cursor: None,
             minecraft_version_manifest: None,
             forge_version_manifest: None,
         })
     }
 
+    /// An app on data and cache directories in `directory`, for driving the ui in tests
+    ///
+    /// It knows two instances, a forge one named Survival and a vanilla one named Vanilla, that
+    /// only exist in memory. Unlike [`App::new`] this leaves global state such as the endpoints
+    /// alone.
+    #[cfg(test)]
+    pub fn fake(directory: &std::path::Path) -> (Self, std::sync::mpsc::Receiver<IoEvent>) {
+        let data_directory = directory.join("data");
+        let cache_directory = directory.join("cache");
+        std::fs::create_dir_all(&data_directory).unwrap();
+        std::fs::create_dir_all(&cache_directory).unwrap();
+        let opt = Opt {
+            verbosity: 0,
+            data_directory: Some(data_directory),
+            cache_directory: Some(cache_directory),
+            launcher: Some(directory.join("minecraft-launcher")),
+            java_home: None,
+            offline: false,
+            java_runtime_manifest: None,
+        };
+        let (io_tx, io_rx) = std::sync::mpsc::channel();
+        let paths = Paths::new(&opt).unwrap();
+        let mut app = Self::with_settings(&opt, paths, Settings::default(), io_tx).unwrap();
+        for (i, (name, version_id, forge_name)) in [
+            ("Survival", "1.16.5", Some("forge-36.1.0")),
+            ("Vanilla", "1.17.1", None),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let instance = crate::Instance {
+                uuid: uuid::Uuid::from_u128(i as u128 + 1),
+                name: String::from(*name),
+                version_id: String::from(*version_id),
+                forge_name: forge_name.map(String::from),
+                instances_directory: app.paths.directory.instances.clone(),
+                ..Default::default()
+            };
+            app.instances.inner.insert(instance.name.clone(), instance);
+        }
+        (app, io_rx)
+    }
+
     /// Send a io event to the io thread
     pub fn dispatch(&self, action: IoEvent) {
         self.io_tx.lock().unwrap().send(action).unwrap();
     }
 
+    /// Update the filesystem watcher to match the current instances
+    pub fn sync_watcher(&self) {
+        self.watcher.lock().unwrap().sync(&self.instances);
+    }
+
+    /// The java home to use, with the command line taking precedence over the settings
+    pub fn java_home(&self) -> Option<PathBuf> {
+        self.java_home_overwrite
+            .clone()
+            .or_else(|| self.settings.java_home.clone())
+    }
+
+    pub fn cache_policy(&self) -> util::CachePolicy {
+        util::CachePolicy {
+            offline: self.offline,
+            max_age: Duration::from_secs(self.settings.manifest_max_age * 60),
+        }
+    }
+
+    /// Whether an action that needs the network may run, explaining why not if it may not
+    pub fn require_online(&mut self, action: &str) -> bool {
+        if self.offline {
+            self.status = Some(format!(
+                "{} needs the network - restart without --offline.",
+                action
+            ));
+        }
+        !self.offline
+    }
+
+    /// Whether the minecraft version manifest is available, explaining why not if it is not
+    pub fn require_minecraft_version_manifest(&mut self) -> bool {
+        if self.offline
+            && self.minecraft_version_manifest.is_none()
+            && !self.paths.file.minecraft_versions_cache.is_file()
+        {
+            self.status = Some(String::from(
+                "The version manifest has never been downloaded - restart without --offline.",
+            ));
+            return false;
+        }
+        true
+    }
+
     pub fn push_route(&mut self, route: Route) {
         self.route_stack.push(route);
     }
@@ -68,6 +226,18 @@ impl App {
         }
     }
 
+    /// Remove the topmost route of the same kind, wherever it is, leaving the ones above it be
+    pub fn remove_route(&mut self, route: &Route) -> Option<Route> {
+        let i = self
+            .route_stack
+            .iter()
+            .rposition(|other| mem::discriminant(other) == mem::discriminant(route))?;
+        if i == 0 {
+            return None;
+        }
+        Some(self.route_stack.remove(i))
+    }
+
     pub fn get_current_routes(&self) -> Vec<&Route> {
         let mut routes = Vec::new();
         for route in self.route_stack.iter().rev() {
@@ -84,7 +254,7 @@ impl App {
         self.should_quit = true;
     }
 
-    pub fn show_cursor(&mut self) {
-        self.hide_cursor = false;
+    pub fn show_cursor(&mut self, position: (u16, u16)) {
+        self.cursor = Some(position);
     }
 }
@@ -0,0 +1,181 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::{
+    env,
+    sync::{Arc, RwLock},
+};
+
+/// Rewrites urls starting with `from` to start with `to` instead
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Mirror {
+    pub from: String,
+    pub to: String,
+}
+
+/// Every remote service polyblock talks to
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Endpoints {
+    pub minecraft_version_manifest: String,
+    pub java_runtime_manifest: String,
+    /// Base of the forge version manifest, its timestamp and the per version install data
+    pub forge_modloader: String,
+    /// Base of the curse addon api
+    pub curse_api: String,
+    /// Applied to every url we fetch, including the ones found in manifests such as libraries,
+    /// assets and maven artifacts
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<Mirror>,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            minecraft_version_manifest: String::from(
+                "https://launchermeta.mojang.com/mc/game/version_manifest.json",
+            ),
+            java_runtime_manifest: String::from("https://launchermeta.mojang.com/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json"),
+            forge_modloader: String::from(
+                "https://addons-ecs.forgesvc.net/api/v2/minecraft/modloader",
+            ),
+            curse_api: String::from("https://addons-ecs.forgesvc.net/api/v2"),
+            mirrors: Vec::new(),
+        }
+    }
+}
+
+/// Parse mirrors written as from=to pairs separated by commas
+fn parse_mirrors(s: &str) -> Vec<Mirror> {
+    s.split(',')
+        .filter_map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            let from = parts.next()?.trim();
+            let to = parts.next()?.trim();
+            if from.is_empty() {
+                return None;
+            }
+            Some(Mirror {
+                from: from.to_owned(),
+                to: to.to_owned(),
+            })
+        })
+        .collect()
+}
+
+impl Endpoints {
+    /// Apply overrides from the environment
+    ///
+    /// Mirrors from POLYBLOCK_MIRRORS take precedence over the configured ones.
+    pub fn with_env(mut self) -> Self {
+        let mut vars: [(&str, &mut String); 4] = [
+            (
+                "POLYBLOCK_MINECRAFT_VERSION_MANIFEST",
+                &mut self.minecraft_version_manifest,
+            ),
+            (
+                "POLYBLOCK_JAVA_RUNTIME_MANIFEST",
+                &mut self.java_runtime_manifest,
+            ),
+            ("POLYBLOCK_FORGE_MODLOADER", &mut self.forge_modloader),
+            ("POLYBLOCK_CURSE_API", &mut self.curse_api),
+        ];
+        for (name, value) in vars.iter_mut() {
+            if let Ok(var) = env::var(name) {
+                **value = var;
+            }
+        }
+        if let Ok(var) = env::var("POLYBLOCK_MIRRORS") {
+            let mut mirrors = parse_mirrors(&var);
+            mirrors.append(&mut self.mirrors);
+            self.mirrors = mirrors;
+        }
+        self
+    }
+
+    /// The url to actually fetch, after applying the first matching mirror
+    ///
+    /// Only applied where the request is made, as a url rewritten twice may match another mirror.
+    pub fn rewrite(&self, url: &str) -> String {
+        for mirror in &self.mirrors {
+            if let Some(rest) = url.strip_prefix(&mirror.from) {
+                return format!("{}{}", mirror.to, rest);
+            }
+        }
+        url.to_owned()
+    }
+
+    pub fn forge_version_manifest(&self) -> String {
+        self.forge_modloader.clone()
+    }
+
+    pub fn forge_version_manifest_timestamp(&self) -> String {
+        format!("{}/timestamp", self.forge_modloader)
+    }
+
+    pub fn forge_version(&self, name: &str) -> String {
+        format!("{}/{}", self.forge_modloader, name)
+    }
+
+    pub fn curse_addon_files(&self, project_id: u64) -> String {
+        format!("{}/addon/{}/files", self.curse_api, project_id)
+    }
+}
+
+lazy_static! {
+    static ref CURRENT: RwLock<Arc<Endpoints>> = RwLock::new(Arc::new(Endpoints::default()));
+}
+
+/// The endpoints in use, shared by every fetcher
+pub fn current() -> Arc<Endpoints> {
+    Arc::clone(&CURRENT.read().unwrap())
+}
+
+pub fn set(endpoints: Endpoints) {
+    *CURRENT.write().unwrap() = Arc::new(endpoints);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite() {
+        let endpoints = Endpoints {
+            mirrors: parse_mirrors(
+                "https://libraries.minecraft.net/=http://localhost:8080/libraries/, https://maven.minecraftforge.net=http://localhost:8080/maven,broken",
+            ),
+            ..Default::default()
+        };
+        assert_eq!(endpoints.mirrors.len(), 2);
+        assert_eq!(
+            endpoints.rewrite("https://libraries.minecraft.net/com/mojang/a.jar"),
+            "http://localhost:8080/libraries/com/mojang/a.jar"
+        );
+        assert_eq!(
+            endpoints.rewrite("https://maven.minecraftforge.net/net/minecraftforge/forge.jar"),
+            "http://localhost:8080/maven/net/minecraftforge/forge.jar"
+        );
+        assert_eq!(
+            endpoints.rewrite("https://example.com/a.jar"),
+            "https://example.com/a.jar"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_once() {
+        // A mirror whose target is the source of another one
+        let endpoints = Endpoints {
+            forge_modloader: String::from("https://a.example.com/modloader"),
+            mirrors: parse_mirrors(
+                "https://a.example.com/=https://b.example.com/,https://b.example.com/=https://c.example.com/",
+            ),
+            ..Default::default()
+        };
+        let url = endpoints.forge_version("forge-1.16.5-36.2.0");
+        assert_eq!(url, "https://a.example.com/modloader/forge-1.16.5-36.2.0");
+        assert_eq!(
+            endpoints.rewrite(&url),
+            "https://b.example.com/modloader/forge-1.16.5-36.2.0"
+        );
+    }
+}
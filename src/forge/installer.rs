@@ -4,12 +4,13 @@ use log::{debug, trace, warn};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::ffi::OsString;
-use std::fs::{create_dir_all, File};
+use std::fs::{self, create_dir_all, File};
 use std::io::{BufWriter, Read};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use tokio::process::Command;
 
-use super::manifest::{VersionManifestVersion, URL};
+use super::manifest::VersionManifestVersion;
+use crate::endpoints;
 use crate::minecraft;
 use crate::util;
 
@@ -80,18 +81,16 @@ pub async fn install<P: AsRef<Path>>(
     version: VersionManifestVersion,
     forge_version_manifests_cache_directory: P,
     launcher: &minecraft::Launcher,
+    client: &reqwest::Client,
+    java_runtimes: &minecraft::JavaRuntimes,
     java_home: Option<PathBuf>,
+    java_exec: Option<PathBuf>,
+    download_concurrency: usize,
+    cancel: &util::Cancel,
 ) -> ::anyhow::Result<()> {
-    main_pb.inc_with_msg(1, "Locating java.").await;
-
     create_dir_all(&forge_version_manifests_cache_directory)
         .context("Failed to create forge version manifests cache directory!")?;
 
-    // Find java executable
-    let java_exec = util::java::find_exec(java_home)?;
-
-    debug!("Using java at: {:?}", java_exec);
-
     main_pb
         .inc_with_msg(1, "Fetching forge manifest for version.")
         .await;
@@ -101,6 +100,7 @@ pub async fn install<P: AsRef<Path>>(
             .as_ref()
             .join(format!("{}.json", &version.name)),
         &version,
+        cancel,
     )
     .await?;
 
@@ -119,22 +119,46 @@ pub async fn install<P: AsRef<Path>>(
     main_pb
         .inc_with_msg(1, "Downloading minecraft version.")
         .await;
-    launcher.download_version(sub_pb, minecraft_version).await?;
+    let java_version = launcher
+        .download_version(sub_pb, minecraft_version, cancel)
+        .await?;
+    sub_pb.reset().await;
+
+    main_pb.inc_with_msg(1, "Locating java.").await;
+
+    // Unless the instance names its own java, prefer the runtime mojang ships for this version,
+    // since forge processors are picky about the java version they run on
+    let java_exec = match java_exec {
+        Some(java_exec) => java_exec,
+        None => match java_runtimes
+            .ensure(sub_pb, client, &java_version, download_concurrency, cancel)
+            .await?
+        {
+            Some(java_exec) => java_exec,
+            None => util::java::find_exec_for(java_home, java_version.major_version)?,
+        },
+    };
     sub_pb.reset().await;
 
+    debug!("Using java at: {:?}", java_exec);
+
+    cancel.check()?;
     main_pb.inc_with_msg(1, "Writing forge version file.").await;
-    write_version_file(
+    let mut written = Written::default();
+    written.0.extend(write_version_file(
         minecraft_version.id.clone(),
         manifest.name,
         manifest.version_json,
         launcher.versions_directory.clone(),
-    )?;
+    )?);
 
     main_pb.inc_with_msg(1, "Downloading libraries.").await;
     download_libraries(
         sub_pb,
         install_profile.libraries,
         launcher.libraries_directory.clone(),
+        download_concurrency,
+        cancel,
     )
     .await?;
     sub_pb.reset().await;
@@ -157,6 +181,7 @@ pub async fn install<P: AsRef<Path>>(
         let hash = util::sha1_file(&forge_jar)?;
         if hash == forge_jar_sha1 {
             debug!("Patched forge checksum ok.");
+            written.keep();
             return Ok(());
         } else {
             debug!("Patched forge checksum did not match.");
@@ -165,6 +190,8 @@ pub async fn install<P: AsRef<Path>>(
 
     debug!("Running forge install processors.");
 
+    // Whatever the processors leave of it is no good if they don't finish
+    written.0.push(forge_jar.clone());
     run_processors(
         sub_pb,
         java_exec,
@@ -175,25 +202,62 @@ pub async fn install<P: AsRef<Path>>(
         launcher
             .libraries_directory
             .join(util::java::parse_maven(install_profile.path)),
+        cancel,
     )
     .await?;
     sub_pb.reset().await;
 
     main_pb.inc_with_msg(1, "Checking forge.").await;
-    let hash = util::sha1_file_with_progress(sub_pb, &forge_jar).await?;
+    let hash = util::sha1_file_with_progress(sub_pb, &forge_jar, cancel).await?;
     if hash != forge_jar_sha1 {
         return Err(anyhow!("Patched forge jar does not match checksum!"));
     }
 
+    written.keep();
     Ok(())
 }
 
+/// Files written by an install, removed again if it fails or is cancelled before finishing
+///
+/// Downloaded libraries are left alone, they are complete and shared with other versions.
+#[derive(Default)]
+struct Written(Vec<PathBuf>);
+
+impl Written {
+    fn keep(mut self) {
+        self.0.clear();
+    }
+}
+
+impl Drop for Written {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            debug!("Removing {:?} of unfinished forge install.", path);
+            if let Err(e) = fs::remove_file(path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to remove {:?}: {}", path, e);
+                }
+            }
+            // Only goes if nothing else is in there
+            let _ = fs::remove_dir(path.parent().unwrap());
+        }
+    }
+}
+
 async fn download_manifest(
     manifest_path: PathBuf,
     version: &VersionManifestVersion,
+    cancel: &util::Cancel,
 ) -> ::anyhow::Result<Manifest> {
     if !manifest_path.exists() {
-        util::download_file(&format!("{}/{}", URL, version.name), &manifest_path).await?;
+        util::download::download(
+            None,
+            &endpoints::current().forge_version(&version.name),
+            &manifest_path,
+            &Default::default(),
+            cancel,
+        )
+        .await?;
     }
 
     let mut manifest_file = File::open(&manifest_path)?;
@@ -209,6 +273,8 @@ async fn download_libraries(
     pb: &util::Progress,
     libraries: Vec<InstallProfileLibrary>,
     libraries_directory: PathBuf,
+    download_concurrency: usize,
+    cancel: &util::Cancel,
 ) -> ::anyhow::Result<()> {
     pb.set_length(libraries.len() as u64).await;
 
@@ -221,14 +287,14 @@ async fn download_libraries(
                 } => {
                     let library_path = libraries_directory.join(path);
                     async move {
-                        let r = download_library(name, library_path, url, sha1).await;
+                        let r = download_library(name, library_path, url, sha1, cancel).await;
                         pb.inc(1).await;
                         r
                     }
                 }
             }
         }))
-        .buffer_unordered(8)
+        .buffer_unordered(download_concurrency)
         .collect::<Vec<_>>()
         .await;
 
@@ -240,6 +306,7 @@ async fn download_library(
     path: PathBuf,
     url: String,
     sha1: Option<String>,
+    cancel: &util::Cancel,
 ) -> ::anyhow::Result<()> {
     debug!("Downloading library {}", name);
 
@@ -273,7 +340,7 @@ async fn download_library(
         return Ok(());
     }
 
-    util::download_file(&url, &path).await?;
+    util::download::download(None, &url, &path, &Default::default(), cancel).await?;
 
     match sha1 {
         Some(sha1) => {
@@ -306,12 +373,14 @@ async fn run_processors(
     data: InstallProfileData,
     version_jar: PathBuf,
     path: PathBuf,
+    cancel: &util::Cancel,
 ) -> ::anyhow::Result<()> {
     let classpath_divider = OsString::from(if cfg!(windows) { ";" } else { ":" });
 
     pb.set_length(processors.len() as u64).await;
 
     for processor in processors {
+        cancel.check()?;
         debug!("Running processor: {}", processor.jar);
         // pb.set_message(&format!("Running processor from {}", processor.jar));
 
@@ -388,12 +457,18 @@ async fn run_processors(
 
         trace!("Main class is {}", main_class);
 
-        let output = Command::new(java_exec.clone())
-            .arg("-cp")
-            .arg(classpath)
-            .arg(main_class)
-            .args(args)
-            .output()?;
+        // Dropping the output future on cancellation kills the processor
+        let output = cancel
+            .guard(
+                Command::new(java_exec.clone())
+                    .arg("-cp")
+                    .arg(classpath)
+                    .arg(main_class)
+                    .args(args)
+                    .kill_on_drop(true)
+                    .output(),
+            )
+            .await??;
 
         trace!("Stdout: {}", String::from_utf8(output.stdout)?);
         trace!("Stderr: {}", String::from_utf8(output.stderr)?);
@@ -413,13 +488,14 @@ fn write_version_file(
     forge_name: String,
     json: String,
     versions_directory: PathBuf,
-) -> ::anyhow::Result<()> {
+) -> ::anyhow::Result<Option<PathBuf>> {
     let name = format!("{}-{}", minecraft_version_id, forge_name);
     let path = versions_directory
         .join(&name)
         .join(format!("{}.json", &name));
     if path.exists() {
         debug!("Version file exists.");
+        Ok(None)
     } else {
         let mut data: HashMap<String, serde_json::Value> = serde_json::from_str(&json)?;
         data.remove("jar");
@@ -430,7 +506,82 @@ fn write_version_file(
         let file = File::create(&path)?;
         let writer = BufWriter::new(file);
         serde_json::to_writer(writer, &data)?;
+        Ok(Some(path))
     }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+    use std::fs;
+
+    const FORGE_A_URL: &str =
+        "https://maven.minecraftforge.net/net/minecraftforge/fixture-a/1.0/fixture-a-1.0.jar";
+    const FIXTURE_B_URL: &str =
+        "https://libraries.minecraft.net/com/mojang/fixture-b/1.0/fixture-b-1.0.jar";
+    const FIXTURE_C_URL: &str =
+        "https://libraries.minecraft.net/com/mojang/fixture-c/1.0/fixture-c-1.0.jar";
+
+    #[tokio::test]
+    async fn test_download_manifest_and_libraries() {
+        let server = fixture::server();
+        let temp = fixture::TempDir::new();
+        let pb = util::Progress::new();
+
+        let version = VersionManifestVersion {
+            name: String::from("forge-36.1.0"),
+            game_version: String::from("1.16.5"),
+            latest: false,
+            recommended: true,
+            date_modified: chrono::Utc::now(),
+        };
+        let cancel = util::Cancel::new();
+        let manifest = download_manifest(temp.path().join("forge-36.1.0.json"), &version, &cancel)
+            .await
+            .unwrap();
+        assert_eq!(manifest.install_method, 3);
+        let install_profile: InstallProfile =
+            serde_json::from_str(manifest.install_profile_json.as_ref().unwrap()).unwrap();
+        assert_eq!(install_profile.libraries.len(), 4);
+
+        // b is already installed, c is installed but does not match its checksum
+        let libraries_directory = temp.path().join("libraries");
+        let b_path = libraries_directory.join("com/mojang/fixture-b/1.0/fixture-b-1.0.jar");
+        let c_path = libraries_directory.join("com/mojang/fixture-c/1.0/fixture-c-1.0.jar");
+        fs::create_dir_all(b_path.parent().unwrap()).unwrap();
+        fs::create_dir_all(c_path.parent().unwrap()).unwrap();
+        fs::copy(fixture::path(FIXTURE_B_URL), &b_path).unwrap();
+        fs::write(&c_path, "stale").unwrap();
+
+        download_libraries(
+            &pb,
+            install_profile.libraries,
+            libraries_directory.clone(),
+            2,
+            &cancel,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(server.requests(FORGE_A_URL).len(), 1);
+        assert_eq!(server.requests(FIXTURE_B_URL).len(), 0);
+        assert_eq!(server.requests(FIXTURE_C_URL).len(), 1);
+        for (path, url) in &[
+            (
+                libraries_directory.join("net/minecraftforge/fixture-a/1.0/fixture-a-1.0.jar"),
+                FORGE_A_URL,
+            ),
+            (c_path, FIXTURE_C_URL),
+        ] {
+            assert_eq!(
+                fs::read(path).unwrap(),
+                fs::read(fixture::path(url)).unwrap()
+            );
+        }
+        // Libraries without a url are left to the processors
+        assert!(!libraries_directory
+            .join("net/minecraftforge/forge/1.16.5-36.1.0/forge-1.16.5-36.1.0-universal.jar")
+            .exists());
+    }
 }
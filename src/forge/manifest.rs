@@ -5,16 +5,13 @@ use reqwest;
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Reverse,
+    collections::HashSet,
     fs,
     io::{BufReader, BufWriter},
     path::PathBuf,
 };
 
-use crate::util;
-
-pub const URL: &str = "https://addons-ecs.forgesvc.net/api/v2/minecraft/modloader";
-
-const TIMESTAMP_URL: &str = "https://addons-ecs.forgesvc.net/api/v2/minecraft/modloader/timestamp";
+use crate::{endpoints, util};
 
 #[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -33,13 +30,28 @@ pub struct VersionManifest {
 }
 
 impl VersionManifest {
+    /// The forge versions for a minecraft version
+    pub fn for_game_version(&self, game_version: &str) -> Vec<&VersionManifestVersion> {
+        self.versions
+            .iter()
+            .filter(|version| version.game_version == game_version)
+            .collect()
+    }
+
+    /// The minecraft versions forge has builds for
+    pub fn game_versions(&self) -> HashSet<&str> {
+        self.versions
+            .iter()
+            .map(|version| version.game_version.as_str())
+            .collect()
+    }
+
     pub async fn fetch(
         pb: &util::Progress,
         client: &reqwest::Client,
         data_file_path: &PathBuf,
+        cache_policy: util::CachePolicy,
     ) -> ::anyhow::Result<Self> {
-        // TODO: If less than 15 min since cache was saved, don't even bother the server at all
-
         // the endpoint provides no content-length data so a proper progress bar here is near impossible
         pb.set_length(5).await;
 
@@ -58,20 +70,37 @@ impl VersionManifest {
 
         pb.inc(1).await;
 
+        if let Some(data) = &cached_data {
+            if cache_policy.is_fresh(data_file_path) {
+                debug!("Forge version manifest cache is fresh - not asking the server.");
+                return Ok(data.clone());
+            }
+        }
+        if cache_policy.offline {
+            return Err(cache_policy.not_cached("forge version manifest"));
+        }
+
         let mut current_timestamp: Option<DateTime<Utc>> = None;
 
         if let Some(data) = cached_data {
             current_timestamp = Some(Self::fetch_timestamp(&client).await?);
             if current_timestamp.clone().unwrap() == data.timestamp {
                 debug!("Timestamp is same - cache hit!");
+                // Touch the cache so it counts as fresh again
+                let writer = BufWriter::new(
+                    fs::File::create(data_file_path)
+                        .context("Failed to create forge version manifest cache.")?,
+                );
+                serde_json::to_writer(writer, &data)?;
                 return Ok(data);
             }
         }
 
         pb.inc(1).await;
 
+        let endpoints = endpoints::current();
         let response = client
-            .get(URL)
+            .get(&endpoints.rewrite(&endpoints.forge_version_manifest()))
             .send()
             .await
             .context("Failed to get forge version manifest.")?
@@ -110,8 +139,9 @@ impl VersionManifest {
     }
 
     async fn fetch_timestamp(client: &reqwest::Client) -> ::anyhow::Result<DateTime<Utc>> {
+        let endpoints = endpoints::current();
         let response = client
-            .get(TIMESTAMP_URL)
+            .get(&endpoints.rewrite(&endpoints.forge_version_manifest_timestamp()))
             .send()
             .await
             .context("Failed to get forge version manifest timestamp")?
@@ -123,3 +153,54 @@ impl VersionManifest {
             .context("Failed to decode forge version manifest timestamp")?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+    use std::time::Duration;
+
+    const URL: &str = "https://addons-ecs.forgesvc.net/api/v2/minecraft/modloader";
+
+    const TIMESTAMP_URL: &str =
+        "https://addons-ecs.forgesvc.net/api/v2/minecraft/modloader/timestamp";
+
+    #[tokio::test]
+    async fn test_fetch_timestamp_cache() {
+        let server = fixture::server();
+        let temp = fixture::TempDir::new();
+        let cache_path = temp.path().join("forge_versions.json");
+        let client = reqwest::Client::new();
+        let pb = util::Progress::new();
+        let stale = util::CachePolicy {
+            offline: false,
+            max_age: Duration::from_secs(0),
+        };
+
+        let manifest = VersionManifest::fetch(&pb, &client, &cache_path, stale)
+            .await
+            .unwrap();
+        let names: Vec<&str> = manifest.versions.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["forge-37.0.0", "forge-36.1.32", "forge-36.1.0"]);
+        assert_eq!(server.requests(URL).len(), 1);
+        assert_eq!(server.requests(TIMESTAMP_URL).len(), 1);
+
+        // An unchanged timestamp means the cached versions are still current
+        let cached = VersionManifest::fetch(&pb, &client, &cache_path, stale)
+            .await
+            .unwrap();
+        assert_eq!(cached.versions, manifest.versions);
+        assert_eq!(server.requests(URL).len(), 1);
+        assert_eq!(server.requests(TIMESTAMP_URL).len(), 2);
+
+        // Unless the cache is fresh enough not to ask at all
+        let fresh = util::CachePolicy {
+            max_age: Duration::from_secs(3600),
+            ..stale
+        };
+        VersionManifest::fetch(&pb, &client, &cache_path, fresh)
+            .await
+            .unwrap();
+        assert_eq!(server.requests(TIMESTAMP_URL).len(), 2);
+    }
+}
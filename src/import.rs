@@ -0,0 +1,221 @@
+use ::anyhow::Context;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{minecraft, util};
+
+/// Entries of a game directory that belong to the launcher rather than the instance, left out
+/// when copying a directory into the instances directory
+pub const LAUNCHER_ENTRIES: [&str; 6] = [
+    "versions",
+    "libraries",
+    "assets",
+    "runtime",
+    "launcher_profiles.json",
+    "launcher_accounts.json",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    Copy,
+    Move,
+    /// Leave the directory where it is and point the instance at it
+    KeepInPlace,
+}
+
+impl ImportMode {
+    pub const ALL: [ImportMode; 3] = [ImportMode::Copy, ImportMode::Move, ImportMode::KeepInPlace];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ImportMode::Copy => "Copy into the instances directory",
+            ImportMode::Move => "Move into the instances directory",
+            ImportMode::KeepInPlace => "Keep in place",
+        }
+    }
+}
+
+/// A version an imported directory was played with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedVersion {
+    pub version_id: String,
+    pub forge_name: Option<String>,
+    /// Where the version was found, e.g. a launcher profile name
+    pub source: String,
+}
+
+impl DetectedVersion {
+    pub fn full_version_id(&self) -> String {
+        match &self.forge_name {
+            Some(forge_name) => format!("{}-{}", self.version_id, forge_name),
+            None => self.version_id.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ForeignLauncherConfig {
+    #[serde(default)]
+    profiles: HashMap<String, ForeignLauncherProfile>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForeignLauncherProfile {
+    #[serde(default)]
+    name: String,
+    last_version_id: Option<String>,
+    game_dir: Option<PathBuf>,
+    #[serde(default)]
+    last_used: String,
+}
+
+fn same_directory(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// (profile name, version id) of the profiles in a launcher_profiles.json that play in the given
+/// directory, most recently used first
+fn profile_versions(s: &str, directory: &Path) -> ::anyhow::Result<Vec<(String, String)>> {
+    let config: ForeignLauncherConfig =
+        serde_json::from_str(s).context("Got malformed launcher_profiles.json.")?;
+    let mut profiles: Vec<_> = config
+        .profiles
+        .into_iter()
+        .map(|(_, profile)| profile)
+        .filter(|profile| match &profile.game_dir {
+            Some(game_dir) => same_directory(game_dir, directory),
+            // Profiles without a game directory play in the launcher's own directory
+            None => true,
+        })
+        .collect();
+    // lastUsed is an ISO 8601 timestamp, so it sorts as a string
+    profiles.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+
+    Ok(profiles
+        .into_iter()
+        .filter_map(|profile| {
+            let version_id = profile.last_version_id?;
+            // latest-release and latest-snapshot are resolved by the launcher at launch
+            if version_id.starts_with("latest-") {
+                return None;
+            }
+            Some((profile.name, version_id))
+        })
+        .collect())
+}
+
+/// Guess the versions a game directory was played with, from its launcher_profiles.json and its
+/// versions directory, most likely first
+pub fn detect_versions(directory: &Path) -> Vec<DetectedVersion> {
+    let versions_directory = directory.join("versions");
+    let mut detected: Vec<DetectedVersion> = Vec::new();
+    let mut push = |version_id: &str, source: String| {
+        let (version_id, forge_name) = minecraft::split_version_id(&versions_directory, version_id);
+        let version = DetectedVersion {
+            version_id,
+            forge_name,
+            source,
+        };
+        if !detected
+            .iter()
+            .any(|v| v.full_version_id() == version.full_version_id())
+        {
+            detected.push(version);
+        }
+    };
+
+    if let Ok(s) = fs::read_to_string(directory.join("launcher_profiles.json")) {
+        for (name, version_id) in profile_versions(&s, directory).unwrap_or_default() {
+            push(&version_id, format!("profile {}", name));
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir(&versions_directory) {
+        let mut version_ids: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|id| {
+                versions_directory
+                    .join(id)
+                    .join(format!("{}.json", id))
+                    .is_file()
+            })
+            .collect();
+        version_ids.sort();
+        for version_id in version_ids.iter().rev() {
+            push(version_id, String::from("versions directory"));
+        }
+    }
+
+    detected
+}
+
+/// Make a forge version installed in an imported directory available to our launcher, copying
+/// its version json and libraries
+pub async fn adopt_forge_version(
+    pb: &util::Progress,
+    directory: &Path,
+    launcher: &minecraft::Launcher,
+    full_version_id: &str,
+) -> ::anyhow::Result<()> {
+    let version_directory = directory.join("versions").join(full_version_id);
+    let target = launcher.versions_directory.join(full_version_id);
+    if target.exists() || !version_directory.is_dir() {
+        return Ok(());
+    }
+
+    pb.set_msg(format!("Copying {}.", full_version_id)).await;
+    util::copy_directory_with_progress(pb, &version_directory, &target, &[])
+        .await
+        .context("Failed to copy forge version!")?;
+
+    let libraries_directory = directory.join("libraries");
+    if libraries_directory.is_dir() {
+        pb.set_msg("Copying libraries.").await;
+        util::copy_directory_with_progress(
+            pb,
+            &libraries_directory,
+            &launcher.libraries_directory,
+            &[],
+        )
+        .await
+        .context("Failed to copy libraries!")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_versions() {
+        let s = r#"{
+            "profiles": {
+                "a": {"name": "Old", "lastVersionId": "1.12.2", "lastUsed": "2019-01-01T00:00:00.000Z"},
+                "b": {"name": "New", "lastVersionId": "1.12.2-forge-14.23.5.2847", "lastUsed": "2020-01-01T00:00:00.000Z"},
+                "c": {"name": "Latest", "lastVersionId": "latest-release", "type": "latest-release"},
+                "d": {"name": "Elsewhere", "lastVersionId": "1.15.2", "gameDir": "/nonexistent/elsewhere"}
+            },
+            "settings": {}
+        }"#;
+        assert_eq!(
+            profile_versions(s, Path::new("/nonexistent/minecraft")).unwrap(),
+            vec![
+                (
+                    String::from("New"),
+                    String::from("1.12.2-forge-14.23.5.2847")
+                ),
+                (String::from("Old"), String::from("1.12.2")),
+            ]
+        );
+    }
+}
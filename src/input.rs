@@ -1,14 +1,415 @@
 use crate::{App, Key};
 
 pub fn handle(key: Key, app: &mut App) {
+    app.status = None;
+
     let routes = app.get_current_routes();
     let route = routes.last().unwrap();
 
     if key == Key::Esc {
-        if app.pop_route().is_none() {
+        if !route.get_impl().handle_esc(app) && app.pop_route().is_none() {
             app.quit();
         }
+    } else if key == Key::Ctrl('b') {
+        // Leave the job running on this route to finish in the background
+        let route = (*route).clone();
+        if app.jobs.hide(&route) {
+            app.pop_route();
+            app.status = Some(String::from(
+                "Running in the background, see ctrl+T on home.",
+            ));
+        }
     } else {
         route.get_impl().handle_key(key, app);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        minecraft,
+        routes::{self, instance_settings, new_instance},
+        theme::Theme,
+        util::fixture,
+        IoEvent, Route,
+    };
+    use std::fs;
+
+    fn handle_keys(keys: &[Key], app: &mut App) {
+        for key in keys {
+            handle(*key, app);
+        }
+    }
+
+    fn top(app: &App) -> Route {
+        app.get_current_routes().last().cloned().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_menu_navigation() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+
+        // Down wraps around to Survival again before opening its menu
+        handle_keys(&[Key::Down, Key::Down, Key::Enter], &mut app);
+        assert!(matches!(top(&app), Route::InstanceMenu));
+        assert_eq!(
+            app.state.instance_menu.instance.as_ref().unwrap().name,
+            "Survival"
+        );
+
+        handle(Key::Esc, &mut app);
+        assert!(matches!(top(&app), Route::Home));
+        assert!(!app.should_quit);
+        handle(Key::Esc, &mut app);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_filter() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+
+        // Vanilla stays selected while it matches, then the selection moves to what does
+        handle_keys(&[Key::Down, Key::Char('/'), Key::Char('i')], &mut app);
+        handle_keys(&[Key::Char('v'), Key::Backspace, Key::Enter], &mut app);
+        assert!(matches!(top(&app), Route::InstanceMenu));
+        assert_eq!(
+            app.state.instance_menu.instance.as_ref().unwrap().name,
+            "Survival"
+        );
+
+        // Esc clears the filter before quitting
+        handle_keys(&[Key::Esc, Key::Esc], &mut app);
+        assert!(!app.should_quit);
+        handle(Key::Esc, &mut app);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_version_types() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        let manifest = fs::read(fixture::path(
+            "https://launchermeta.mojang.com/mc/game/version_manifest.json",
+        ))
+        .unwrap();
+        app.minecraft_version_manifest = Some(serde_json::from_slice(&manifest).unwrap());
+        app.push_route(Route::NewInstance);
+        app.state.new_instance.inner = new_instance::InnerState::ChooseMinecraftVersion;
+        let ids = |app: &App| -> Vec<String> {
+            let picker = &app.state.new_instance.version_picker;
+            picker
+                .choices(app.minecraft_version_manifest.as_ref().unwrap())
+                .into_iter()
+                .map(|choice| choice.label)
+                .collect()
+        };
+
+        assert_eq!(ids(&app), ["Latest release (1.17.1)", "1.16.5", "1.17.1"]);
+        handle(Key::Char('s'), &mut app);
+        assert_eq!(
+            ids(&app),
+            ["Latest release (1.17.1)", "1.16.5", "1.17.1", "21w03a"]
+        );
+
+        // The latest release is looked up in the manifest
+        handle(Key::Enter, &mut app);
+        let chosen: &minecraft::VersionManifestVersion = app
+            .state
+            .new_instance
+            .chosen_minecraft_version
+            .as_ref()
+            .unwrap();
+        assert_eq!(chosen.id, "1.17.1");
+    }
+
+    #[test]
+    fn test_rename() {
+        let temp = fixture::TempDir::new();
+        let (mut app, io_rx) = App::fake(temp.path());
+
+        handle_keys(&[Key::Down, Key::F2], &mut app);
+        assert!(matches!(top(&app), Route::RenameInstance));
+        assert_eq!(app.state.rename_instance.name_input, "Vanilla");
+
+        // Taken names are refused
+        handle_keys(&[Key::Backspace; 7], &mut app);
+        handle_keys(
+            &"Survival".chars().map(Key::Char).collect::<Vec<_>>(),
+            &mut app,
+        );
+        assert!(app.state.rename_instance.error.is_some());
+        handle(Key::Enter, &mut app);
+        assert!(io_rx.try_recv().is_err());
+
+        handle_keys(&[Key::Char('!'), Key::Enter], &mut app);
+        assert_eq!(app.state.rename_instance.name_input, "Survival!");
+        assert_eq!(io_rx.try_recv(), Ok(IoEvent::RenameInstance));
+    }
+
+    #[test]
+    fn test_status_cleared() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        app.offline = true;
+
+        // Without a cached version manifest there is nothing to create an instance from
+        handle(Key::Ctrl('n'), &mut app);
+        assert!(matches!(top(&app), Route::Home));
+        assert!(app.status.is_some());
+
+        handle(Key::Down, &mut app);
+        assert!(app.status.is_none());
+    }
+
+    #[test]
+    fn test_error_dialog() {
+        let temp = fixture::TempDir::new();
+        let (mut app, io_rx) = App::fake(temp.path());
+        let e = ::anyhow::anyhow!("connection refused");
+
+        // Retrying sends the event again and goes back to what was waiting on it
+        handle(Key::Ctrl('s'), &mut app);
+        app.push_route(Route::NewInstance);
+        routes::error::show(
+            &mut app,
+            routes::error::Failure::new(IoEvent::NewInstanceFetchMinecraftVersionManifest, &e),
+        );
+        handle(Key::Char('r'), &mut app);
+        assert!(matches!(top(&app), Route::NewInstance));
+        assert_eq!(
+            io_rx.try_recv(),
+            Ok(IoEvent::NewInstanceFetchMinecraftVersionManifest)
+        );
+
+        // Dismissing also closes it, but leaves screens that were not waiting alone
+        routes::error::show(
+            &mut app,
+            routes::error::Failure::new(IoEvent::NewInstanceFetchMinecraftVersionManifest, &e),
+        );
+        routes::error::show(
+            &mut app,
+            routes::error::Failure::new(IoEvent::ReloadInstances, &e),
+        );
+        handle(Key::Esc, &mut app);
+        assert!(matches!(top(&app), Route::Error));
+        handle_keys(&[Key::Left, Key::Enter], &mut app);
+        assert!(matches!(top(&app), Route::Settings));
+        assert!(io_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_retry_changed_inputs() {
+        let temp = fixture::TempDir::new();
+        let (mut app, io_rx) = App::fake(temp.path());
+        app.push_route(Route::NewInstance);
+        app.state.new_instance.name_input = String::from("Creative");
+        let mut failure =
+            routes::error::Failure::new(IoEvent::NewInstance, &::anyhow::anyhow!("disk full"));
+        failure.inputs = crate::jobs::inputs(&IoEvent::NewInstance, &app);
+
+        // The dialog was opened again for another instance meanwhile
+        app.state.new_instance = new_instance::State::default();
+        app.state.new_instance.name_input = String::from("Survival");
+        routes::error::show(&mut app, failure);
+        handle_keys(&[Key::Char('r'), Key::Enter], &mut app);
+        assert!(matches!(top(&app), Route::Error));
+        assert!(io_rx.try_recv().is_err());
+
+        app.state.new_instance.name_input = String::from("Creative");
+        handle(Key::Char('r'), &mut app);
+        assert!(matches!(top(&app), Route::NewInstance));
+        assert_eq!(io_rx.try_recv(), Ok(IoEvent::NewInstance));
+    }
+
+    #[test]
+    fn test_cancel_install() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        app.push_route(Route::NewInstance);
+        app.state.new_instance.inner = new_instance::InnerState::Install;
+        let cancel = app.state.new_instance.confirm_cancel.cancel.clone();
+
+        // Esc asks first, taking it back or saying no keeps going
+        handle(Key::Esc, &mut app);
+        handle(Key::Esc, &mut app);
+        handle_keys(&[Key::Esc, Key::Enter], &mut app);
+        handle_keys(&[Key::Esc, Key::Char('n')], &mut app);
+        assert!(matches!(top(&app), Route::NewInstance));
+        assert!(!cancel.is_cancelled());
+
+        // The install closes the dialog once it stopped
+        handle_keys(&[Key::Esc, Key::Left, Key::Enter], &mut app);
+        assert!(cancel.is_cancelled());
+        handle(Key::Esc, &mut app);
+        assert!(matches!(top(&app), Route::NewInstance));
+    }
+
+    #[test]
+    fn test_background_job() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        app.push_route(Route::NewInstance);
+        app.state.new_instance.inner = new_instance::InnerState::Install;
+
+        // Nothing to leave running yet
+        handle(Key::Ctrl('b'), &mut app);
+        assert!(matches!(top(&app), Route::NewInstance));
+
+        let id = app.jobs.add(
+            String::from("Create instance Creative"),
+            Some(Route::NewInstance),
+        );
+        handle(Key::Ctrl('b'), &mut app);
+        assert!(matches!(top(&app), Route::Home));
+        assert!(!app.jobs.is_shown(id));
+
+        handle(Key::Ctrl('t'), &mut app);
+        assert!(matches!(top(&app), Route::Jobs));
+        assert_eq!(app.jobs.running(), 1);
+    }
+
+    #[test]
+    fn test_remove_route() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        app.push_route(Route::NewInstance);
+        app.push_route(Route::Settings);
+
+        // A job finishing under a route opened meanwhile only closes its own
+        assert!(app.remove_route(&Route::NewInstance).is_some());
+        assert!(matches!(top(&app), Route::Settings));
+        assert!(app.remove_route(&Route::NewInstance).is_none());
+        handle(Key::Esc, &mut app);
+        assert!(matches!(top(&app), Route::Home));
+        assert!(app.remove_route(&Route::Home).is_none());
+        assert!(!app.should_quit);
+    }
+
+    #[test]
+    fn test_groups() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        app.instances.inner.get_mut("Survival").unwrap().group = Some(String::from("Modded"));
+
+        // Vanilla is ungrouped and comes first, so the second row is the heading of Modded
+        handle_keys(&[Key::Down, Key::Enter], &mut app);
+        assert!(matches!(top(&app), Route::Home));
+        handle_keys(&[Key::Down, Key::Enter], &mut app);
+        assert_eq!(
+            app.state.instance_menu.instance.as_ref().unwrap().name,
+            "Vanilla"
+        );
+
+        // Expanded again, Survival follows its heading
+        handle_keys(
+            &[Key::Esc, Key::Down, Key::Enter, Key::Down, Key::Enter],
+            &mut app,
+        );
+        assert_eq!(
+            app.state.instance_menu.instance.as_ref().unwrap().name,
+            "Survival"
+        );
+    }
+
+    #[test]
+    fn test_sort() {
+        let temp = fixture::TempDir::new();
+        let (mut app, io_rx) = App::fake(temp.path());
+        app.instances
+            .inner
+            .get_mut("Vanilla")
+            .unwrap()
+            .sessions
+            .push(crate::instance::Session {
+                started: chrono::Utc::now(),
+                ended: None,
+            });
+
+        // Played instances come first
+        handle_keys(&[Key::Char('s'), Key::Enter], &mut app);
+        assert_eq!(
+            app.settings.instance_sort,
+            crate::settings::InstanceSort::LastPlayed
+        );
+        assert!(matches!(io_rx.try_recv(), Ok(IoEvent::SaveSettings)));
+        assert_eq!(
+            app.state.instance_menu.instance.as_ref().unwrap().name,
+            "Vanilla"
+        );
+
+        // The newest version comes first
+        app.instances
+            .inner
+            .get_mut("Vanilla")
+            .unwrap()
+            .sessions
+            .clear();
+        handle_keys(&[Key::Esc, Key::Char('s'), Key::Enter], &mut app);
+        assert_eq!(
+            app.settings.instance_sort,
+            crate::settings::InstanceSort::MinecraftVersion
+        );
+        assert_eq!(
+            app.state.instance_menu.instance.as_ref().unwrap().name,
+            "Vanilla"
+        );
+    }
+
+    #[test]
+    fn test_cancel_setting() {
+        let temp = fixture::TempDir::new();
+        let (mut app, io_rx) = App::fake(temp.path());
+
+        // Esc while typing only cancels the edit, so the next visit is not left editing
+        handle_keys(&[Key::Ctrl('s'), Key::Down, Key::Enter, Key::Esc], &mut app);
+        assert!(matches!(top(&app), Route::Settings));
+        handle_keys(&[Key::Esc, Key::Ctrl('s'), Key::Esc], &mut app);
+        assert!(matches!(top(&app), Route::Home));
+        assert!(io_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_memory_min() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        let type_keys = |text: &str| text.chars().map(Key::Char).collect::<Vec<_>>();
+
+        // Without a maximum of its own the instance launches with the default of 2048 MiB
+        let instance = app.instances.inner["Survival"].clone();
+        app.state.instance_settings = instance_settings::State::new(instance);
+        app.push_route(Route::InstanceSettings);
+        handle_keys(&[Key::Down, Key::Enter], &mut app);
+        handle_keys(&type_keys("4096"), &mut app);
+        handle_keys(&[Key::Enter, Key::Esc], &mut app);
+        let instance = app.state.instance_settings.instance.as_ref().unwrap();
+        assert_eq!(instance.memory_min, None);
+        assert!(matches!(top(&app), Route::InstanceSettings));
+
+        // Nor can the default be lowered below an instance's minimum
+        app.instances.inner.get_mut("Survival").unwrap().memory_min = Some(1024);
+        handle_keys(&[Key::Esc, Key::Ctrl('s'), Key::Down, Key::Enter], &mut app);
+        handle_keys(&[Key::Backspace; 4], &mut app);
+        handle_keys(&type_keys("768"), &mut app);
+        handle(Key::Enter, &mut app);
+        assert_eq!(app.settings.memory, 2048);
+    }
+
+    #[test]
+    fn test_theme() {
+        let temp = fixture::TempDir::new();
+        let (mut app, io_rx) = App::fake(temp.path());
+
+        // The theme is the last setting
+        handle_keys(&[Key::Ctrl('s'), Key::Up, Key::Enter], &mut app);
+        assert_eq!(app.settings.theme, "light");
+        assert_eq!(app.theme, Theme::light());
+        assert!(matches!(io_rx.try_recv(), Ok(IoEvent::SaveSettings)));
+
+        handle_keys(&[Key::Enter, Key::Enter], &mut app);
+        assert_eq!(app.settings.theme, "dark");
+        assert_eq!(app.theme, Theme::dark());
+    }
+}
@@ -1,16 +1,46 @@
 use ::anyhow::{anyhow, Context};
 use array_tool::vec::Intersect;
+use chrono::{DateTime, Utc};
 use log::{debug, trace, warn};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::{
     collections::{hash_map, HashMap, HashSet},
     fs,
     io::{BufReader, BufWriter},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 use uuid::Uuid;
 
-use crate::mods::{ModFileInfo, ModInfo};
+use crate::{
+    minecraft::Launcher,
+    mods::{ModFileInfo, ModInfo},
+};
+
+/// The jvm arguments the minecraft launcher uses by default, minus the memory limit
+const DEFAULT_JAVA_ARGS: &str = "-XX:+UnlockExperimentalVMOptions -XX:+UseG1GC -XX:G1NewSizePercent=20 -XX:G1ReservePercent=20 -XX:MaxGCPauseMillis=50 -XX:G1HeapRegionSize=32M";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A launch of an instance
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Session {
+    pub started: DateTime<Utc>,
+    /// Only known if polyblock stayed open until the game closed
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ended: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        self.ended.map(|ended| ended - self.started)
+    }
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct Instance {
@@ -20,10 +50,25 @@ pub struct Instance {
     pub uuid: Uuid,
     pub mods: HashMap<PathBuf, ModInfo>,
     pub instances_directory: PathBuf,
+    /// Minimum memory in MiB
+    pub memory_min: Option<u64>,
+    /// Maximum memory in MiB - falls back to the default from the settings
+    pub memory_max: Option<u64>,
+    /// Extra jvm arguments - falls back to the launcher's defaults
+    pub java_args: Option<String>,
+    pub java_exec: Option<PathBuf>,
+    pub resolution: Option<Resolution>,
+    /// Game directory outside of the instances directory, for directories adopted in place
+    pub external_directory: Option<PathBuf>,
+    /// Heading the instance is listed under on the home screen
+    pub group: Option<String>,
+    pub tags: Vec<String>,
+    /// Every launch, oldest first
+    pub sessions: Vec<Session>,
 }
 
 impl Instance {
-    fn from_file_instance<P: Into<PathBuf>>(
+    pub(crate) fn from_file_instance<P: Into<PathBuf>>(
         file_instance: FileInstance,
         instances_directory: P,
     ) -> Self {
@@ -34,19 +79,49 @@ impl Instance {
             forge_name: file_instance.forge_name,
             uuid: file_instance.uuid,
             mods: file_instance.mods,
+            memory_min: file_instance.memory_min,
+            memory_max: file_instance.memory_max,
+            java_args: file_instance.java_args,
+            java_exec: file_instance.java_exec,
+            resolution: file_instance.resolution,
+            external_directory: file_instance.directory,
+            group: file_instance.group,
+            tags: file_instance.tags,
+            sessions: file_instance.sessions,
         }
     }
 
-    fn to_file_instance(self) -> FileInstance {
+    pub(crate) fn to_file_instance(self) -> FileInstance {
         FileInstance {
             name: self.name,
             version_id: self.version_id,
             forge_name: self.forge_name,
             uuid: self.uuid,
             mods: self.mods,
+            memory_min: self.memory_min,
+            memory_max: self.memory_max,
+            java_args: self.java_args,
+            java_exec: self.java_exec,
+            resolution: self.resolution,
+            directory: self.external_directory,
+            group: self.group,
+            tags: self.tags,
+            sessions: self.sessions,
         }
     }
 
+    pub fn last_played(&self) -> Option<DateTime<Utc>> {
+        self.sessions.last().map(|session| session.started)
+    }
+
+    /// Total time played, counting only the sessions whose end is known
+    pub fn playtime(&self) -> chrono::Duration {
+        self.sessions
+            .iter()
+            .filter_map(Session::duration)
+            .fold(chrono::Duration::zero(), |total, duration| total + duration)
+    }
+
     pub fn full_version_id(&self) -> String {
         match &self.forge_name {
             Some(forge_name) => format!("{}-{}", self.version_id, forge_name),
@@ -54,15 +129,43 @@ impl Instance {
         }
     }
 
+    /// The complete jvm arguments to hand to the launcher
+    pub fn full_java_args(&self, default_memory: u64) -> String {
+        let mut args = format!("-Xmx{}M", self.memory_max.unwrap_or(default_memory));
+        if let Some(memory_min) = self.memory_min {
+            args.push_str(&format!(" -Xms{}M", memory_min));
+        }
+        args.push(' ');
+        args.push_str(self.java_args.as_deref().unwrap_or(DEFAULT_JAVA_ARGS));
+        args
+    }
+
     pub fn directory(&self) -> PathBuf {
-        self.instances_directory
-            .join(self.uuid.to_hyphenated().to_string())
+        match &self.external_directory {
+            Some(directory) => directory.clone(),
+            None => self
+                .instances_directory
+                .join(self.uuid.to_hyphenated().to_string()),
+        }
     }
 
     pub fn mods_directory(&self) -> PathBuf {
         self.directory().join("mods")
     }
 
+    /// The log of the latest launch of the game
+    pub fn latest_log(&self) -> PathBuf {
+        self.directory().join("logs").join("latest.log")
+    }
+
+    pub fn saves_directory(&self) -> PathBuf {
+        self.directory().join("saves")
+    }
+
+    pub fn world_backups_directory(&self) -> PathBuf {
+        self.directory().join("backups")
+    }
+
     pub fn update_mod_file_info(&mut self) -> ::anyhow::Result<()> {
         // TODO: recurse into directories (but do not follow symlinks -- that might be bad)
         // TODO: Lots of room for optimization here i'd think
@@ -72,6 +175,15 @@ impl Instance {
         for entry in fs::read_dir(&mods_dir)? {
             let entry = entry?;
             let path = entry.path();
+            // Version subdirectories, configs and disabled mods are no mods of their own
+            let is_mod = matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("jar") | Some("zip")
+            );
+            if !is_mod || !path.is_file() {
+                trace!("Skipping {:?} in the mods directory.", path);
+                continue;
+            }
             let file_path = path.strip_prefix(&mods_dir)?.to_path_buf();
             mod_paths.insert(file_path);
         }
@@ -172,7 +284,7 @@ impl Instance {
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
-struct FileInstance {
+pub(crate) struct FileInstance {
     pub name: String,
     pub version_id: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -180,8 +292,67 @@ struct FileInstance {
     pub uuid: Uuid,
     #[serde(skip_serializing_if = "HashMap::is_empty", default)]
     pub mods: HashMap<PathBuf, ModInfo>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub memory_min: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub memory_max: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub java_args: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub java_exec: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub resolution: Option<Resolution>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub directory: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub group: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub sessions: Vec<Session>,
 }
 
+/// Version of the instances.json format, bumped whenever a migration is added
+const SCHEMA_VERSION: u64 = 1;
+
+/// Migrations from each schema version to the next, indexed by the version they migrate from
+const MIGRATIONS: [fn(Value) -> Value; 1] = [migrate_v0];
+
+/// Version 0 was the bare map of instance names to instances
+fn migrate_v0(value: Value) -> Value {
+    json!({ "version": 1, "instances": value })
+}
+
+/// Bring an instances.json of any known schema version up to date
+fn migrate(mut value: Value) -> ::anyhow::Result<Value> {
+    // An instance named "version" would be an object, never a number
+    let mut version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > SCHEMA_VERSION {
+        return Err(anyhow!(
+            "Instance data has version {} but only {} is supported. Was it written by a newer polyblock?",
+            version,
+            SCHEMA_VERSION
+        ));
+    }
+    while version < SCHEMA_VERSION {
+        debug!("Migrating instance data from version {}.", version);
+        value = MIGRATIONS[version as usize](value);
+        version += 1;
+    }
+    Ok(value)
+}
+
+#[derive(Deserialize, Serialize)]
+struct InstancesFile {
+    version: u64,
+    instances: HashMap<String, FileInstance>,
+}
+
+/// Number of rolling backups of instances.json to keep
+const BACKUPS: usize = 3;
+/// Minimum time between rolling the backups, so a burst of saves does not push out every backup
+const BACKUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
 #[derive(Debug, Clone, Default)]
 pub struct Instances {
     pub inner: HashMap<String, Instance>,
@@ -191,42 +362,287 @@ pub struct Instances {
 impl Instances {
     pub fn from_file<P: Into<PathBuf>>(path: P, instances_directory: P) -> ::anyhow::Result<Self> {
         let path = path.into();
-        match fs::File::open(&path) {
-            Ok(file) => {
-                let reader = BufReader::new(file);
-                let instances_directory = instances_directory.into();
-                let mut data: HashMap<String, FileInstance> =
-                    serde_json::from_reader(reader).context("Got malformed instance data.")?;
-                let data = data
-                    .drain()
-                    .map(|(k, v)| (k, Instance::from_file_instance(v, &instances_directory)))
-                    .collect();
-                Ok(Self {
-                    path: path,
-                    inner: data,
-                })
+        if !path.exists() {
+            debug!("No config file found.");
+            return Ok(Self {
+                path,
+                ..Default::default()
+            });
+        }
+        Ok(Self {
+            inner: read_file(&path, &instances_directory.into())?,
+            path,
+        })
+    }
+
+    /// Whether reading instances.json failed because it is corrupt, which is worth recovering from
+    ///
+    /// Data of a newer schema version and failures to read the file are not, as recovering would
+    /// replace data that is fine.
+    pub fn is_corrupt(e: &::anyhow::Error) -> bool {
+        matches!(e.downcast_ref::<serde_json::Error>(), Some(e) if !e.is_io())
+    }
+
+    /// Recover from a corrupt instances.json
+    ///
+    /// The newest readable backup is used if there is one, otherwise the instances are rebuilt
+    /// from their directories and launcher profiles. The corrupt file is moved aside.
+    pub fn recover<P: Into<PathBuf>>(
+        path: P,
+        instances_directory: P,
+        launcher: &Launcher,
+    ) -> ::anyhow::Result<Self> {
+        let path = path.into();
+        let instances_directory = instances_directory.into();
+        let mut instances = Self {
+            path,
+            ..Default::default()
+        };
+
+        let backup = (1..=BACKUPS).find_map(|n| {
+            let backup_path = instances.backup_path(n);
+            match read_file(&backup_path, &instances_directory) {
+                Ok(inner) => Some((backup_path, inner)),
+                Err(e) => {
+                    debug!("Backup {:?} is not usable: {:?}", backup_path, e);
+                    None
+                }
             }
-            _ => {
-                debug!("No config file found.");
-                Ok(Self {
-                    path: path,
-                    ..Default::default()
-                })
+        });
+        match backup {
+            Some((backup_path, inner)) => {
+                warn!("Recovered instances from {:?}.", backup_path);
+                instances.inner = inner;
+            }
+            None => {
+                warn!("No usable backup - rebuilding instances from their directories.");
+                instances.inner = rebuild(&instances_directory, launcher)?;
             }
         }
+
+        if instances.path.exists() {
+            let corrupt_path = instances.path.with_extension("json.corrupt");
+            warn!("Moving corrupt instance data to {:?}.", corrupt_path);
+            fs::rename(&instances.path, &corrupt_path)
+                .context("Failed to move corrupt instance data aside.")?;
+        }
+        instances.save()?;
+        Ok(instances)
+    }
+
+    fn backup_path(&self, n: usize) -> PathBuf {
+        self.path.with_extension(format!("json.bak.{}", n))
+    }
+
+    /// Shift the backups along and copy the current file into the first one
+    fn roll_backups(&self) -> ::anyhow::Result<()> {
+        if !self.path.is_file() {
+            return Ok(());
+        }
+        let newest_backup_age = fs::metadata(self.backup_path(1))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+        if matches!(newest_backup_age, Some(age) if age < BACKUP_INTERVAL) {
+            return Ok(());
+        }
+
+        for n in (1..BACKUPS).rev() {
+            let from = self.backup_path(n);
+            if from.exists() {
+                fs::rename(&from, self.backup_path(n + 1))?;
+            }
+        }
+        fs::copy(&self.path, self.backup_path(1))?;
+        Ok(())
     }
 
+    /// Atomically replace instances.json by writing a temporary file and renaming it over
     pub fn save(&self) -> ::anyhow::Result<()> {
-        let writer = BufWriter::new(
-            fs::File::create(&self.path).context("Could not create instance file.")?,
-        );
-        let data: HashMap<String, FileInstance> = self
-            .inner
-            .clone()
-            .drain()
-            .map(|(k, v)| (k, v.to_file_instance()))
-            .collect();
-        serde_json::to_writer(writer, &data).context("Tried to save malformed instance data.")?;
+        let data = InstancesFile {
+            version: SCHEMA_VERSION,
+            instances: self
+                .inner
+                .clone()
+                .drain()
+                .map(|(k, v)| (k, v.to_file_instance()))
+                .collect(),
+        };
+
+        let temp_path = self.path.with_extension("json.tmp");
+        {
+            let mut writer = BufWriter::new(
+                fs::File::create(&temp_path).context("Could not create instance file.")?,
+            );
+            serde_json::to_writer(&mut writer, &data)
+                .context("Tried to save malformed instance data.")?;
+            writer
+                .into_inner()
+                .context("Failed to write instance file.")?
+                .sync_all()
+                .context("Failed to write instance file.")?;
+        }
+
+        if let Err(e) = self.roll_backups() {
+            warn!("Failed to back up instance data: {:?}", e);
+        }
+
+        fs::rename(&temp_path, &self.path).context("Failed to replace instance file.")?;
         Ok(())
     }
 }
+
+fn read_file(
+    path: &Path,
+    instances_directory: &Path,
+) -> ::anyhow::Result<HashMap<String, Instance>> {
+    let reader = BufReader::new(fs::File::open(path)?);
+    let value: Value = serde_json::from_reader(reader).context("Got malformed instance data.")?;
+    let mut data: InstancesFile =
+        serde_json::from_value(migrate(value)?).context("Got malformed instance data.")?;
+    Ok(data
+        .instances
+        .drain()
+        .map(|(k, v)| (k, Instance::from_file_instance(v, instances_directory)))
+        .collect())
+}
+
+/// Rebuild instances from the directories in the instances directory and their launcher profiles
+fn rebuild(
+    instances_directory: &Path,
+    launcher: &Launcher,
+) -> ::anyhow::Result<HashMap<String, Instance>> {
+    let mut inner = HashMap::new();
+    if !instances_directory.is_dir() {
+        return Ok(inner);
+    }
+
+    for entry in fs::read_dir(instances_directory).context("Failed to read instances directory.")? {
+        let path = entry?.path();
+        let uuid = match path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| Uuid::parse_str(name).ok())
+        {
+            Some(uuid) if path.is_dir() => uuid,
+            _ => continue,
+        };
+        let profile = match launcher.profile_info(&uuid)? {
+            Some(profile) => profile,
+            None => {
+                warn!("No launcher profile for {:?} - cannot recover it.", path);
+                continue;
+            }
+        };
+
+        let mut name = profile.name.clone();
+        let mut n = 2;
+        while inner.contains_key(&name) {
+            name = format!("{} ({})", profile.name, n);
+            n += 1;
+        }
+
+        let mut instance = Instance {
+            name: name.clone(),
+            version_id: profile.version_id,
+            forge_name: profile.forge_name,
+            uuid,
+            instances_directory: instances_directory.to_path_buf(),
+            resolution: profile.resolution,
+            ..Default::default()
+        };
+        if let Err(e) = instance.update_mod_file_info() {
+            warn!("Failed to read mods of {}: {:?}", name, e);
+        }
+        debug!("Recovered {:?}.", instance);
+        inner.insert(name, instance);
+    }
+    Ok(inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+
+    #[test]
+    fn test_migrate_v0() {
+        let value = json!({
+            "version": {"name": "version", "version_id": "1.12.2", "uuid": "f1c1b5b2-7a4e-4a6e-9a36-7bdb6e3c9b1d"}
+        });
+        let data: InstancesFile = serde_json::from_value(migrate(value).unwrap()).unwrap();
+        assert_eq!(data.version, SCHEMA_VERSION);
+        assert_eq!(data.instances["version"].version_id, "1.12.2");
+    }
+
+    #[test]
+    fn test_migrate_current() {
+        let value = json!({"version": SCHEMA_VERSION, "instances": {}});
+        assert_eq!(migrate(value.clone()).unwrap(), value);
+        assert!(migrate(json!({"version": SCHEMA_VERSION + 1, "instances": {}})).is_err());
+    }
+
+    #[test]
+    fn test_is_corrupt() {
+        let temp = fixture::TempDir::new();
+        let path = temp.path().join("instances.json");
+        let from_file = || Instances::from_file(&path, &temp.path().to_path_buf()).unwrap_err();
+
+        fs::write(&path, "{\"version\": 1, \"instances\": ").unwrap();
+        assert!(Instances::is_corrupt(&from_file()));
+
+        fs::write(&path, json!({"version": SCHEMA_VERSION + 1}).to_string()).unwrap();
+        assert!(!Instances::is_corrupt(&from_file()));
+
+        // A directory cannot be read as a file
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        assert!(!Instances::is_corrupt(&from_file()));
+    }
+
+    #[test]
+    fn test_update_mod_file_info_skips_non_mods() {
+        let temp = fixture::TempDir::new();
+        let mods = temp.path().join("mods");
+        fs::create_dir_all(mods.join("1.12.2")).unwrap();
+        fs::write(mods.join("notes.txt"), "").unwrap();
+        fs::write(mods.join("old.jar.disabled"), "").unwrap();
+
+        let mut instance = Instance {
+            external_directory: Some(temp.path().to_path_buf()),
+            ..Default::default()
+        };
+        instance.update_mod_file_info().unwrap();
+        assert!(instance.mods.is_empty());
+    }
+
+    #[test]
+    fn test_playtime() {
+        let at = |hour, min| {
+            format!("2021-07-01T{:02}:{:02}:00Z", hour, min)
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+        };
+        let instance = Instance {
+            sessions: vec![
+                Session {
+                    started: at(10, 0),
+                    ended: Some(at(11, 30)),
+                },
+                // Launched with polyblock quitting, so the end is not known
+                Session {
+                    started: at(14, 0),
+                    ended: None,
+                },
+                Session {
+                    started: at(20, 0),
+                    ended: Some(at(20, 45)),
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(instance.playtime(), chrono::Duration::minutes(135));
+        assert_eq!(instance.last_played(), Some(at(20, 0)));
+        assert_eq!(Instance::default().playtime(), chrono::Duration::zero());
+    }
+}
@@ -1,38 +1,429 @@
-use ::anyhow::Context;
-use log::debug;
-use std::{fs, sync::Arc};
+use ::anyhow::{anyhow, Context};
+use chrono::{DateTime, Utc};
+use log::{debug, error, warn};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Child,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
-use crate::{forge, minecraft, routes, util, App, Instance};
+use crate::{
+    forge,
+    import::{self, ImportMode},
+    instance::Session,
+    jobs::{self, JobId, Lane, Status},
+    minecraft, packs, routes, util, worlds, App, Instance, Instances, Route,
+};
 
-#[derive(Debug)]
+/// How often to check whether the game closed
+const SESSION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long the game may take to start after the launcher closed
+const GAME_START_GRACE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum IoEvent {
     NewInstanceFetchMinecraftVersionManifest,
     NewInstanceFetchForgeVersionManifest,
     NewInstance,
     RemoveInstance,
     RenameInstance,
+    DuplicateInstance,
     PlayThenQuit,
+    Play,
     AddForgeFetchVersionManifests,
     AddForge,
     RemoveForge,
     ChangeVersion,
     ChangeVersionFetchMinecraftVersionManifest,
+    ReloadInstances,
+    RefreshMods(Uuid),
+    SaveSettings,
+    SaveInstanceSettings,
+    LoadWorlds,
+    BackupWorld,
+    DeleteWorld,
+    LoadWorldBackups,
+    RestoreWorld,
+    LoadSnapshots,
+    RollbackSnapshot,
+    LoadPacks,
+    TogglePack,
+    ImportPack,
+    ImportDirectory,
+    LoadModStore,
+    AddToModStore,
+    CollectModStoreGarbage,
+    VerifyModStore,
 }
 
 #[derive(Clone)]
-pub struct Io<'a> {
-    app: &'a Arc<RwLock<App>>,
+pub struct Io {
+    app: Arc<RwLock<App>>,
     pub client: reqwest::Client,
+    lanes: jobs::Lanes,
+    /// The job being handled, if the io event is listed as one
+    job: Option<JobId>,
+    /// The route the io event was sent from, which may wait on it
+    route: Option<Route>,
+    /// What the io event read from the routes when it was sent, for retrying it
+    inputs: String,
 }
 
-impl<'a> Io<'a> {
-    pub fn new(app: &'a Arc<RwLock<App>>, client: reqwest::Client) -> Self {
-        Io { app, client }
+impl Io {
+    pub fn new(app: &Arc<RwLock<App>>, client: reqwest::Client) -> Self {
+        Io {
+            app: Arc::clone(app),
+            client,
+            lanes: Default::default(),
+            job: None,
+            route: None,
+            inputs: String::new(),
+        }
+    }
+
+    /// Handle an io event in a task of its own, listing it as a job if it is a long one
+    pub async fn spawn(&self, io_event: IoEvent) {
+        let mut io = self.clone();
+        {
+            let mut app = self.app.write().await;
+            // Retrying while more failures are shown sends the event from the error dialog
+            io.route = app
+                .get_current_routes()
+                .into_iter()
+                .rev()
+                .find(|route| !matches!(route, Route::Error))
+                .cloned();
+            io.inputs = jobs::inputs(&io_event, &app);
+            io.job =
+                jobs::title(&io_event, &app).map(|title| app.jobs.add(title, io.route.clone()));
+        }
+        tokio::spawn(async move {
+            let result = io.handle_io_event(io_event.clone()).await;
+            io.finish(io_event, result).await;
+        });
+    }
+
+    async fn finish(&self, io_event: IoEvent, result: ::anyhow::Result<()>) {
+        let status = match result {
+            Ok(()) => Status::Finished,
+            Err(e) if util::is_cancelled(&e) => {
+                debug!("{:?} was cancelled.", io_event);
+                self.close_route(&mut *self.app.write().await);
+                Status::Cancelled
+            }
+            Err(e) => {
+                self.report_error(io_event, e).await;
+                Status::Failed
+            }
+        };
+        if let Some(id) = self.job {
+            let mut app = self.app.write().await;
+            let hidden = !app.jobs.is_shown(id);
+            app.jobs.set_status(id, status);
+            if hidden && status == Status::Finished {
+                let title = app.jobs.get(id).map(|job| job.title.clone());
+                app.status = title.map(|title| format!("{} - done.", title));
+            }
+        }
+    }
+
+    async fn set_status(&self, status: Status) {
+        if let Some(id) = self.job {
+            self.app.write().await.jobs.set_status(id, status);
+        }
+    }
+
+    /// Wait until it is our turn on the lanes, listed as queued meanwhile
+    async fn turn(&self, lanes: Vec<Lane>) -> jobs::Turn {
+        self.set_status(Status::Queued).await;
+        let turn = self.lanes.take(lanes).await;
+        self.set_status(Status::Running).await;
+        turn
+    }
+
+    /// List the progress of the job
+    async fn track(&self, pb: &util::Progress) {
+        if let Some(id) = self.job {
+            if let Some(job) = self.app.write().await.jobs.get_mut(id) {
+                job.progress = Some(pb.clone());
+            }
+        }
+    }
+
+    /// Close the route showing the progress, unless the user left it already
+    ///
+    /// Routes opened on top of it meanwhile, such as the failure of another job, are left alone.
+    fn close_route(&self, app: &mut App) {
+        if let Some(route) = &self.route {
+            if self.is_shown(app) {
+                app.remove_route(route);
+            }
+        }
+    }
+
+    /// Whether the route showing the progress is still there to be updated
+    fn is_shown(&self, app: &App) -> bool {
+        self.job.map(|id| app.jobs.is_shown(id)).unwrap_or(true)
+    }
+
+    /// An instance as it is now, after other jobs may have changed it
+    async fn instance(&self, uuid: Uuid) -> ::anyhow::Result<Instance> {
+        self.app
+            .read()
+            .await
+            .instances
+            .inner
+            .values()
+            .find(|instance| instance.uuid == uuid)
+            .cloned()
+            .ok_or_else(|| anyhow!("The instance was removed meanwhile."))
+    }
+
+    /// Launch an instance, recording when it was started
+    ///
+    /// Returns the launcher process along with the start of the session.
+    async fn play(&self, uuid: Uuid) -> ::anyhow::Result<(Child, DateTime<Utc>)> {
+        let instance = self.instance(uuid).await?;
+        let (java_version, java_runtimes, download_concurrency) = {
+            let app = self.app.read().await;
+            // Only known if we downloaded the version ourselves (i.e. forge instances),
+            // otherwise the launcher picks its own runtime
+            let java_version = app.launcher.java_version(&instance.version_id);
+            (
+                java_version,
+                app.java_runtimes.clone(),
+                app.settings.download_concurrency,
+            )
+        };
+
+        let runtime = match java_version {
+            Some(java_version) if instance.java_exec.is_none() => {
+                let pb = {
+                    let mut app = self.app.write().await;
+                    let pb = util::Progress::new();
+                    app.state.instance_menu.progress = Some(pb.clone());
+                    pb
+                };
+                java_runtimes
+                    .ensure(
+                        &pb,
+                        &self.client,
+                        &java_version,
+                        download_concurrency,
+                        &util::Cancel::new(),
+                    )
+                    .await
+                    .context("Failed to prepare java runtime")?
+            }
+            _ => None,
+        };
+
+        let mut app = self.app.write().await;
+        let launcher = app
+            .launcher
+            .launch_instance(&instance, &app.settings, runtime)?;
+
+        let started = Utc::now();
+        if let Some(instance) = app
+            .instances
+            .inner
+            .values_mut()
+            .find(|instance| instance.uuid == uuid)
+        {
+            instance.sessions.push(Session {
+                started,
+                ended: None,
+            });
+        }
+        app.instances.save()?;
+        Ok((launcher, started))
+    }
+
+    /// Wait for the game to close and record when it did
+    ///
+    /// The launcher may stay open or close on its own while the game runs, so the game's log is
+    /// watched instead. If no game writes it shortly after the launcher closed, none was started.
+    async fn finish_session(
+        &self,
+        uuid: Uuid,
+        mut launcher: Child,
+        started: DateTime<Utc>,
+    ) -> ::anyhow::Result<()> {
+        let log = self.instance(uuid).await?.latest_log();
+        let mut launcher_closed = None;
+        let ended = loop {
+            tokio::time::delay_for(SESSION_POLL_INTERVAL).await;
+            // A std child, so poll it rather than block the runtime
+            if launcher_closed.is_none()
+                && launcher
+                    .try_wait()
+                    .context("Failed to wait for the launcher")?
+                    .is_some()
+            {
+                launcher_closed = Some((Instant::now(), Utc::now()));
+            }
+            let game_started = util::game_log::modified(&log)
+                .map(DateTime::<Utc>::from)
+                .filter(|modified| *modified >= started);
+            if let Some(modified) = game_started {
+                if util::game_log::has_stopped(&log)
+                    .with_context(|| format!("Failed to read {:?}", log))?
+                {
+                    break modified;
+                }
+            } else if let Some((instant, closed)) = launcher_closed {
+                if instant.elapsed() > GAME_START_GRACE {
+                    break closed;
+                }
+            }
+        };
+
+        let mut app = self.app.write().await;
+        let instance = app
+            .instances
+            .inner
+            .values_mut()
+            .find(|instance| instance.uuid == uuid)
+            .ok_or_else(|| anyhow!("The instance was removed meanwhile."))?;
+        let name = instance.name.clone();
+        let session = instance
+            .sessions
+            .iter_mut()
+            .rev()
+            .find(|session| session.started == started)
+            .context("The session was removed meanwhile")?;
+        session.ended = Some(ended.max(started));
+        let playtime = session.duration().unwrap_or_else(chrono::Duration::zero);
+        app.instances.save()?;
+        app.status = Some(format!(
+            "Finished playing {} after {}.",
+            name,
+            util::format_playtime(playtime)
+        ));
+        Ok(())
+    }
+
+    /// Register an imported instance whose directory is in place, or staged in `partial`
+    ///
+    /// A staged copy is only moved into place once nothing else can fail but the launcher profile.
+    async fn register_import(
+        &self,
+        instance: Instance,
+        partial: Option<util::PartialDirectory>,
+    ) -> ::anyhow::Result<()> {
+        let directory = match &partial {
+            Some(partial) => partial.path().to_path_buf(),
+            None => instance.directory(),
+        };
+        self.store_mods(&instance, &directory).await?;
+
+        let mut app = self.app.write().await;
+        if app.instances.inner.contains_key(&instance.name) {
+            return Err(anyhow!(
+                "An instance named {} was created meanwhile.",
+                instance.name
+            ));
+        }
+        let staged = partial.is_some();
+        if let Some(partial) = partial {
+            partial.finish()?;
+        }
+        if let Err(e) = app.launcher.ensure_profile(&instance, &app.settings) {
+            if staged {
+                let _ = fs::remove_dir_all(instance.directory());
+            }
+            return Err(e);
+        }
+
+        app.instances.inner.insert(instance.name.clone(), instance);
+        app.instances.save()?;
+        self.close_route(&mut app);
+        Ok(())
+    }
+
+    /// Snapshot an instance before a risky operation, within the configured budget
+    async fn snapshot(&self, instance: &Instance, reason: &str) -> ::anyhow::Result<()> {
+        let (snapshots, budget) = {
+            let app = self.app.read().await;
+            (app.snapshots.clone(), app.settings.snapshot_budget)
+        };
+        snapshots
+            .create(&util::Progress::new(), instance, reason, budget)
+            .await
+            .context("Failed to snapshot instance")
+    }
+
+    /// Move the mod jars of an instance into the mod store, if it is enabled
+    ///
+    /// The directory is given apart from the instance, as it may not be in place yet.
+    async fn store_mods(&self, instance: &Instance, directory: &Path) -> ::anyhow::Result<()> {
+        let _turn = self.lanes.take(vec![Lane::ModStore]).await;
+        let mod_store = {
+            let app = self.app.read().await;
+            if !app.settings.mod_store {
+                return Ok(());
+            }
+            app.mod_store.clone()
+        };
+        let mods_directory = directory.join("mods");
+        if mods_directory.is_dir() {
+            let added = mod_store
+                .add_directory(&util::Progress::new(), &mods_directory)
+                .await
+                .context("Failed to store mods")?;
+            debug!(
+                "Added {} jars of {} to the mod store.",
+                added, instance.name
+            );
+        }
+        Ok(())
+    }
+
+    /// Show a failed io event to the user and append it to the error log
+    async fn report_error(&self, io_event: IoEvent, e: ::anyhow::Error) {
+        error!("{:?} failed: {:?}", io_event, e);
+        let mut app = self.app.write().await;
+        if let Err(log_error) = append_error_log(&app.paths.file.error_log, &io_event, &e) {
+            warn!("Failed to write error log: {:?}", log_error);
+        }
+        let mut failure = routes::error::Failure::new(io_event, &e);
+        failure.inputs = self.inputs.clone();
+        // Nothing is waiting on a job the user left
+        failure.background |= !self.is_shown(&app);
+        routes::error::show(&mut app, failure);
+    }
+
+    /// Lets the version picker grey out versions forge has no builds for, without going online
+    async fn load_cached_forge_version_manifest(&self) {
+        let (data_file_path, cache_policy) = {
+            let app = self.app.read().await;
+            if app.forge_version_manifest.is_some() {
+                return;
+            }
+            let cache_policy = util::CachePolicy {
+                offline: true,
+                ..app.cache_policy()
+            };
+            (app.paths.file.forge_versions_cache.clone(), cache_policy)
+        };
+        if !data_file_path.is_file() {
+            return;
+        }
+
+        let pb = util::Progress::new();
+        match forge::VersionManifest::fetch(&pb, &self.client, &data_file_path, cache_policy).await
+        {
+            Ok(manifest) => self.app.write().await.forge_version_manifest = Some(manifest),
+            Err(e) => warn!("Failed to read the cached forge version manifest: {:?}", e),
+        }
     }
 
-    pub async fn handle_io_event(&mut self, io_event: IoEvent) -> ::anyhow::Result<()> {
+    async fn handle_io_event(&self, io_event: IoEvent) -> ::anyhow::Result<()> {
         use IoEvent::*;
 
         match io_event {
@@ -46,12 +437,18 @@ impl<'a> Io<'a> {
                         (app.paths.file.minecraft_versions_cache.clone(), pb)
                     };
 
-                    let manifest =
-                        minecraft::VersionManifest::fetch(&pb, &self.client, &data_file_path)
-                            .await?;
+                    let cache_policy = { self.app.read().await.cache_policy() };
+                    let manifest = minecraft::VersionManifest::fetch(
+                        &pb,
+                        &self.client,
+                        &data_file_path,
+                        cache_policy,
+                    )
+                    .await?;
 
                     self.app.write().await.minecraft_version_manifest = Some(manifest);
                 }
+                self.load_cached_forge_version_manifest().await;
                 self.app.write().await.state.new_instance.inner =
                     routes::new_instance::InnerState::ChooseMinecraftVersion;
             }
@@ -65,9 +462,14 @@ impl<'a> Io<'a> {
                         (app.paths.file.minecraft_versions_cache.clone(), pb)
                     };
 
-                    let manifest =
-                        minecraft::VersionManifest::fetch(&pb, &self.client, &data_file_path)
-                            .await?;
+                    let cache_policy = { self.app.read().await.cache_policy() };
+                    let manifest = minecraft::VersionManifest::fetch(
+                        &pb,
+                        &self.client,
+                        &data_file_path,
+                        cache_policy,
+                    )
+                    .await?;
 
                     self.app.write().await.minecraft_version_manifest = Some(manifest);
                 }
@@ -98,8 +500,14 @@ impl<'a> Io<'a> {
                         (app.paths.file.forge_versions_cache.clone(), pb)
                     };
 
-                    let manifest =
-                        forge::VersionManifest::fetch(&pb, &self.client, &data_file_path).await?;
+                    let cache_policy = { self.app.read().await.cache_policy() };
+                    let manifest = forge::VersionManifest::fetch(
+                        &pb,
+                        &self.client,
+                        &data_file_path,
+                        cache_policy,
+                    )
+                    .await?;
                     self.app.write().await.forge_version_manifest = Some(manifest);
                 }
                 self.app.write().await.state.new_instance.inner =
@@ -116,9 +524,14 @@ impl<'a> Io<'a> {
                         (app.paths.file.minecraft_versions_cache.clone(), pb)
                     };
 
-                    let manifest =
-                        minecraft::VersionManifest::fetch(&pb, &self.client, &data_file_path)
-                            .await?;
+                    let cache_policy = { self.app.read().await.cache_policy() };
+                    let manifest = minecraft::VersionManifest::fetch(
+                        &pb,
+                        &self.client,
+                        &data_file_path,
+                        cache_policy,
+                    )
+                    .await?;
 
                     self.app.write().await.minecraft_version_manifest = Some(manifest);
                 }
@@ -132,8 +545,14 @@ impl<'a> Io<'a> {
                         (app.paths.file.forge_versions_cache.clone(), pb)
                     };
 
-                    let manifest =
-                        forge::VersionManifest::fetch(&pb, &self.client, &data_file_path).await?;
+                    let cache_policy = { self.app.read().await.cache_policy() };
+                    let manifest = forge::VersionManifest::fetch(
+                        &pb,
+                        &self.client,
+                        &data_file_path,
+                        cache_policy,
+                    )
+                    .await?;
                     self.app.write().await.forge_version_manifest = Some(manifest);
                 }
 
@@ -141,8 +560,8 @@ impl<'a> Io<'a> {
                     routes::add_forge::InnerState::ChooseForgeVersion;
             }
             NewInstance => {
-                let (name, instance) = {
-                    let (minecraft_version, forge_version, name, instances_directory) = {
+                let (name, instance, main_pb) = {
+                    let (minecraft_version, forge_version, name, instances_directory, cancel) = {
                         let app = self.app.read().await;
                         (
                             app.state
@@ -153,6 +572,7 @@ impl<'a> Io<'a> {
                             app.state.new_instance.chosen_forge_version.clone(),
                             app.state.new_instance.name_input.clone(),
                             app.paths.directory.instances.clone(),
+                            app.state.new_instance.confirm_cancel.cancel.clone(),
                         )
                     };
                     let main_pb = {
@@ -161,6 +581,7 @@ impl<'a> Io<'a> {
                         app.state.new_instance.progress_main = Some(pb.clone());
                         pb
                     };
+                    self.track(&main_pb).await;
 
                     let forge = if let Some(forge_version) = forge_version {
                         main_pb.set_length(10).await;
@@ -171,14 +592,23 @@ impl<'a> Io<'a> {
                             pb
                         };
 
-                        let (forge_version_manifests_cache, launcher, java_home_overwrite) = {
+                        let (
+                            forge_version_manifests_cache,
+                            launcher,
+                            java_runtimes,
+                            java_home,
+                            download_concurrency,
+                        ) = {
                             let app = self.app.read().await;
                             (
                                 app.paths.directory.forge_version_manifests_cache.clone(),
                                 app.launcher.clone(),
-                                app.java_home_overwrite.clone(),
+                                app.java_runtimes.clone(),
+                                app.java_home(),
+                                app.settings.download_concurrency,
                             )
                         };
+                        let _turn = self.turn(vec![Lane::Versions]).await;
                         forge::install(
                             &main_pb,
                             &sub_pb,
@@ -186,10 +616,17 @@ impl<'a> Io<'a> {
                             forge_version.clone(),
                             &forge_version_manifests_cache,
                             &launcher,
-                            java_home_overwrite,
+                            &self.client,
+                            &java_runtimes,
+                            java_home,
+                            None,
+                            download_concurrency,
+                            &cancel,
                         )
                         .await
                         .context("Failed to install forge")?;
+                        // Once installed the instance gets created in full
+                        cancel.check()?;
 
                         Some(forge_version)
                     } else {
@@ -207,28 +644,33 @@ impl<'a> Io<'a> {
                         ..Default::default()
                     };
 
-                    main_pb
-                        .inc_with_msg(1, "Creating instance directory.")
-                        .await;
-
-                    fs::create_dir_all(&instance.directory())
-                        .context("Failed to create instance directory!")?;
-
-                    (name, instance)
+                    (name, instance, main_pb)
                 };
                 let mut app = self.app.write().await;
-                let main_pb = &app.state.new_instance.progress_main.as_ref().unwrap();
+                if app.instances.inner.contains_key(&name) {
+                    return Err(anyhow!("An instance named {} was created meanwhile.", name));
+                }
+
+                // Only created once the name is known to be free, and removed again on failure
+                main_pb
+                    .inc_with_msg(1, "Creating instance directory.")
+                    .await;
+                fs::create_dir_all(instance.directory())
+                    .context("Failed to create instance directory!")?;
 
                 main_pb.inc_with_msg(1, "Ensuring launcher profile.").await;
 
-                app.launcher.ensure_profile(&instance)?;
+                if let Err(e) = app.launcher.ensure_profile(&instance, &app.settings) {
+                    let _ = fs::remove_dir_all(instance.directory());
+                    return Err(e);
+                }
 
                 main_pb.inc_with_msg(1, "Saving instance").await;
 
                 app.instances.inner.insert(name, instance);
                 app.instances.save()?;
 
-                app.pop_route();
+                self.close_route(&mut app);
             }
             AddForge => {
                 let (minecraft_version, forge_version) = {
@@ -267,13 +709,43 @@ impl<'a> Io<'a> {
                     app.state.add_forge.progress_sub = Some(sub_pb.clone());
                     (main_pb, sub_pb)
                 };
+                self.track(&main_pb).await;
+
+                let (uuid, cancel) = {
+                    let app = self.app.read().await;
+                    (
+                        app.state.add_forge.instance.as_ref().unwrap().uuid,
+                        app.state.add_forge.confirm_cancel.cancel.clone(),
+                    )
+                };
+                let _turn = self.turn(vec![Lane::Instance(uuid), Lane::Versions]).await;
+                let mut instance = self.instance(uuid).await?;
+                if instance.version_id != minecraft_version.id {
+                    return Err(anyhow!(
+                        "The version of {} was changed meanwhile.",
+                        instance.name
+                    ));
+                }
+                self.snapshot(
+                    &instance,
+                    &format!("Before installing forge {}", forge_version.name),
+                )
+                .await?;
 
-                let (forge_version_manifests_cache, launcher, java_home_overwrite) = {
+                let (
+                    forge_version_manifests_cache,
+                    launcher,
+                    java_runtimes,
+                    java_home,
+                    download_concurrency,
+                ) = {
                     let app = self.app.read().await;
                     (
                         app.paths.directory.forge_version_manifests_cache.clone(),
                         app.launcher.clone(),
-                        app.java_home_overwrite.clone(),
+                        app.java_runtimes.clone(),
+                        app.java_home(),
+                        app.settings.download_concurrency,
                     )
                 };
                 forge::install(
@@ -283,101 +755,744 @@ impl<'a> Io<'a> {
                     forge_version.clone(),
                     &forge_version_manifests_cache,
                     &launcher,
-                    java_home_overwrite,
+                    &self.client,
+                    &java_runtimes,
+                    java_home,
+                    instance.java_exec.clone(),
+                    download_concurrency,
+                    &cancel,
                 )
                 .await
                 .context("Failed to install forge")?;
+                cancel.check()?;
 
                 let mut app = self.app.write().await;
-                let main_pb = &app.state.add_forge.progress_main.as_ref().unwrap();
-
-                let mut instance = app.state.add_forge.instance.as_ref().unwrap().clone();
                 instance.forge_name = Some(forge_version.name);
 
                 main_pb.inc_with_msg(1, "Ensuring launcher profile.").await;
-                app.launcher.ensure_profile(&instance)?;
+                app.launcher.ensure_profile(&instance, &app.settings)?;
 
                 main_pb.inc_with_msg(1, "Saving instance").await;
                 app.instances.inner.insert(instance.name.clone(), instance);
                 app.instances.save()?;
 
-                app.pop_route();
+                self.close_route(&mut app);
             }
             RemoveInstance => {
-                let instance = {
+                let uuid = {
+                    let app = self.app.read().await;
+                    app.state.remove_instance.instance.as_ref().unwrap().uuid
+                };
+                let _turn = self.turn(vec![Lane::Instance(uuid)]).await;
+                let instance = self.instance(uuid).await?;
+                // Directories adopted in place are left alone, so there is nothing to lose
+                if instance.external_directory.is_none() {
+                    self.snapshot(&instance, "Before removing instance").await?;
+                }
+
+                {
                     let app = self.app.read().await;
-                    let instance = app.state.remove_instance.instance.clone().unwrap();
 
                     debug!("Removing launcher profile.");
                     app.launcher.remove_profile(&instance)?;
 
-                    debug!("Removing data folder.");
-                    let _ = fs::remove_dir_all(&instance.directory());
-                    instance
-                };
+                    if instance.external_directory.is_none() {
+                        debug!("Removing data folder.");
+                        let _ = fs::remove_dir_all(&instance.directory());
+                    }
+                }
 
                 let mut app = self.app.write().await;
                 debug!("Removing from config.");
                 app.instances.inner.remove(&instance.name);
                 app.instances.save()?;
-                app.pop_route();
+                self.close_route(&mut app);
             }
             RenameInstance => {
-                let mut app = self.app.write().await;
-                let mut instance = app.state.rename_instance.instance.clone().unwrap();
+                let (uuid, name) = {
+                    let app = self.app.read().await;
+                    let state = &app.state.rename_instance;
+                    (
+                        state.instance.as_ref().unwrap().uuid,
+                        state.name_input.clone(),
+                    )
+                };
+                let _turn = self.turn(vec![Lane::Instance(uuid)]).await;
+                let mut instance = self.instance(uuid).await?;
 
+                let mut app = self.app.write().await;
                 let old_name = instance.name;
-                instance.name = app.state.rename_instance.name_input.clone();
+                instance.name = name;
 
-                app.launcher.ensure_profile(&instance)?;
+                app.launcher.ensure_profile(&instance, &app.settings)?;
 
                 app.instances.inner.remove(&old_name);
                 app.instances.inner.insert(instance.name.clone(), instance);
                 app.instances.save()?;
-                app.pop_route();
+                self.close_route(&mut app);
+            }
+            DuplicateInstance => {
+                let (uuid, name, include) = {
+                    let app = self.app.read().await;
+                    let state = &app.state.duplicate_instance;
+                    (
+                        state.instance.as_ref().unwrap().uuid,
+                        state.name_input.clone(),
+                        state.include,
+                    )
+                };
+                let pb = {
+                    let mut app = self.app.write().await;
+                    let pb = util::Progress::new();
+                    pb.set_msg("Copying instance directory.").await;
+                    app.state.duplicate_instance.progress = Some(pb.clone());
+                    pb
+                };
+                self.track(&pb).await;
+                let turn = self.turn(vec![Lane::Instance(uuid)]).await;
+                let source = self.instance(uuid).await?;
+
+                // Duplicates always live in the instances directory
+                let instance = Instance {
+                    name,
+                    uuid: Uuid::new_v4(),
+                    external_directory: None,
+                    sessions: Vec::new(),
+                    ..source.clone()
+                };
+
+                let exclude: Vec<PathBuf> = routes::duplicate_instance::OPTIONAL_DIRECTORIES
+                    .iter()
+                    .zip(include.iter())
+                    .filter(|(_, include)| !**include)
+                    .map(|(directory, _)| source.directory().join(directory))
+                    .collect();
+
+                // Copied next to its place first, so that a failure leaves nothing behind
+                let partial = util::PartialDirectory::new(&instance.directory());
+                if source.directory().is_dir() {
+                    util::copy_directory_with_progress(
+                        &pb,
+                        source.directory().as_path(),
+                        partial.path(),
+                        &exclude,
+                    )
+                    .await
+                    .context("Failed to copy instance directory!")?;
+                } else {
+                    fs::create_dir_all(partial.path())
+                        .context("Failed to create instance directory!")?;
+                }
+                drop(turn);
+
+                self.store_mods(&instance, partial.path()).await?;
+
+                let mut app = self.app.write().await;
+                if app.instances.inner.contains_key(&instance.name) {
+                    return Err(anyhow!(
+                        "An instance named {} was created meanwhile.",
+                        instance.name
+                    ));
+                }
+                partial.finish()?;
+                if let Err(e) = app.launcher.ensure_profile(&instance, &app.settings) {
+                    let _ = fs::remove_dir_all(instance.directory());
+                    return Err(e);
+                }
+
+                app.instances.inner.insert(instance.name.clone(), instance);
+                app.instances.save()?;
+                self.close_route(&mut app);
             }
             RemoveForge => {
+                let uuid = {
+                    let app = self.app.read().await;
+                    app.state.instance_menu.instance.as_ref().unwrap().uuid
+                };
+                let _turn = self.turn(vec![Lane::Instance(uuid)]).await;
+                let mut instance = self.instance(uuid).await?;
+                self.snapshot(&instance, "Before removing forge").await?;
+
                 let mut app = self.app.write().await;
-                let mut instance = app.state.instance_menu.instance.clone().unwrap();
                 instance.forge_name = None;
 
-                app.launcher.ensure_profile(&instance)?;
+                app.launcher.ensure_profile(&instance, &app.settings)?;
 
                 app.instances.inner.insert(instance.name.clone(), instance);
                 app.instances.save()?;
-                app.pop_route();
+                self.close_route(&mut app);
             }
             ChangeVersion => {
+                let (uuid, version_id) = {
+                    let app = self.app.read().await;
+                    let state = &app.state.change_version;
+                    (
+                        state.instance.as_ref().unwrap().uuid,
+                        state.chosen_version.as_ref().unwrap().id.clone(),
+                    )
+                };
+                let _turn = self.turn(vec![Lane::Instance(uuid)]).await;
+                let mut instance = self.instance(uuid).await?;
+                self.snapshot(
+                    &instance,
+                    &format!(
+                        "Before changing version from {}",
+                        instance.full_version_id()
+                    ),
+                )
+                .await?;
+
                 let mut app = self.app.write().await;
-                let mut instance = app.state.change_version.instance.clone().unwrap();
-                instance.version_id = app
-                    .state
-                    .change_version
-                    .chosen_version
-                    .as_ref()
-                    .unwrap()
-                    .id
-                    .clone();
+                instance.version_id = version_id;
                 instance.forge_name = None;
 
-                app.launcher.ensure_profile(&instance)?;
+                app.launcher.ensure_profile(&instance, &app.settings)?;
 
                 app.instances.inner.insert(instance.name.clone(), instance);
                 app.instances.save()?;
-                app.pop_route();
+                self.close_route(&mut app);
             }
             PlayThenQuit => {
+                let uuid = {
+                    let app = self.app.read().await;
+                    app.state.instance_menu.instance.as_ref().unwrap().uuid
+                };
+                let _turn = self.turn(vec![Lane::Instance(uuid)]).await;
+                self.play(uuid).await?;
+                self.app.write().await.quit();
+            }
+            Play => {
+                let uuid = {
+                    let app = self.app.read().await;
+                    app.state.instance_menu.instance.as_ref().unwrap().uuid
+                };
+                // Only held while launching - the session is finished by a task of its own
+                let (launcher, started) = {
+                    let _turn = self.turn(vec![Lane::Instance(uuid)]).await;
+                    self.play(uuid).await?
+                };
+                let name = self.instance(uuid).await?.name;
                 {
+                    let mut app = self.app.write().await;
+                    self.close_route(&mut app);
+                    app.status = Some(format!(
+                        "Playing {} - the playtime is recorded when the game closes.",
+                        name
+                    ));
+                }
+                let io = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = io.finish_session(uuid, launcher, started).await {
+                        error!("Failed to record the session of {}: {:?}", name, e);
+                        io.app.write().await.status = Some(format!(
+                            "Failed to record the playtime of {}: {:#}",
+                            name, e
+                        ));
+                    }
+                });
+            }
+            SaveSettings => {
+                let app = self.app.read().await;
+                util::download::set_concurrency(app.settings.download_concurrency);
+                app.settings.save()?;
+            }
+            SaveInstanceSettings => {
+                let edited = {
+                    let app = self.app.read().await;
+                    app.state.instance_settings.instance.clone().unwrap()
+                };
+                let _turn = self.turn(vec![Lane::Instance(edited.uuid)]).await;
+                // Only the settings are ours, the rest may have been changed by other jobs
+                let current = self.instance(edited.uuid).await?;
+                let instance = Instance {
+                    memory_min: edited.memory_min,
+                    memory_max: edited.memory_max,
+                    java_args: edited.java_args,
+                    java_exec: edited.java_exec,
+                    resolution: edited.resolution,
+                    group: edited.group,
+                    tags: edited.tags,
+                    ..current
+                };
+
+                let mut app = self.app.write().await;
+
+                app.launcher.ensure_profile(&instance, &app.settings)?;
+
+                app.instances.inner.insert(instance.name.clone(), instance);
+                app.instances.save()?;
+            }
+            LoadWorlds => {
+                let instance = { self.app.read().await.state.worlds.instance.clone().unwrap() };
+                let worlds = worlds::list(
+                    &instance.saves_directory(),
+                    &instance.world_backups_directory(),
+                )?;
+                self.app.write().await.state.worlds.set_worlds(worlds);
+            }
+            BackupWorld => {
+                let (instance, world) = {
+                    let app = self.app.read().await;
+                    let state = &app.state.worlds;
+                    (
+                        state.instance.clone().unwrap(),
+                        state.selected_world().unwrap().clone(),
+                    )
+                };
+                let pb = {
+                    let mut app = self.app.write().await;
+                    let pb = util::Progress::new();
+                    pb.set_msg(format!("Backing up {}.", world.name)).await;
+                    app.state.worlds.progress = Some(pb.clone());
+                    pb
+                };
+                self.track(&pb).await;
+                let _turn = self.turn(vec![Lane::Instance(instance.uuid)]).await;
+
+                let path = worlds::backup(&pb, &world, &instance.world_backups_directory()).await?;
+                debug!("Backed up {} to {:?}.", world.name, path);
+
+                let mut app = self.app.write().await;
+                if self.is_shown(&app) {
+                    app.state.worlds.inner = routes::worlds::InnerState::List;
+                }
+            }
+            DeleteWorld => {
+                let (instance, world) = {
+                    let app = self.app.read().await;
+                    let state = &app.state.worlds;
+                    (
+                        state.instance.clone().unwrap(),
+                        state.selected_world().unwrap().clone(),
+                    )
+                };
+                worlds::delete(&world)?;
+
+                let worlds = worlds::list(
+                    &instance.saves_directory(),
+                    &instance.world_backups_directory(),
+                )?;
+                self.app.write().await.state.worlds.set_worlds(worlds);
+            }
+            LoadWorldBackups => {
+                let (instance, world) = {
                     let app = self.app.read().await;
-                    let instance = app.state.instance_menu.instance.clone().unwrap();
+                    let state = &app.state.world_backups;
+                    (
+                        state.instance.clone().unwrap(),
+                        state.world.clone().unwrap(),
+                    )
+                };
+                let backups =
+                    worlds::backups(&instance.world_backups_directory(), &world.directory_name())?;
+
+                let mut app = self.app.write().await;
+                app.state.world_backups.backups = backups;
+                app.state.world_backups.inner = routes::world_backups::InnerState::List;
+            }
+            RestoreWorld => {
+                let (instance, world, backup) = {
+                    let app = self.app.read().await;
+                    let state = &app.state.world_backups;
+                    (
+                        state.instance.clone().unwrap(),
+                        state.world.clone().unwrap(),
+                        state.selected_backup().unwrap().clone(),
+                    )
+                };
+                let pb = {
+                    let mut app = self.app.write().await;
+                    let pb = util::Progress::new();
+                    pb.set_msg(format!("Restoring {}.", world.name)).await;
+                    app.state.world_backups.progress = Some(pb.clone());
+                    pb
+                };
+                self.track(&pb).await;
+                let _turn = self.turn(vec![Lane::Instance(instance.uuid)]).await;
 
-                    app.launcher.launch_instance(&instance)?;
+                worlds::restore(&pb, &backup, &world.path).await?;
+
+                let worlds = worlds::list(
+                    &instance.saves_directory(),
+                    &instance.world_backups_directory(),
+                )?;
+                let mut app = self.app.write().await;
+                if self.is_shown(&app) {
+                    app.state.worlds.set_worlds(worlds);
+                }
+                self.close_route(&mut app);
+            }
+            LoadSnapshots => {
+                let (snapshots, instances_directory) = {
+                    let app = self.app.read().await;
+                    (app.snapshots.clone(), app.paths.directory.instances.clone())
+                };
+                let list = snapshots.list(&instances_directory)?;
+                self.app.write().await.state.snapshots.set_snapshots(list);
+            }
+            RollbackSnapshot => {
+                let (snapshots, snapshot, instances_directory) = {
+                    let app = self.app.read().await;
+                    (
+                        app.snapshots.clone(),
+                        app.state.snapshots.selected_snapshot().unwrap().clone(),
+                        app.paths.directory.instances.clone(),
+                    )
+                };
+                let instance = snapshot.instance.clone();
+                {
+                    let app = self.app.read().await;
+                    if let Some(other) = app.instances.inner.get(&instance.name) {
+                        if other.uuid != instance.uuid {
+                            return Err(anyhow!(
+                                "Another instance is already named {}. Rename it first.",
+                                instance.name
+                            ));
+                        }
+                    }
                 }
+                let pb = {
+                    let mut app = self.app.write().await;
+                    let pb = util::Progress::new();
+                    pb.set_msg(format!("Restoring {}.", instance.name)).await;
+                    app.state.snapshots.progress = Some(pb.clone());
+                    pb
+                };
+                self.track(&pb).await;
+                let _turn = self.turn(vec![Lane::Instance(instance.uuid)]).await;
+
+                let (current, budget) = {
+                    let app = self.app.read().await;
+                    let current = app
+                        .instances
+                        .inner
+                        .values()
+                        .find(|other| other.uuid == instance.uuid)
+                        .cloned();
+                    (current, app.settings.snapshot_budget)
+                };
+                snapshots
+                    .rollback(&pb, &snapshot, current.as_ref(), budget)
+                    .await?;
+
                 let mut app = self.app.write().await;
-                app.quit();
+                app.launcher.ensure_profile(&instance, &app.settings)?;
+
+                // The instance may have been renamed since the snapshot
+                app.instances
+                    .inner
+                    .retain(|_, other| other.uuid != instance.uuid);
+                app.instances.inner.insert(instance.name.clone(), instance);
+                app.instances.save()?;
+
+                if self.is_shown(&app) {
+                    let list = snapshots.list(&instances_directory)?;
+                    app.state.snapshots.set_snapshots(list);
+                }
+            }
+            LoadPacks => {
+                let (instance, kind) = {
+                    let app = self.app.read().await;
+                    let state = &app.state.packs;
+                    (state.instance.clone().unwrap(), state.kind)
+                };
+                let list = packs::list(&instance.directory(), kind)?;
+                self.app.write().await.state.packs.set_packs(list);
+            }
+            ImportDirectory => {
+                let (from, version, name, mode, instances_directory, launcher) = {
+                    let app = self.app.read().await;
+                    let state = &app.state.import_directory;
+                    (
+                        state.path().canonicalize()?,
+                        state.version().clone(),
+                        state.name_input.clone(),
+                        state.mode(),
+                        app.paths.directory.instances.clone(),
+                        app.launcher.clone(),
+                    )
+                };
+                let pb = {
+                    let mut app = self.app.write().await;
+                    let pb = util::Progress::new();
+                    app.state.import_directory.progress = Some(pb.clone());
+                    pb
+                };
+                self.track(&pb).await;
+
+                let mut instance = Instance {
+                    name,
+                    uuid: Uuid::new_v4(),
+                    version_id: version.version_id.clone(),
+                    forge_name: version.forge_name.clone(),
+                    instances_directory,
+                    ..Default::default()
+                };
+
+                // Everything that may fail is checked before the directory is touched
+                if self
+                    .app
+                    .read()
+                    .await
+                    .instances
+                    .inner
+                    .contains_key(&instance.name)
+                {
+                    return Err(anyhow!(
+                        "An instance named {} already exists.",
+                        instance.name
+                    ));
+                }
+                if from.join("mods").is_dir() {
+                    pb.set_msg("Reading mods.").await;
+                    let mut scanned = Instance {
+                        external_directory: Some(from.clone()),
+                        ..instance.clone()
+                    };
+                    scanned
+                        .update_mod_file_info()
+                        .context("Failed to read mods")?;
+                    instance.mods = scanned.mods;
+                }
+
+                if version.forge_name.is_some() {
+                    let _turn = self.turn(vec![Lane::Versions]).await;
+                    import::adopt_forge_version(&pb, &from, &launcher, &version.full_version_id())
+                        .await?;
+                }
+
+                // Copies are made next to their place, so that a failure leaves nothing behind
+                let mut partial = None;
+                let mut moved = false;
+                match mode {
+                    ImportMode::Copy => {
+                        pb.set_msg("Copying directory.").await;
+                        let exclude: Vec<PathBuf> = import::LAUNCHER_ENTRIES
+                            .iter()
+                            .map(|entry| from.join(entry))
+                            .collect();
+                        let copy = util::PartialDirectory::new(&instance.directory());
+                        util::copy_directory_with_progress(
+                            &pb,
+                            from.as_path(),
+                            copy.path(),
+                            &exclude,
+                        )
+                        .await
+                        .context("Failed to copy directory!")?;
+                        partial = Some(copy);
+                    }
+                    ImportMode::Move => {
+                        pb.set_msg("Moving directory.").await;
+                        fs::create_dir_all(&instance.instances_directory)
+                            .context("Failed to create instances directory!")?;
+                        if fs::rename(&from, instance.directory()).is_ok() {
+                            moved = true;
+                        } else {
+                            // Renaming fails across filesystems, so fall back to copying and only
+                            // remove the original once the instance is registered
+                            let copy = util::PartialDirectory::new(&instance.directory());
+                            util::copy_directory_with_progress(
+                                &pb,
+                                from.as_path(),
+                                copy.path(),
+                                &[],
+                            )
+                            .await
+                            .context("Failed to move directory!")?;
+                            partial = Some(copy);
+                        }
+                    }
+                    ImportMode::KeepInPlace => instance.external_directory = Some(from.clone()),
+                }
+
+                let name = instance.name.clone();
+                if let Err(e) = self.register_import(instance.clone(), partial).await {
+                    if moved {
+                        // The user's only copy must not be left where the ui can't reach it
+                        if let Err(rename_error) = fs::rename(instance.directory(), &from) {
+                            return Err(e.context(format!(
+                                "Failed to move the directory back, it is left at {:?}: {}",
+                                instance.directory(),
+                                rename_error
+                            )));
+                        }
+                    }
+                    return Err(e);
+                }
+
+                if mode == ImportMode::Move && !moved {
+                    if let Err(e) = fs::remove_dir_all(&from) {
+                        warn!("Failed to remove moved directory {:?}: {:?}", from, e);
+                        self.app.write().await.status = Some(format!(
+                            "Imported {}, but failed to remove the original directory: {}",
+                            name, e
+                        ));
+                    }
+                }
+            }
+            TogglePack => {
+                let (instance, pack, pack_format) = {
+                    let app = self.app.read().await;
+                    let state = &app.state.packs;
+                    (
+                        state.instance.clone().unwrap(),
+                        state.selected_pack().unwrap().clone(),
+                        state.pack_format,
+                    )
+                };
+                packs::set_enabled(&instance.directory(), &pack, !pack.enabled, pack_format)?;
+
+                let list = packs::list(&instance.directory(), pack.kind)?;
+                self.app.write().await.state.packs.set_packs(list);
+            }
+            ImportPack => {
+                let (instance, kind, from) = {
+                    let app = self.app.read().await;
+                    let state = &app.state.packs;
+                    (
+                        state.instance.clone().unwrap(),
+                        state.kind,
+                        PathBuf::from(&state.import_input),
+                    )
+                };
+                let path = packs::import(&instance.directory(), kind, &from)?;
+                debug!("Imported pack to {:?}.", path);
+
+                let list = packs::list(&instance.directory(), kind)?;
+                self.app.write().await.state.packs.set_packs(list);
+            }
+            ReloadInstances => {
+                // Jobs still working on an instance would have their changes replaced
+                let _turn = self.turn(vec![Lane::Instances]).await;
+                let mut app = self.app.write().await;
+                debug!("Instances file changed - reloading.");
+                match Instances::from_file(
+                    &app.paths.file.instances,
+                    &app.paths.directory.instances,
+                ) {
+                    Ok(instances) => app.instances = instances,
+                    Err(e) => warn!("Failed to reload instances: {:?}", e),
+                }
+            }
+            RefreshMods(uuid) => {
+                let _turn = self.turn(vec![Lane::Instance(uuid)]).await;
+                if let Ok(mut instance) = self.instance(uuid).await {
+                    debug!("Mods of {} changed - refreshing.", instance.name);
+                    if let Err(e) = self.store_mods(&instance, &instance.directory()).await {
+                        warn!("Failed to store mods of {}: {:?}", instance.name, e);
+                    }
+                    let mut app = self.app.write().await;
+                    match instance.update_mod_file_info() {
+                        Ok(_) => {
+                            app.instances.inner.insert(instance.name.clone(), instance);
+                            app.instances.save()?;
+                        }
+                        Err(e) => warn!("Failed to refresh mods of {}: {:?}", instance.name, e),
+                    }
+                }
+            }
+            LoadModStore => {
+                let mod_store = { self.app.read().await.mod_store.clone() };
+                let stats = mod_store.stats()?;
+                self.app.write().await.state.mod_store.set_stats(stats);
+            }
+            AddToModStore => {
+                let (mod_store, instances, pb) = {
+                    let mut app = self.app.write().await;
+                    let pb = util::Progress::new();
+                    app.state.mod_store.progress = Some(pb.clone());
+                    let instances: Vec<Instance> = app.instances.inner.values().cloned().collect();
+                    (app.mod_store.clone(), instances, pb)
+                };
+                self.track(&pb).await;
+                let _turn = self.turn(vec![Lane::ModStore]).await;
+                let mut added = 0;
+                for instance in &instances {
+                    let mods_directory = instance.mods_directory();
+                    if mods_directory.is_dir() {
+                        added += mod_store.add_directory(&pb, &mods_directory).await?;
+                    }
+                }
+
+                let stats = mod_store.stats()?;
+                let mut app = self.app.write().await;
+                let state = &mut app.state.mod_store;
+                state.set_stats(stats);
+                state.message = Some(format!(
+                    "Added {} jars from {} instances",
+                    added,
+                    instances.len()
+                ));
+            }
+            CollectModStoreGarbage => {
+                let (mod_store, pb) = {
+                    let mut app = self.app.write().await;
+                    let pb = util::Progress::new();
+                    app.state.mod_store.progress = Some(pb.clone());
+                    (app.mod_store.clone(), pb)
+                };
+                self.track(&pb).await;
+                let _turn = self.turn(vec![Lane::ModStore]).await;
+                let (removed, freed) = mod_store.collect_garbage(&pb).await?;
+
+                let stats = mod_store.stats()?;
+                let mut app = self.app.write().await;
+                let state = &mut app.state.mod_store;
+                state.set_stats(stats);
+                state.message = Some(format!(
+                    "Removed {} unreferenced jars, freeing {}",
+                    removed,
+                    util::format_size(freed)
+                ));
+            }
+            VerifyModStore => {
+                let (mod_store, pb) = {
+                    let mut app = self.app.write().await;
+                    let pb = util::Progress::new();
+                    app.state.mod_store.progress = Some(pb.clone());
+                    (app.mod_store.clone(), pb)
+                };
+                self.track(&pb).await;
+                let _turn = self.turn(vec![Lane::ModStore]).await;
+                let affected = mod_store.verify(&pb).await?;
+                for path in &affected {
+                    warn!("{:?} referred to a corrupt jar.", path);
+                }
+
+                let stats = mod_store.stats()?;
+                let mut app = self.app.write().await;
+                let state = &mut app.state.mod_store;
+                state.set_stats(stats);
+                state.message = Some(if affected.is_empty() {
+                    String::from("All jars are intact")
+                } else {
+                    format!(
+                        "Removed corrupt jars used by {} mod files - reinstall them",
+                        affected.len()
+                    )
+                });
             }
         }
 
+        self.app.read().await.sync_watcher();
+
         Ok(())
     }
 }
+
+fn append_error_log(path: &Path, io_event: &IoEvent, e: &::anyhow::Error) -> ::anyhow::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context("Failed to open error log.")?;
+    writeln!(
+        file,
+        "[{}] {:?} failed: {:?}\n",
+        chrono::Utc::now().to_rfc3339(),
+        io_event,
+        e
+    )?;
+    Ok(())
+}
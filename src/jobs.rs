@@ -0,0 +1,520 @@
+//! Io events run concurrently, the long ones listed as jobs the user can leave to run in the
+//! background
+//!
+//! Jobs working on the same thing take the same lanes, so they run one after another in the order
+//! they were queued instead of getting in each other's way.
+
+use chrono::{DateTime, Local};
+use std::{
+    collections::HashSet,
+    mem,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use uuid::Uuid;
+
+use crate::{util, App, IoEvent, Route};
+
+/// How often a queued job checks whether it is its turn
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How many ended jobs are remembered
+const HISTORY: usize = 50;
+
+pub type JobId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Queued,
+    Running,
+    Finished,
+    Failed,
+    Cancelled,
+}
+
+impl Status {
+    pub fn has_ended(self) -> bool {
+        !matches!(self, Status::Queued | Status::Running)
+    }
+}
+
+pub struct Job {
+    pub id: JobId,
+    pub title: String,
+    pub status: Status,
+    pub progress: Option<util::Progress>,
+    pub started: DateTime<Local>,
+    /// The route showing the job's progress, until the user leaves it
+    route: Option<Route>,
+}
+
+impl Job {
+    pub fn is_shown(&self) -> bool {
+        self.route.is_some()
+    }
+}
+
+#[derive(Default)]
+pub struct Jobs {
+    next_id: JobId,
+    /// Oldest first
+    list: Vec<Job>,
+}
+
+impl Jobs {
+    /// Start listing a job, shown by the route it was started from
+    pub fn add(&mut self, title: String, route: Option<Route>) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.list.push(Job {
+            id,
+            title,
+            status: Status::Running,
+            progress: None,
+            started: Local::now(),
+            route,
+        });
+        id
+    }
+
+    pub fn list(&self) -> &[Job] {
+        &self.list
+    }
+
+    pub fn get(&self, id: JobId) -> Option<&Job> {
+        self.list.iter().find(|job| job.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: JobId) -> Option<&mut Job> {
+        self.list.iter_mut().find(|job| job.id == id)
+    }
+
+    pub fn set_status(&mut self, id: JobId, status: Status) {
+        if let Some(job) = self.get_mut(id) {
+            job.status = status;
+            if status.has_ended() {
+                job.route = None;
+            }
+        }
+        self.forget_old();
+    }
+
+    /// Whether the job's progress is still on screen
+    pub fn is_shown(&self, id: JobId) -> bool {
+        self.get(id).map(Job::is_shown).unwrap_or(false)
+    }
+
+    /// The job still running on a route
+    pub fn shown_on(&self, route: &Route) -> Option<&Job> {
+        self.list.iter().find(|job| {
+            !job.status.has_ended()
+                && job
+                    .route
+                    .as_ref()
+                    .map(|shown| mem::discriminant(shown) == mem::discriminant(route))
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Let the job running on a route go on without it, returning whether there was one
+    pub fn hide(&mut self, route: &Route) -> bool {
+        let id = match self.shown_on(route) {
+            Some(job) => job.id,
+            None => return false,
+        };
+        self.get_mut(id).unwrap().route = None;
+        true
+    }
+
+    pub fn running(&self) -> usize {
+        self.list
+            .iter()
+            .filter(|job| !job.status.has_ended())
+            .count()
+    }
+
+    pub fn clear_ended(&mut self) {
+        self.list.retain(|job| !job.status.has_ended());
+    }
+
+    fn forget_old(&mut self) {
+        let mut ended = self
+            .list
+            .iter()
+            .filter(|job| job.status.has_ended())
+            .count();
+        self.list.retain(|job| {
+            if ended > HISTORY && job.status.has_ended() {
+                ended -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+/// What an io event is listed as, if it is long enough to be a job
+pub fn title(io_event: &IoEvent, app: &App) -> Option<String> {
+    let state = &app.state;
+    let instance_name = |instance: &Option<crate::Instance>| {
+        instance
+            .as_ref()
+            .map(|instance| instance.name.clone())
+            .unwrap_or_default()
+    };
+    Some(match io_event {
+        IoEvent::NewInstance => format!("Create instance {}", state.new_instance.name_input),
+        IoEvent::AddForge => format!(
+            "Install {} into {}",
+            state
+                .add_forge
+                .chosen_forge_version
+                .as_ref()
+                .map(|version| version.name.as_str())
+                .unwrap_or("forge"),
+            instance_name(&state.add_forge.instance)
+        ),
+        IoEvent::Play => format!("Play {}", instance_name(&state.instance_menu.instance)),
+        IoEvent::RemoveForge => format!(
+            "Remove forge from {}",
+            instance_name(&state.instance_menu.instance)
+        ),
+        IoEvent::ChangeVersion => format!(
+            "Change version of {}",
+            instance_name(&state.change_version.instance)
+        ),
+        IoEvent::DuplicateInstance => format!(
+            "Duplicate {} as {}",
+            instance_name(&state.duplicate_instance.instance),
+            state.duplicate_instance.name_input
+        ),
+        IoEvent::ImportDirectory => format!("Import {}", state.import_directory.name_input),
+        IoEvent::BackupWorld => format!(
+            "Back up world {}",
+            state
+                .worlds
+                .selected_world()
+                .map(|world| world.name.as_str())
+                .unwrap_or_default()
+        ),
+        IoEvent::RestoreWorld => format!(
+            "Restore world {}",
+            state
+                .world_backups
+                .world
+                .as_ref()
+                .map(|world| world.name.as_str())
+                .unwrap_or_default()
+        ),
+        IoEvent::RollbackSnapshot => format!(
+            "Roll back {}",
+            state
+                .snapshots
+                .selected_snapshot()
+                .map(|snapshot| snapshot.instance.name.as_str())
+                .unwrap_or_default()
+        ),
+        IoEvent::AddToModStore => String::from("Add mods to the mod store"),
+        IoEvent::CollectModStoreGarbage => String::from("Clean up the mod store"),
+        IoEvent::VerifyModStore => String::from("Verify the mod store"),
+        _ => return None,
+    })
+}
+
+/// What the handler of an io event reads from the routes, so a retry can tell whether it would
+/// still act on the same things as the failed attempt
+pub fn inputs(io_event: &IoEvent, app: &App) -> String {
+    let state = &app.state;
+    let uuid = |instance: &Option<crate::Instance>| instance.as_ref().map(|instance| instance.uuid);
+    match io_event {
+        IoEvent::NewInstance => format!(
+            "{:?}",
+            (
+                state
+                    .new_instance
+                    .chosen_minecraft_version
+                    .as_ref()
+                    .map(|version| &version.id),
+                state
+                    .new_instance
+                    .chosen_forge_version
+                    .as_ref()
+                    .map(|version| &version.name),
+                &state.new_instance.name_input,
+            )
+        ),
+        IoEvent::AddForge => format!(
+            "{:?}",
+            (
+                uuid(&state.add_forge.instance),
+                state
+                    .add_forge
+                    .chosen_forge_version
+                    .as_ref()
+                    .map(|version| &version.name),
+            )
+        ),
+        IoEvent::RemoveInstance => format!("{:?}", uuid(&state.remove_instance.instance)),
+        IoEvent::RenameInstance => format!(
+            "{:?}",
+            (
+                uuid(&state.rename_instance.instance),
+                &state.rename_instance.name_input,
+            )
+        ),
+        IoEvent::DuplicateInstance => format!(
+            "{:?}",
+            (
+                uuid(&state.duplicate_instance.instance),
+                &state.duplicate_instance.name_input,
+                state.duplicate_instance.include,
+            )
+        ),
+        IoEvent::Play | IoEvent::PlayThenQuit | IoEvent::RemoveForge => {
+            format!("{:?}", uuid(&state.instance_menu.instance))
+        }
+        IoEvent::ChangeVersion => format!(
+            "{:?}",
+            (
+                uuid(&state.change_version.instance),
+                state
+                    .change_version
+                    .chosen_version
+                    .as_ref()
+                    .map(|version| &version.id),
+            )
+        ),
+        IoEvent::SaveInstanceSettings => format!("{:?}", uuid(&state.instance_settings.instance)),
+        IoEvent::LoadWorlds => format!("{:?}", uuid(&state.worlds.instance)),
+        IoEvent::BackupWorld | IoEvent::DeleteWorld => format!(
+            "{:?}",
+            (
+                uuid(&state.worlds.instance),
+                state.worlds.selected_world().map(|world| &world.path),
+            )
+        ),
+        IoEvent::LoadWorldBackups => format!(
+            "{:?}",
+            (
+                uuid(&state.world_backups.instance),
+                state.world_backups.world.as_ref().map(|world| &world.path),
+            )
+        ),
+        IoEvent::RestoreWorld => format!(
+            "{:?}",
+            (
+                uuid(&state.world_backups.instance),
+                state.world_backups.world.as_ref().map(|world| &world.path),
+                state
+                    .world_backups
+                    .selected_backup()
+                    .map(|backup| &backup.path),
+            )
+        ),
+        IoEvent::RollbackSnapshot => format!(
+            "{:?}",
+            state
+                .snapshots
+                .selected_snapshot()
+                .map(|snapshot| &snapshot.path)
+        ),
+        IoEvent::LoadPacks => format!("{:?}", (uuid(&state.packs.instance), state.packs.kind)),
+        IoEvent::TogglePack => format!(
+            "{:?}",
+            (
+                uuid(&state.packs.instance),
+                state.packs.selected_pack().map(|pack| &pack.path),
+            )
+        ),
+        IoEvent::ImportPack => format!(
+            "{:?}",
+            (
+                uuid(&state.packs.instance),
+                state.packs.kind,
+                &state.packs.import_input,
+            )
+        ),
+        IoEvent::ImportDirectory => format!(
+            "{:?}",
+            (
+                &state.import_directory.path_input,
+                state.import_directory.chosen_version(),
+                &state.import_directory.name_input,
+                state.import_directory.mode(),
+            )
+        ),
+        _ => String::new(),
+    }
+}
+
+/// What a job works on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lane {
+    Instance(Uuid),
+    /// Every instance at once, for replacing them all
+    Instances,
+    /// The versions and libraries shared by all instances, written by forge installs
+    Versions,
+    ModStore,
+}
+
+impl Lane {
+    /// Whether two lanes cannot be taken at the same time
+    fn conflicts(&self, other: &Lane) -> bool {
+        match (self, other) {
+            (Lane::Instances, Lane::Instance(_)) | (Lane::Instance(_), Lane::Instances) => true,
+            _ => self == other,
+        }
+    }
+}
+
+#[derive(Default)]
+struct LanesState {
+    next_ticket: u64,
+    /// Oldest first
+    waiting: Vec<(u64, Vec<Lane>)>,
+    busy: HashSet<Lane>,
+}
+
+/// Hands out turns on lanes, first come first served
+#[derive(Clone, Default)]
+pub struct Lanes(Arc<Mutex<LanesState>>);
+
+impl Lanes {
+    /// Wait until the lanes are free and nobody queued on them before us is waiting for them
+    pub async fn take(&self, lanes: Vec<Lane>) -> Turn {
+        let ticket = self.queue(lanes);
+        loop {
+            if let Some(turn) = self.try_take(&ticket) {
+                return turn;
+            }
+            tokio::time::delay_for(POLL_INTERVAL).await;
+        }
+    }
+
+    fn queue(&self, lanes: Vec<Lane>) -> Ticket {
+        let mut state = self.0.lock().unwrap();
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.waiting.push((ticket, lanes));
+        Ticket {
+            lanes: self.clone(),
+            ticket,
+        }
+    }
+
+    fn try_take(&self, ticket: &Ticket) -> Option<Turn> {
+        let mut state = self.0.lock().unwrap();
+        let i = state
+            .waiting
+            .iter()
+            .position(|(waiting, _)| *waiting == ticket.ticket)?;
+        let lanes = &state.waiting[i].1;
+        let conflicts = |other: &Lane| lanes.iter().any(|lane| lane.conflicts(other));
+        let blocked = state.busy.iter().any(conflicts)
+            || state.waiting[..i]
+                .iter()
+                .any(|(_, ahead)| ahead.iter().any(conflicts));
+        if blocked {
+            return None;
+        }
+        let (_, lanes) = state.waiting.remove(i);
+        state.busy.extend(lanes.iter().copied());
+        Some(Turn {
+            lanes: self.clone(),
+            taken: lanes,
+        })
+    }
+}
+
+/// A place in the queue, given up when dropped before it was our turn
+struct Ticket {
+    lanes: Lanes,
+    ticket: u64,
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        let mut state = self.lanes.0.lock().unwrap();
+        state.waiting.retain(|(waiting, _)| *waiting != self.ticket);
+    }
+}
+
+/// The lanes a job took, freed when dropped
+pub struct Turn {
+    lanes: Lanes,
+    taken: Vec<Lane>,
+}
+
+impl Drop for Turn {
+    fn drop(&mut self) {
+        let mut state = self.lanes.0.lock().unwrap();
+        for lane in &self.taken {
+            state.busy.remove(lane);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lanes() {
+        let lanes = Lanes::default();
+        let a = Lane::Instance(Uuid::new_v4());
+        let b = Lane::Instance(Uuid::new_v4());
+
+        let first = lanes.queue(vec![a]);
+        let second = lanes.queue(vec![a, Lane::Versions]);
+        let third = lanes.queue(vec![Lane::Versions]);
+        let fourth = lanes.queue(vec![b]);
+
+        let first = lanes.try_take(&first).unwrap();
+        // Waiting for the first, and the third waits for the second even though its lane is free
+        assert!(lanes.try_take(&second).is_none());
+        assert!(lanes.try_take(&third).is_none());
+        let _fourth = lanes.try_take(&fourth).unwrap();
+
+        drop(first);
+        assert!(lanes.try_take(&third).is_none());
+        let second_turn = lanes.try_take(&second).unwrap();
+        assert!(lanes.try_take(&third).is_none());
+        drop(second_turn);
+        assert!(lanes.try_take(&third).is_some());
+    }
+
+    #[test]
+    fn test_all_instances() {
+        let lanes = Lanes::default();
+        let a = Lane::Instance(Uuid::new_v4());
+
+        let first = lanes.queue(vec![a]);
+        let all = lanes.queue(vec![Lane::Instances]);
+        let versions = lanes.queue(vec![Lane::Versions]);
+
+        let first = lanes.try_take(&first).unwrap();
+        assert!(lanes.try_take(&all).is_none());
+        let _versions = lanes.try_take(&versions).unwrap();
+        drop(first);
+        let _all = lanes.try_take(&all).unwrap();
+        assert!(lanes.try_take(&lanes.queue(vec![a])).is_none());
+    }
+
+    #[test]
+    fn test_hide() {
+        let mut jobs = Jobs::default();
+        let id = jobs.add(String::from("Create instance"), Some(Route::NewInstance));
+        assert!(jobs.is_shown(id));
+        assert!(!jobs.hide(&Route::AddForge));
+        assert!(jobs.hide(&Route::NewInstance));
+        assert!(!jobs.is_shown(id));
+        assert!(jobs.shown_on(&Route::NewInstance).is_none());
+
+        jobs.set_status(id, Status::Finished);
+        assert_eq!(jobs.running(), 0);
+        jobs.clear_ended();
+        assert!(jobs.list().is_empty());
+    }
+}
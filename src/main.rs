@@ -18,22 +18,32 @@ use tokio::sync::RwLock;
 use tui::{backend::CrosstermBackend, Terminal};
 
 mod app;
+mod endpoints;
 mod forge;
+mod import;
 mod input;
 mod instance;
 mod io;
+mod jobs;
 mod minecraft;
 mod mods;
+mod packs;
 mod paths;
 mod routes;
+mod settings;
+mod snapshots;
+mod theme;
 mod ui;
 mod util;
+mod watcher;
+mod worlds;
 
 use app::App;
 use instance::{Instance, Instances};
 use io::{Io, IoEvent};
 use paths::Paths;
 use routes::Route;
+use settings::Settings;
 use util::{Event, Events, Key};
 
 pub fn cleanup_terminal() {
@@ -72,9 +82,22 @@ pub struct Opt {
 
     /// Overwrite path to java home
     ///
-    /// Note that java is only required for installation of forge
-    #[structopt(long = "java", parse(from_os_str), env = "JAVA_HOME")]
+    /// Note that java is only required for installation of forge, and is only used when no
+    /// managed java runtime is available for this platform. Takes precedence over the java home
+    /// in the settings, while JAVA_HOME is only considered when neither is given.
+    #[structopt(long = "java", parse(from_os_str))]
     pub java_home: Option<PathBuf>,
+
+    /// Never use the network
+    ///
+    /// Manifests and install data come from the cache only, and actions that need the network are
+    /// disabled
+    #[structopt(long, env = "POLYBLOCK_OFFLINE")]
+    pub offline: bool,
+
+    /// Overwrite url of mojang's java runtime manifest
+    #[structopt(long, env = "POLYBLOCK_JAVA_RUNTIME_MANIFEST")]
+    pub java_runtime_manifest: Option<String>,
 }
 
 #[tokio::main]
@@ -106,23 +129,15 @@ async fn main() -> ::anyhow::Result<()> {
     let app = Arc::new(RwLock::new(App::new(&opt, io_tx)?));
     let cloned_app = Arc::clone(&app);
 
-    let reqwest_client = reqwest::Client::builder()
-        .gzip(true)
-        .user_agent(concat!(
-            env!("CARGO_PKG_NAME"),
-            "/",
-            env!("CARGO_PKG_VERSION")
-        ))
-        .build()?;
     thread::Builder::new().name("io".into()).spawn(move || {
-        let mut io = Io::new(&app, reqwest_client);
-        io_inner(io_rx, &mut io);
+        let io = Io::new(&app, util::download::client());
+        io_inner(io_rx, &io);
     })?;
 
     loop {
         {
             let mut app = cloned_app.write().await;
-            app.hide_cursor = true;
+            app.cursor = None;
 
             // Replicate start of terminal.draw so we can draw async
             terminal.autoresize()?;
@@ -152,10 +167,12 @@ async fn main() -> ::anyhow::Result<()> {
                 break;
             }
 
-            if app.hide_cursor {
-                terminal.hide_cursor()?;
-            } else {
-                terminal.show_cursor()?;
+            match app.cursor {
+                Some((x, y)) => {
+                    terminal.set_cursor(x, y)?;
+                    terminal.show_cursor()?;
+                }
+                None => terminal.hide_cursor()?,
             }
         }
     }
@@ -166,13 +183,8 @@ async fn main() -> ::anyhow::Result<()> {
 }
 
 #[tokio::main]
-async fn io_inner(io_rx: Receiver<IoEvent>, io: &mut Io) {
+async fn io_inner(io_rx: Receiver<IoEvent>, io: &Io) {
     while let Ok(io_event) = io_rx.recv() {
-        match io.handle_io_event(io_event).await {
-            Ok(_) => {}
-            Err(e) => {
-                panic!(format!("{:?}", e));
-            }
-        };
+        io.spawn(io_event).await;
     }
 }
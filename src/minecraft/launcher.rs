@@ -1,4 +1,4 @@
-use crate::Instance;
+use crate::{instance::Resolution, Instance, Settings};
 use anyhow::{anyhow, Context};
 use chrono::{DateTime, Utc};
 use log::debug;
@@ -7,11 +7,12 @@ use std::{
     collections::HashMap,
     fs,
     io::{BufReader, BufWriter},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{Child, Command},
 };
+use uuid::Uuid;
 
-use super::VersionManifestVersion;
+use super::{JavaVersion, VersionManifestVersion};
 use crate::util;
 
 #[cfg(target_os = "linux")]
@@ -32,6 +33,12 @@ pub struct LauncherProfile {
     r#type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     game_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    java_args: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    java_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolution: Option<Resolution>,
     #[serde(flatten)]
     other: Other,
 }
@@ -56,6 +63,43 @@ pub struct LauncherConfig {
     other: Other,
 }
 
+/// Split a version id into the minecraft version and forge name, using the inheritsFrom of the
+/// version json in the versions directory
+pub fn split_version_id(versions_directory: &Path, version_id: &str) -> (String, Option<String>) {
+    // Forge versions inherit from the minecraft version they were installed for
+    let inherits_from = fs::File::open(
+        versions_directory
+            .join(version_id)
+            .join(format!("{}.json", version_id)),
+    )
+    .ok()
+    .and_then(|file| serde_json::from_reader::<_, Other>(BufReader::new(file)).ok())
+    .and_then(|version_json| {
+        version_json
+            .get("inheritsFrom")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    });
+    match inherits_from {
+        Some(minecraft_version_id) => {
+            let forge_name = version_id
+                .trim_start_matches(&format!("{}-", minecraft_version_id))
+                .to_owned();
+            (minecraft_version_id, Some(forge_name))
+        }
+        None => (version_id.to_owned(), None),
+    }
+}
+
+/// What can be recovered about an instance from its launcher profile
+#[derive(Debug, Clone)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub version_id: String,
+    pub forge_name: Option<String>,
+    pub resolution: Option<Resolution>,
+}
+
 #[derive(Clone)]
 pub struct Launcher {
     work_directory: PathBuf,
@@ -67,13 +111,17 @@ pub struct Launcher {
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
 struct LauncherVersion {
     downloads: HashMap<String, LauncherVersionDownload>,
+    #[serde(default)]
+    java_version: JavaVersion,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 struct LauncherVersionDownload {
     sha1: String,
+    size: u64,
     url: String,
 }
 
@@ -164,11 +212,19 @@ impl Launcher {
             .context("Could not launch minecraft launcher. Is the launcher executable path set correctly?")?)
     }
 
-    pub fn launch_instance(&self, instance: &Instance) -> ::anyhow::Result<()> {
-        self.ensure_profile(instance)?;
-        let _ = self.launch()?;
-
-        Ok(())
+    /// Launch the launcher with the instance's profile up to date
+    ///
+    /// The runtime is used as the java executable unless the instance specifies its own.
+    pub fn launch_instance(
+        &self,
+        instance: &Instance,
+        settings: &Settings,
+        runtime: Option<PathBuf>,
+    ) -> ::anyhow::Result<Child> {
+        let mut instance = instance.clone();
+        instance.java_exec = instance.java_exec.or(runtime);
+        self.ensure_profile(&instance, settings)?;
+        self.launch()
     }
 
     fn read(&self) -> ::anyhow::Result<LauncherConfig> {
@@ -201,12 +257,18 @@ impl Launcher {
             .context("Failed to write launcher profiles.")?)
     }
 
-    pub fn ensure_profile(&self, instance: &Instance) -> ::anyhow::Result<()> {
+    pub fn ensure_profile(&self, instance: &Instance, settings: &Settings) -> ::anyhow::Result<()> {
         debug!("Ensuring launcher profile for {:?}", instance);
         let mut config = self.read()?;
 
         let uuid_key = instance.uuid.to_simple().to_string();
 
+        let java_args = instance.full_java_args(settings.memory);
+        let java_dir = instance
+            .java_exec
+            .as_ref()
+            .map(|java_exec| java_exec.to_str().unwrap().to_owned());
+
         config
             .profiles
             .entry(uuid_key)
@@ -214,6 +276,9 @@ impl Launcher {
                 profile.name = instance.name.clone();
                 profile.last_version_id = instance.full_version_id();
                 profile.last_used = Utc::now();
+                profile.java_args = Some(java_args.clone());
+                profile.java_dir = java_dir.clone();
+                profile.resolution = instance.resolution;
             })
             .or_insert(LauncherProfile {
                 name: instance.name.clone(),
@@ -222,6 +287,9 @@ impl Launcher {
                 r#type: "custom".to_string(),
                 created: Utc::now(),
                 game_dir: Some(instance.directory().to_str().unwrap().to_owned()),
+                java_args: Some(java_args),
+                java_dir,
+                resolution: instance.resolution,
                 other: Default::default(),
             });
 
@@ -241,20 +309,54 @@ impl Launcher {
         Ok(())
     }
 
+    pub fn profile_info(&self, uuid: &Uuid) -> ::anyhow::Result<Option<ProfileInfo>> {
+        let config = self.read()?;
+        let profile = match config.profiles.get(&uuid.to_simple().to_string()) {
+            Some(profile) => profile,
+            None => return Ok(None),
+        };
+
+        let (version_id, forge_name) =
+            split_version_id(&self.versions_directory, &profile.last_version_id);
+
+        Ok(Some(ProfileInfo {
+            name: profile.name.clone(),
+            version_id,
+            forge_name,
+            resolution: profile.resolution,
+        }))
+    }
+
+    /// Download the version json and jar, returning the java version the game needs
     pub async fn download_version(
         &self,
         pb: &util::Progress,
         version: &VersionManifestVersion,
-    ) -> ::anyhow::Result<()> {
-        let version_json = self.download_version_json(pb, version).await?;
-        self.download_version_jar(pb, version, version_json).await?;
-        Ok(())
+        cancel: &util::Cancel,
+    ) -> ::anyhow::Result<JavaVersion> {
+        let version_json = self.download_version_json(pb, version, cancel).await?;
+        let java_version = version_json.java_version.clone();
+        self.download_version_jar(pb, version, version_json, cancel)
+            .await?;
+        Ok(java_version)
+    }
+
+    /// The java version needed by an already downloaded minecraft version
+    pub fn java_version(&self, version_id: &str) -> Option<JavaVersion> {
+        let path = self
+            .versions_directory
+            .join(version_id)
+            .join(format!("{}.json", version_id));
+        let file = fs::File::open(path).ok()?;
+        let version_json: LauncherVersion = serde_json::from_reader(BufReader::new(file)).ok()?;
+        Some(version_json.java_version)
     }
 
     async fn download_version_json(
         &self,
         pb: &util::Progress,
         version: &VersionManifestVersion,
+        cancel: &util::Cancel,
     ) -> ::anyhow::Result<LauncherVersion> {
         let version_json_path = version.launcher_version_json(&self.versions_directory);
 
@@ -266,7 +368,7 @@ impl Launcher {
                 "Version json for version {} missing. Downloading...",
                 version.id
             );
-            util::download_file_with_progress(pb, &version.url, &version_json_path).await?;
+            util::download_file_with_progress(pb, &version.url, &version_json_path, cancel).await?;
         }
 
         let version_json_file = fs::File::open(&version_json_path)?;
@@ -279,6 +381,7 @@ impl Launcher {
         pb: &util::Progress,
         version: &VersionManifestVersion,
         version_json: LauncherVersion,
+        cancel: &util::Cancel,
     ) -> ::anyhow::Result<()> {
         let version_jar_path = version.launcher_version_jar(&self.versions_directory);
         let download = version_json.downloads.get("client").unwrap();
@@ -292,13 +395,24 @@ impl Launcher {
                 version.id
             );
 
-            util::download_file_with_progress(pb, &download.url, &version_jar_path).await?;
+            let expected = util::download::Expected {
+                size: Some(download.size),
+                sha1: Some(download.sha1.clone()),
+            };
+            util::download::download(
+                Some(pb),
+                &download.url,
+                &version_jar_path,
+                &expected,
+                cancel,
+            )
+            .await?;
         }
 
         pb.reset().await;
         pb.set_msg("Checking version jar.").await;
 
-        let hash = util::sha1_file_with_progress(pb, &version_jar_path).await?;
+        let hash = util::sha1_file_with_progress(pb, &version_jar_path, cancel).await?;
         if hash != download.sha1 {
             return Err(anyhow!(
                 "Version jar for version {} does not match its sha1.",
@@ -309,3 +423,80 @@ impl Launcher {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{minecraft::VersionManifestVersionType, util::fixture};
+
+    const VERSION_URL: &str = "https://launchermeta.mojang.com/v1/packages/fixture/1.17.1.json";
+    const JAR_URL: &str = "https://launcher.mojang.com/v1/objects/fixture/client.jar";
+
+    #[tokio::test]
+    async fn test_download_version() {
+        let server = fixture::server();
+        let temp = fixture::TempDir::new();
+        let pb = util::Progress::new();
+        let cancel = util::Cancel::new();
+        let launcher = Launcher::new(
+            temp.path().join("work"),
+            temp.path().join("cache"),
+            Some(temp.path().join("launcher")),
+        )
+        .unwrap();
+
+        let version = VersionManifestVersion {
+            id: String::from("1.17.1"),
+            r#type: VersionManifestVersionType::Release,
+            url: String::from(VERSION_URL),
+            time: Utc::now(),
+            release_time: Utc::now(),
+        };
+        let java_version = JavaVersion {
+            component: String::from("java-runtime-alpha"),
+            major_version: 16,
+        };
+
+        assert_eq!(launcher.java_version("1.17.1"), None);
+        assert_eq!(
+            launcher
+                .download_version(&pb, &version, &cancel)
+                .await
+                .unwrap(),
+            java_version
+        );
+        assert_eq!(launcher.java_version("1.17.1"), Some(java_version));
+        let jar_path = version.launcher_version_jar(&launcher.versions_directory);
+        assert_eq!(
+            fs::read(&jar_path).unwrap(),
+            fs::read(fixture::path(JAR_URL)).unwrap()
+        );
+        assert_eq!(server.requests(VERSION_URL).len(), 1);
+        assert_eq!(server.requests(JAR_URL).len(), 1);
+
+        // Already downloaded versions are only checked
+        launcher
+            .download_version(&pb, &version, &cancel)
+            .await
+            .unwrap();
+        assert_eq!(server.requests(VERSION_URL).len(), 1);
+        assert_eq!(server.requests(JAR_URL).len(), 1);
+
+        fs::write(&jar_path, "corrupt").unwrap();
+        assert!(launcher
+            .download_version(&pb, &version, &cancel)
+            .await
+            .is_err());
+
+        // A cancelled download leaves nothing behind
+        fs::remove_file(&jar_path).unwrap();
+        cancel.cancel();
+        let e = launcher
+            .download_version(&pb, &version, &cancel)
+            .await
+            .unwrap_err();
+        assert!(util::is_cancelled(&e));
+        assert!(!jar_path.exists());
+        assert_eq!(server.requests(JAR_URL).len(), 1);
+    }
+}
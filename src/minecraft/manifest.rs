@@ -11,9 +11,7 @@ use std::{
     path::PathBuf,
 };
 
-use crate::util;
-
-const URL: &'static str = "https://launchermeta.mojang.com/mc/game/version_manifest.json";
+use crate::{endpoints, util};
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -59,9 +57,8 @@ impl VersionManifest {
         pb: &util::Progress,
         client: &reqwest::Client,
         data_file_path: &PathBuf,
+        cache_policy: util::CachePolicy,
     ) -> ::anyhow::Result<Self> {
-        // TODO: If less than 15 min since cache was saved, don't even bother the server at all
-
         let cached_data = match File::open(data_file_path) {
             Ok(file) => {
                 debug!("Version manifest cache file found.");
@@ -75,7 +72,18 @@ impl VersionManifest {
             }
         };
 
-        let mut builder = client.get(URL);
+        if let Some(data) = &cached_data {
+            if cache_policy.is_fresh(data_file_path) {
+                debug!("Version manifest cache is fresh - not asking the server.");
+                return Ok(data.manifest.clone().sorted());
+            }
+        }
+        if cache_policy.offline {
+            return Err(cache_policy.not_cached("minecraft version manifest"));
+        }
+
+        let endpoints = endpoints::current();
+        let mut builder = client.get(&endpoints.rewrite(&endpoints.minecraft_version_manifest));
         if let Some(data) = cached_data.clone() {
             builder = builder.header(reqwest::header::IF_NONE_MATCH, data.etag)
         }
@@ -97,7 +105,7 @@ impl VersionManifest {
             .as_ref()
             .map(|etag| etag.to_str().unwrap().to_owned());
 
-        let mut manifest = match response.status() {
+        let manifest = match response.status() {
             reqwest::StatusCode::NOT_MODIFIED => {
                 debug!("Not modified - cache hit.");
                 cached_data.clone().unwrap().manifest
@@ -117,13 +125,12 @@ impl VersionManifest {
             }
         };
 
-        if cached_data.is_none()
-            || (etag.is_some() && etag.clone().unwrap() != cached_data.unwrap().etag)
+        // Rewritten even when not modified, since its age is what makes the cache fresh
         {
             debug!("Updating cache.");
             let data = Data {
                 manifest: manifest.clone(),
-                etag: etag.unwrap(),
+                etag: etag.or_else(|| cached_data.map(|data| data.etag)).unwrap(),
             };
 
             let writer = BufWriter::new(
@@ -133,10 +140,80 @@ impl VersionManifest {
             serde_json::to_writer(writer, &data)?;
         }
 
-        manifest
-            .versions
+        Ok(manifest.sorted())
+    }
+
+    /// Newest versions first
+    fn sorted(mut self) -> Self {
+        self.versions
             .sort_unstable_by_key(|version| Reverse(version.release_time));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+    use std::time::Duration;
+
+    const URL: &str = "https://launchermeta.mojang.com/mc/game/version_manifest.json";
 
-        Ok(manifest)
+    fn ids(manifest: &VersionManifest) -> Vec<&str> {
+        manifest.versions.iter().map(|v| v.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_fetch_etag_cache() {
+        let server = fixture::server();
+        let temp = fixture::TempDir::new();
+        let cache_path = temp.path().join("minecraft_versions.json");
+        let client = reqwest::Client::new();
+        let pb = util::Progress::new();
+        let stale = util::CachePolicy {
+            offline: false,
+            max_age: Duration::from_secs(0),
+        };
+
+        let offline = util::CachePolicy {
+            offline: true,
+            ..stale
+        };
+        assert!(VersionManifest::fetch(&pb, &client, &cache_path, offline)
+            .await
+            .is_err());
+        assert!(server.requests(URL).is_empty());
+
+        let manifest = VersionManifest::fetch(&pb, &client, &cache_path, stale)
+            .await
+            .unwrap();
+        assert_eq!(ids(&manifest), vec!["1.17.1", "21w03a", "1.16.5"]);
+        let requests = server.requests(URL);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].headers.get("if-none-match"), None);
+        assert_eq!(requests[0].status, 200);
+
+        // A stale cache is revalidated with its etag
+        let manifest = VersionManifest::fetch(&pb, &client, &cache_path, stale)
+            .await
+            .unwrap();
+        assert_eq!(ids(&manifest), vec!["1.17.1", "21w03a", "1.16.5"]);
+        let requests = server.requests(URL);
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].headers.contains_key("if-none-match"));
+        assert_eq!(requests[1].status, 304);
+
+        // A fresh one is used as is, as is any cache when offline
+        let fresh = util::CachePolicy {
+            max_age: Duration::from_secs(3600),
+            ..stale
+        };
+        VersionManifest::fetch(&pb, &client, &cache_path, fresh)
+            .await
+            .unwrap();
+        VersionManifest::fetch(&pb, &client, &cache_path, offline)
+            .await
+            .unwrap();
+        assert_eq!(server.requests(URL).len(), 2);
     }
 }
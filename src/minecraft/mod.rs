@@ -1,8 +1,10 @@
 mod launcher;
 mod manifest;
+mod runtime;
 
-pub use launcher::{default_launcher_exec, Launcher};
+pub use launcher::{default_launcher_exec, split_version_id, Launcher};
 pub use manifest::{VersionManifest, VersionManifestVersion, VersionManifestVersionType};
+pub use runtime::{JavaRuntimes, JavaVersion};
 
 pub fn version_ident(version: &VersionManifestVersion) -> &'static str {
     if version.id == "3D Shareware v1.34" {
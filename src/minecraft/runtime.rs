@@ -0,0 +1,324 @@
+use ::anyhow::{anyhow, Context};
+use futures::stream::StreamExt;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{endpoints, util};
+
+/// The name mojang uses for the current platform in the runtime manifest
+fn platform() -> Option<&'static str> {
+    if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some("linux")
+    } else if cfg!(all(target_os = "linux", target_arch = "x86")) {
+        Some("linux-i386")
+    } else if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        Some("windows-x64")
+    } else if cfg!(all(target_os = "windows", target_arch = "x86")) {
+        Some("windows-x86")
+    } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Some("mac-os-arm64")
+    } else if cfg!(target_os = "macos") {
+        Some("mac-os")
+    } else {
+        None
+    }
+}
+
+/// The javaVersion entry of a version json
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct JavaVersion {
+    pub component: String,
+    pub major_version: u64,
+}
+
+impl Default for JavaVersion {
+    /// Versions older than 1.17 have no javaVersion and run on the legacy java 8 runtime
+    fn default() -> Self {
+        Self {
+            component: String::from("jre-legacy"),
+            major_version: 8,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+struct Download {
+    sha1: String,
+    size: u64,
+    url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+struct RuntimeEntry {
+    manifest: Download,
+}
+
+/// platform -> component -> entries
+type RuntimesManifest = HashMap<String, HashMap<String, Vec<RuntimeEntry>>>;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum RuntimeFile {
+    File {
+        #[serde(default)]
+        executable: bool,
+        downloads: HashMap<String, Download>,
+    },
+    Directory,
+    Link {
+        target: String,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+struct RuntimeManifest {
+    files: HashMap<PathBuf, RuntimeFile>,
+}
+
+/// Java runtimes downloaded from mojang, one directory per component
+#[derive(Clone)]
+pub struct JavaRuntimes {
+    directory: PathBuf,
+    /// Only use runtimes that are already installed
+    offline: bool,
+}
+
+impl JavaRuntimes {
+    pub fn new<P: Into<PathBuf>>(directory: P, offline: bool) -> Self {
+        Self {
+            directory: directory.into(),
+            offline,
+        }
+    }
+
+    fn component_directory(&self, component: &str) -> PathBuf {
+        self.directory.join(component)
+    }
+
+    /// File containing the sha1 of the component manifest that was last fully installed
+    fn marker_path(&self, component: &str) -> PathBuf {
+        self.directory.join(format!("{}.sha1", component))
+    }
+
+    pub fn java_exec(&self, component: &str) -> PathBuf {
+        let home = self.component_directory(component);
+        let home = if cfg!(target_os = "macos") {
+            home.join("jre.bundle").join("Contents").join("Home")
+        } else {
+            home
+        };
+        home.join("bin")
+            .join(if cfg!(windows) { "java.exe" } else { "java" })
+    }
+
+    /// Make sure the runtime for the java version is downloaded and verified
+    ///
+    /// Returns the path to its java executable, or None if mojang provides no such runtime for
+    /// this platform
+    pub async fn ensure(
+        &self,
+        pb: &util::Progress,
+        client: &reqwest::Client,
+        java_version: &JavaVersion,
+        download_concurrency: usize,
+        cancel: &util::Cancel,
+    ) -> ::anyhow::Result<Option<PathBuf>> {
+        let component = &java_version.component;
+
+        pb.reset().await;
+        pb.set_msg(format!("Fetching java runtime {}.", component))
+            .await;
+
+        let platform = match platform() {
+            Some(platform) => platform,
+            None => return Ok(None),
+        };
+
+        // Whatever is installed will have to do
+        if self.offline {
+            let java_exec = self.java_exec(component);
+            if java_exec.is_file() {
+                return Ok(Some(java_exec));
+            }
+            warn!(
+                "Java runtime {} is not installed and we are offline.",
+                component
+            );
+            return Ok(None);
+        }
+
+        let endpoints = endpoints::current();
+        let manifest: RuntimesManifest = client
+            .get(&endpoints.rewrite(&endpoints.java_runtime_manifest))
+            .send()
+            .await
+            .context("Failed to get java runtime manifest.")?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Failed to decode java runtime manifest.")?;
+
+        let entry = match manifest
+            .get(platform)
+            .and_then(|components| components.get(component))
+            .and_then(|entries| entries.first())
+        {
+            Some(entry) => entry,
+            None => {
+                warn!("No java runtime {} for {}.", component, platform);
+                return Ok(None);
+            }
+        };
+
+        let marker_path = self.marker_path(component);
+        let java_exec = self.java_exec(component);
+        if java_exec.is_file()
+            && fs::read_to_string(&marker_path).ok().as_deref() == Some(&entry.manifest.sha1)
+        {
+            debug!("Java runtime {} is up to date.", component);
+            return Ok(Some(java_exec));
+        }
+
+        let runtime_manifest: RuntimeManifest = client
+            .get(&endpoints.rewrite(&entry.manifest.url))
+            .send()
+            .await
+            .with_context(|| format!("Failed to get manifest for java runtime {}.", component))?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| {
+                format!("Failed to decode manifest for java runtime {}.", component)
+            })?;
+
+        self.install(
+            pb,
+            component,
+            runtime_manifest,
+            download_concurrency,
+            cancel,
+        )
+        .await?;
+
+        fs::write(&marker_path, &entry.manifest.sha1)
+            .context("Failed to write java runtime marker.")?;
+
+        if !java_exec.is_file() {
+            return Err(anyhow!(
+                "Java runtime {} did not contain a java executable.",
+                component
+            ));
+        }
+
+        Ok(Some(java_exec))
+    }
+
+    async fn install(
+        &self,
+        pb: &util::Progress,
+        component: &str,
+        manifest: RuntimeManifest,
+        download_concurrency: usize,
+        cancel: &util::Cancel,
+    ) -> ::anyhow::Result<()> {
+        let directory = self.component_directory(component);
+        fs::create_dir_all(&directory).context("Failed to create java runtime directory!")?;
+
+        pb.set_msg(format!("Downloading java runtime {}.", component))
+            .await;
+
+        let mut files = Vec::new();
+        let mut links = Vec::new();
+        for (path, file) in manifest.files {
+            let path = directory.join(path);
+            match file {
+                RuntimeFile::Directory => {
+                    fs::create_dir_all(&path)?;
+                }
+                RuntimeFile::File {
+                    executable,
+                    mut downloads,
+                } => {
+                    let download = downloads
+                        .remove("raw")
+                        .ok_or_else(|| anyhow!("No raw download for {:?}", path))?;
+                    files.push((path, download, executable));
+                }
+                RuntimeFile::Link { target } => links.push((path, target)),
+            }
+        }
+
+        pb.set_length(files.iter().map(|(_, download, _)| download.size).sum())
+            .await;
+
+        let results: Vec<::anyhow::Result<()>> = futures::stream::iter(files.into_iter().map(
+            |(path, download, executable)| async move {
+                let r = install_file(&path, &download, executable, cancel).await;
+                pb.inc(download.size).await;
+                r
+            },
+        ))
+        .buffer_unordered(download_concurrency)
+        .collect::<Vec<_>>()
+        .await;
+        results.into_iter().collect::<::anyhow::Result<()>>()?;
+
+        for (path, target) in links {
+            create_link(&path, &target)?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn install_file(
+    path: &Path,
+    download: &Download,
+    executable: bool,
+    cancel: &util::Cancel,
+) -> ::anyhow::Result<()> {
+    if !path.is_file() || util::sha1_file(path)? != download.sha1 {
+        let expected = util::download::Expected {
+            size: Some(download.size),
+            sha1: Some(download.sha1.clone()),
+        };
+        util::download::download(None, &download.url, path, &expected, cancel)
+            .await
+            .with_context(|| format!("Failed to download java runtime file {:?}", path))?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if executable {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_link(path: &Path, target: &str) -> ::anyhow::Result<()> {
+    if fs::symlink_metadata(path).is_ok() {
+        fs::remove_file(path)?;
+    }
+    fs::create_dir_all(path.parent().unwrap())?;
+    std::os::unix::fs::symlink(target, path)
+        .with_context(|| format!("Failed to link {:?} to {}", path, target))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn create_link(path: &Path, target: &str) -> ::anyhow::Result<()> {
+    debug!("Skipping link {:?} -> {} on this platform.", path, target);
+    Ok(())
+}
@@ -1,6 +1,13 @@
 use ::anyhow::Context;
-use reqwest;
+use log::debug;
 use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use crate::{endpoints, util};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct AddonFile {
@@ -13,12 +20,26 @@ pub struct AddonFile {
 }
 
 impl AddonFile {
-    pub async fn fetch_files(project_id: u64) -> ::anyhow::Result<Vec<AddonFile>> {
-        let client = reqwest::Client::new();
-        let url = format!(
-            "https://addons-ecs.forgesvc.net/api/v2/addon/{}/files",
-            project_id
-        );
+    /// The files of a project, cached as <cache_directory>/<project id>.json
+    pub async fn fetch_files(
+        project_id: u64,
+        cache_directory: &Path,
+        cache_policy: util::CachePolicy,
+    ) -> ::anyhow::Result<Vec<AddonFile>> {
+        let cache_path = cache_directory.join(format!("{}.json", project_id));
+        if cache_policy.is_fresh(&cache_path) {
+            debug!("Addon files of {} are cached.", project_id);
+            let file = fs::File::open(&cache_path)?;
+            return serde_json::from_reader(BufReader::new(file))
+                .context("Got malformed addon files cache.");
+        }
+        if cache_policy.offline {
+            return Err(cache_policy.not_cached(&format!("file list of addon {}", project_id)));
+        }
+
+        let client = util::download::client();
+        let endpoints = endpoints::current();
+        let url = endpoints.rewrite(&endpoints.curse_addon_files(project_id));
         let response = client
             .get(&url)
             .send()
@@ -54,6 +75,12 @@ impl AddonFile {
             })
             .collect();
 
+        fs::create_dir_all(cache_directory).context("Failed to create addon cache directory!")?;
+        let writer = BufWriter::new(
+            fs::File::create(&cache_path).context("Failed to create addon files cache.")?,
+        );
+        serde_json::to_writer(writer, &files)?;
+
         Ok(files)
     }
 }
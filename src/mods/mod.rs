@@ -3,11 +3,34 @@ use std::path::Path;
 
 mod curse;
 mod file_info;
+mod store;
 
 pub use curse::AddonFile;
 pub use file_info::{ModFileInfo, ModFileInfoSource};
+pub use store::{ModStore, ModStoreStats};
 
-use crate::util;
+use crate::{
+    settings::{ModSource, Settings},
+    util,
+};
+
+/// The files of a mod project from the preferred source, leaving out hidden release types
+pub async fn fetch_files(
+    project_id: u64,
+    cache_directory: &Path,
+    cache_policy: util::CachePolicy,
+    settings: &Settings,
+) -> ::anyhow::Result<Vec<AddonFile>> {
+    let files = match settings.mod_source {
+        ModSource::Curse => {
+            AddonFile::fetch_files(project_id, cache_directory, cache_policy).await?
+        }
+    };
+    Ok(files
+        .into_iter()
+        .filter(|file| settings.release_types.allows(file.release_type))
+        .collect())
+}
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ModInfo {
@@ -92,3 +115,48 @@ impl ModInfo {
     //     }
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+    use std::{fs, time::Duration};
+
+    #[tokio::test]
+    async fn test_fetch_files() {
+        let temp = fixture::TempDir::new();
+        let files: Vec<_> = (1..=3)
+            .map(|release_type| AddonFile {
+                release_type,
+                file_name: format!("mod-{}.jar", release_type),
+                game_version: vec![String::from("1.16.5")],
+                download_url: String::new(),
+                project_id: 42,
+                id: release_type,
+            })
+            .collect();
+        fs::write(
+            temp.path().join("42.json"),
+            serde_json::to_string(&files).unwrap(),
+        )
+        .unwrap();
+        let cache_policy = util::CachePolicy {
+            offline: true,
+            max_age: Duration::from_secs(0),
+        };
+
+        let mut settings = Settings::default();
+        let ids = |files: Vec<AddonFile>| files.iter().map(|file| file.id).collect::<Vec<_>>();
+        let shown = fetch_files(42, temp.path(), cache_policy, &settings)
+            .await
+            .unwrap();
+        assert_eq!(ids(shown), vec![1, 2]);
+
+        settings.release_types.beta = false;
+        settings.release_types.alpha = true;
+        let shown = fetch_files(42, temp.path(), cache_policy, &settings)
+            .await
+            .unwrap();
+        assert_eq!(ids(shown), vec![1, 3]);
+    }
+}
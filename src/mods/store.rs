@@ -0,0 +1,275 @@
+use ::anyhow::Context;
+use log::{debug, warn};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+use crate::util;
+
+/// Which files hold each stored jar, keyed by sha1 - these are the store's reference counts
+type Index = BTreeMap<String, BTreeSet<PathBuf>>;
+
+const INDEX_NAME: &str = "refs.json";
+
+#[derive(Debug, Clone, Default)]
+pub struct ModStoreStats {
+    pub jars: usize,
+    pub size: u64,
+    pub references: usize,
+    /// Bytes that would be used by extra copies if the jars were not shared
+    pub saved: u64,
+}
+
+/// Content-addressed storage of mod jars shared between instances, stored as
+/// <directory>/<first two characters of sha1>/<sha1>.jar and hard linked into the mods directories
+#[derive(Clone)]
+pub struct ModStore {
+    directory: PathBuf,
+}
+
+impl ModStore {
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    fn jar_path(&self, sha1: &str) -> PathBuf {
+        self.directory
+            .join(&sha1[..2])
+            .join(format!("{}.jar", sha1))
+    }
+
+    fn temp_path(&self) -> PathBuf {
+        self.directory
+            .join(format!("{}.tmp", Uuid::new_v4().to_simple()))
+    }
+
+    fn read_index(&self) -> ::anyhow::Result<Index> {
+        match File::open(self.directory.join(INDEX_NAME)) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))
+                .context("Got malformed mod store index."),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Index::new()),
+            Err(e) => Err(e).context("Failed to read mod store index."),
+        }
+    }
+
+    fn write_index(&self, index: &Index) -> ::anyhow::Result<()> {
+        let path = self.directory.join(INDEX_NAME);
+        let temp_path = path.with_extension("json.tmp");
+        let writer =
+            BufWriter::new(File::create(&temp_path).context("Could not create mod store index.")?);
+        serde_json::to_writer_pretty(writer, index).context("Failed to write mod store index.")?;
+        fs::rename(&temp_path, &path).context("Failed to move mod store index into place!")?;
+        Ok(())
+    }
+
+    /// All stored jars as (sha1, path)
+    fn jars(&self) -> ::anyhow::Result<Vec<(String, PathBuf)>> {
+        let mut jars = Vec::new();
+        if !self.directory.is_dir() {
+            return Ok(jars);
+        }
+        for entry in fs::read_dir(&self.directory).context("Failed to read mod store.")? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(entry.path())? {
+                let path = entry?.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("jar") {
+                    continue;
+                }
+                if let Some(sha1) = path.file_stem().and_then(|s| s.to_str()) {
+                    jars.push((sha1.to_owned(), path.clone()));
+                }
+            }
+        }
+        Ok(jars)
+    }
+
+    /// Move the jars of a mods directory into the store, replacing them with hard links to the
+    /// stored copy
+    ///
+    /// When hard links are not possible (e.g. the store is on another filesystem) the jar is
+    /// copied into the store and the instance keeps its own copy. Returns the number of jars
+    /// that were newly added.
+    pub async fn add_directory(
+        &self,
+        pb: &util::Progress,
+        mods_directory: &Path,
+    ) -> ::anyhow::Result<usize> {
+        fs::create_dir_all(&self.directory).context("Failed to create mod store directory!")?;
+        let mut index = self.read_index()?;
+
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(mods_directory).context("Failed to read mods directory.")? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().and_then(|e| e.to_str()) == Some("jar") {
+                paths.push(path);
+            }
+        }
+        pb.set_length(paths.len() as u64).await;
+
+        let mut added = 0;
+        for path in paths {
+            pb.inc_with_msg(1, format!("Storing {}.", path.display()))
+                .await;
+
+            // Skip jars that were stored before and have not changed since, going by their
+            // content since different jars may well have the same size
+            let sha1 = util::sha1_file(&path)?;
+            let jar_path = self.jar_path(&sha1);
+            let already_stored = jar_path.is_file()
+                && matches!(index.get(&sha1), Some(references) if references.contains(&path));
+            if already_stored {
+                continue;
+            }
+
+            if jar_path.is_file() {
+                // Swap the instance's copy for a link to the stored one
+                let temp_path = self.temp_path();
+                if fs::hard_link(&jar_path, &temp_path).is_ok() {
+                    fs::rename(&temp_path, &path)
+                        .with_context(|| format!("Failed to link {:?}", path))?;
+                }
+            } else {
+                fs::create_dir_all(jar_path.parent().unwrap())?;
+                if fs::hard_link(&path, &jar_path).is_err() {
+                    debug!("Could not hard link {:?} - copying instead.", path);
+                    let temp_path = self.temp_path();
+                    fs::copy(&path, &temp_path)
+                        .with_context(|| format!("Failed to copy {:?}", path))?;
+                    fs::rename(&temp_path, &jar_path)?;
+                }
+                added += 1;
+            }
+
+            for references in index.values_mut() {
+                references.remove(&path);
+            }
+            index.entry(sha1).or_default().insert(path);
+        }
+
+        self.write_index(&index)?;
+        Ok(added)
+    }
+
+    pub fn stats(&self) -> ::anyhow::Result<ModStoreStats> {
+        let index = self.read_index()?;
+        let mut stats = ModStoreStats::default();
+        for (sha1, path) in self.jars()? {
+            let size = fs::metadata(&path)?.len();
+            let references = index.get(&sha1).map(BTreeSet::len).unwrap_or(0);
+            stats.jars += 1;
+            stats.size += size;
+            stats.references += references;
+            stats.saved += size * references.saturating_sub(1) as u64;
+        }
+        Ok(stats)
+    }
+
+    /// Drop references to files that are gone or have changed, then remove the jars nothing
+    /// refers to anymore. Returns the number of removed jars and the bytes freed.
+    pub async fn collect_garbage(&self, pb: &util::Progress) -> ::anyhow::Result<(usize, u64)> {
+        let mut index = self.read_index()?;
+
+        pb.set_length(index.values().map(BTreeSet::len).sum::<usize>() as u64)
+            .await;
+        for (sha1, references) in index.iter_mut() {
+            let mut live = BTreeSet::new();
+            for path in references.iter() {
+                pb.inc_with_msg(1, format!("Checking {}.", path.display()))
+                    .await;
+                if path.is_file() && util::sha1_file(path).ok().as_ref() == Some(sha1) {
+                    live.insert(path.clone());
+                }
+            }
+            *references = live;
+        }
+        index.retain(|_, references| !references.is_empty());
+
+        let mut removed = 0;
+        let mut freed = 0;
+        for (sha1, path) in self.jars()? {
+            if index.contains_key(&sha1) {
+                continue;
+            }
+            debug!("Removing unreferenced jar {:?}.", path);
+            freed += fs::metadata(&path)?.len();
+            fs::remove_file(&path).context("Failed to remove unreferenced jar.")?;
+            removed += 1;
+        }
+
+        if self.directory.is_dir() {
+            self.write_index(&index)?;
+        }
+        Ok((removed, freed))
+    }
+
+    /// Check every stored jar against its sha1, removing the corrupt ones from the store
+    ///
+    /// Returns the files that referred to corrupt jars - if they were hard linked they are
+    /// corrupt as well.
+    pub async fn verify(&self, pb: &util::Progress) -> ::anyhow::Result<Vec<PathBuf>> {
+        let mut index = self.read_index()?;
+        let jars = self.jars()?;
+
+        pb.set_length(jars.len() as u64).await;
+        let mut affected = Vec::new();
+        for (sha1, path) in jars {
+            pb.inc_with_msg(1, format!("Verifying {}.", sha1)).await;
+            if util::sha1_file(&path)? == sha1 {
+                continue;
+            }
+            warn!("Stored jar {:?} is corrupt - removing it.", path);
+            fs::remove_file(&path).context("Failed to remove corrupt jar.")?;
+            if let Some(references) = index.remove(&sha1) {
+                affected.extend(references);
+            }
+        }
+
+        if self.directory.is_dir() {
+            self.write_index(&index)?;
+        }
+        Ok(affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+
+    #[tokio::test]
+    async fn test_same_size() {
+        let temp = fixture::TempDir::new();
+        let store = ModStore::new(temp.path().join("store"));
+        let mods_directory = temp.path().join("mods");
+        let path = mods_directory.join("a.jar");
+        fs::create_dir_all(&mods_directory).unwrap();
+        let pb = util::Progress::new();
+
+        fs::write(&path, "first").unwrap();
+        assert_eq!(store.add_directory(&pb, &mods_directory).await.unwrap(), 1);
+        assert_eq!(store.add_directory(&pb, &mods_directory).await.unwrap(), 0);
+
+        // Replaced by another jar of the same size, which must be stored as well
+        fs::remove_file(&path).unwrap();
+        fs::write(&path, "other").unwrap();
+        assert_eq!(store.add_directory(&pb, &mods_directory).await.unwrap(), 1);
+        let index = store.read_index().unwrap();
+        assert!(index[&util::sha1_file(&path).unwrap()].contains(&path));
+        assert_eq!(
+            index
+                .values()
+                .filter(|references| references.contains(&path))
+                .count(),
+            1
+        );
+    }
+}
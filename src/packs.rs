@@ -0,0 +1,368 @@
+use ::anyhow::{anyhow, Context};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+/// The first release version of each resource pack_format as (minor, patch, pack_format)
+const PACK_FORMATS: [(u64, u64, u64); 16] = [
+    (6, 1, 1),
+    (9, 0, 2),
+    (11, 0, 3),
+    (13, 0, 4),
+    (15, 0, 5),
+    (16, 2, 6),
+    (17, 0, 7),
+    (18, 0, 8),
+    (19, 0, 9),
+    (19, 3, 12),
+    (19, 4, 13),
+    (20, 0, 15),
+    (20, 2, 18),
+    (20, 3, 22),
+    (20, 5, 32),
+    (21, 0, 34),
+];
+
+/// The newest release the table above is known to cover, later ones may use new pack_formats
+const LAST_KNOWN_VERSION: (u64, u64) = (21, 1);
+
+/// Resource packs in options.txt got a "file/" prefix in 1.13
+const FILE_PREFIX_PACK_FORMAT: u64 = 4;
+
+/// The resource pack_format a release version expects, None for snapshots, versions without
+/// resource packs and ones newer than the table
+pub fn pack_format_for_version(version_id: &str) -> Option<u64> {
+    let mut parts = version_id.split('.');
+    if parts.next()? != "1" {
+        return None;
+    }
+    let minor: u64 = parts.next()?.parse().ok()?;
+    let patch: u64 = match parts.next() {
+        Some(patch) => patch.parse().ok()?,
+        None => 0,
+    };
+    if parts.next().is_some() || (minor, patch) > LAST_KNOWN_VERSION {
+        return None;
+    }
+    PACK_FORMATS
+        .iter()
+        .rev()
+        .find(|(first_minor, first_patch, _)| (minor, patch) >= (*first_minor, *first_patch))
+        .map(|(_, _, pack_format)| *pack_format)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackKind {
+    Resource,
+    Shader,
+}
+
+impl PackKind {
+    pub fn directory_name(self) -> &'static str {
+        match self {
+            PackKind::Resource => "resourcepacks",
+            PackKind::Shader => "shaderpacks",
+        }
+    }
+}
+
+impl fmt::Display for PackKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackKind::Resource => write!(f, "Resource packs"),
+            PackKind::Shader => write!(f, "Shader packs"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct McMeta {
+    pack: McMetaPack,
+}
+
+#[derive(Deserialize)]
+struct McMetaPack {
+    pack_format: u64,
+    #[serde(default)]
+    description: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pack {
+    pub path: PathBuf,
+    pub kind: PackKind,
+    pub pack_format: Option<u64>,
+    pub description: Option<String>,
+    pub enabled: bool,
+}
+
+impl Pack {
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Whether the pack matches the instance's pack_format, None if either is unknown
+    pub fn is_compatible(&self, pack_format: Option<u64>) -> Option<bool> {
+        match (self.kind, self.pack_format, pack_format) {
+            (PackKind::Resource, Some(a), Some(b)) => Some(a == b),
+            _ => None,
+        }
+    }
+}
+
+/// Flatten a description that is either a plain string or a json text component
+fn description_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values.iter().map(description_text).collect(),
+        Value::Object(map) => {
+            let mut text = map.get("text").map(description_text).unwrap_or_default();
+            if let Some(extra) = map.get("extra") {
+                text.push_str(&description_text(extra));
+            }
+            text
+        }
+        _ => String::new(),
+    }
+}
+
+fn parse_mcmeta(s: &str) -> ::anyhow::Result<(u64, String)> {
+    // Some packs start with a byte order mark
+    let mcmeta: McMeta = serde_json::from_str(s.trim_start_matches('\u{feff}'))
+        .context("Got malformed pack.mcmeta.")?;
+    Ok((
+        mcmeta.pack.pack_format,
+        description_text(&mcmeta.pack.description),
+    ))
+}
+
+/// Read pack.mcmeta from either a zipped or an extracted pack
+fn read_mcmeta(path: &Path) -> ::anyhow::Result<(u64, String)> {
+    let mut s = String::new();
+    if path.is_dir() {
+        File::open(path.join("pack.mcmeta"))?.read_to_string(&mut s)?;
+    } else {
+        let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path)?))?;
+        archive
+            .by_name("pack.mcmeta")
+            .map_err(|_| anyhow!("No pack.mcmeta in {:?}", path))?
+            .read_to_string(&mut s)?;
+    }
+    parse_mcmeta(&s)
+}
+
+fn options_path(instance_directory: &Path, kind: PackKind) -> PathBuf {
+    instance_directory.join(match kind {
+        PackKind::Resource => "options.txt",
+        PackKind::Shader => "optionsshaders.txt",
+    })
+}
+
+/// Read key:value (options.txt) or key=value (optionsshaders.txt) lines
+fn read_options(path: &Path) -> ::anyhow::Result<Vec<String>> {
+    match fs::read_to_string(path) {
+        Ok(s) => Ok(s.lines().map(String::from).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+    }
+}
+
+fn get_option<'a>(lines: &'a [String], key: &str) -> Option<&'a str> {
+    lines.iter().find_map(|line| {
+        let rest = line.strip_prefix(key)?;
+        rest.strip_prefix(':').or_else(|| rest.strip_prefix('='))
+    })
+}
+
+fn set_option(lines: &mut Vec<String>, key: &str, separator: char, value: &str) {
+    let new_line = format!("{}{}{}", key, separator, value);
+    match lines
+        .iter_mut()
+        .find(|line| line.starts_with(&format!("{}{}", key, separator)))
+    {
+        Some(line) => *line = new_line,
+        None => lines.push(new_line),
+    }
+}
+
+fn get_list_option(lines: &[String], key: &str) -> Vec<String> {
+    get_option(lines, key)
+        .and_then(|value| serde_json::from_str(value).ok())
+        .unwrap_or_default()
+}
+
+/// The name the game refers to the pack by in options.txt
+fn option_name(pack: &Pack, pack_format: Option<u64>) -> String {
+    match pack_format {
+        Some(pack_format) if pack_format < FILE_PREFIX_PACK_FORMAT => pack.file_name(),
+        _ => format!("file/{}", pack.file_name()),
+    }
+}
+
+/// All packs of a kind in an instance, sorted by file name
+pub fn list(instance_directory: &Path, kind: PackKind) -> ::anyhow::Result<Vec<Pack>> {
+    let directory = instance_directory.join(kind.directory_name());
+    if !directory.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let options = read_options(&options_path(instance_directory, kind))?;
+    let enabled_resource_packs = get_list_option(&options, "resourcePacks");
+    let enabled_shader_pack = get_option(&options, "shaderPack");
+
+    let mut packs = Vec::new();
+    for entry in fs::read_dir(&directory).context("Failed to read pack directory.")? {
+        let path = entry?.path();
+        let is_zip = path.extension().and_then(|e| e.to_str()) == Some("zip");
+        if !path.is_dir() && !is_zip {
+            continue;
+        }
+        let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+
+        let mut pack = Pack {
+            path,
+            kind,
+            pack_format: None,
+            description: None,
+            enabled: false,
+        };
+        match kind {
+            PackKind::Resource => {
+                if let Ok((pack_format, description)) = read_mcmeta(&pack.path) {
+                    pack.pack_format = Some(pack_format);
+                    pack.description = Some(description);
+                }
+                pack.enabled = enabled_resource_packs
+                    .iter()
+                    .any(|name| name.trim_start_matches("file/") == file_name);
+            }
+            PackKind::Shader => pack.enabled = enabled_shader_pack == Some(file_name.as_str()),
+        }
+        packs.push(pack);
+    }
+    packs.sort_by_key(Pack::file_name);
+    Ok(packs)
+}
+
+/// Enable or disable a pack by editing options.txt (or optionsshaders.txt for shader packs)
+///
+/// pack_format is the one the instance's version expects.
+pub fn set_enabled(
+    instance_directory: &Path,
+    pack: &Pack,
+    enabled: bool,
+    pack_format: Option<u64>,
+) -> ::anyhow::Result<()> {
+    let path = options_path(instance_directory, pack.kind);
+    let mut options = read_options(&path)?;
+
+    match pack.kind {
+        PackKind::Resource => {
+            let name = option_name(pack, pack_format);
+            for key in &["resourcePacks", "incompatibleResourcePacks"] {
+                let mut list = get_list_option(&options, key);
+                list.retain(|n| n.trim_start_matches("file/") != pack.file_name());
+                // The game silently drops incompatible packs that are not acknowledged as such
+                let add = enabled
+                    && (*key == "resourcePacks" || pack.is_compatible(pack_format) == Some(false));
+                if add {
+                    list.push(name.clone());
+                }
+                set_option(&mut options, key, ':', &serde_json::to_string(&list)?);
+            }
+        }
+        PackKind::Shader => {
+            let value = if enabled {
+                pack.file_name()
+            } else {
+                String::from("OFF")
+            };
+            set_option(&mut options, "shaderPack", '=', &value);
+        }
+    }
+
+    let mut s = options.join("\n");
+    s.push('\n');
+    fs::write(&path, s).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(())
+}
+
+/// Copy a pack zip into the instance, returning the new path
+pub fn import(instance_directory: &Path, kind: PackKind, from: &Path) -> ::anyhow::Result<PathBuf> {
+    let file_name = from
+        .file_name()
+        .ok_or_else(|| anyhow!("{:?} is not a file.", from))?;
+    let directory = instance_directory.join(kind.directory_name());
+    fs::create_dir_all(&directory).context("Failed to create pack directory!")?;
+
+    let path = directory.join(file_name);
+    if path.exists() {
+        return Err(anyhow!("A pack named {:?} already exists.", file_name));
+    }
+    fs::copy(from, &path).with_context(|| format!("Failed to copy {:?}", from))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_format_for_version() {
+        assert_eq!(pack_format_for_version("1.5.2"), None);
+        assert_eq!(pack_format_for_version("1.6.1"), Some(1));
+        assert_eq!(pack_format_for_version("1.8.9"), Some(1));
+        assert_eq!(pack_format_for_version("1.12"), Some(3));
+        assert_eq!(pack_format_for_version("1.12.2"), Some(3));
+        assert_eq!(pack_format_for_version("1.16.1"), Some(5));
+        assert_eq!(pack_format_for_version("1.16.5"), Some(6));
+        assert_eq!(pack_format_for_version("1.21.1"), Some(34));
+        assert_eq!(pack_format_for_version("1.21.4"), None);
+        assert_eq!(pack_format_for_version("1.22"), None);
+        assert_eq!(pack_format_for_version("20w14a"), None);
+        assert_eq!(pack_format_for_version("1.16-pre1"), None);
+    }
+
+    #[test]
+    fn test_parse_mcmeta() {
+        assert_eq!(
+            parse_mcmeta(r#"{"pack": {"pack_format": 3, "description": "Plain"}}"#).unwrap(),
+            (3, String::from("Plain"))
+        );
+        assert_eq!(
+            parse_mcmeta(
+                "\u{feff}{\"pack\": {\"pack_format\": 6, \"description\": [{\"text\": \"Fancy \"}, {\"text\": \"pack\", \"extra\": [\"!\"]}]}}"
+            )
+            .unwrap(),
+            (6, String::from("Fancy pack!"))
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let mut options = vec![
+            String::from("version:1343"),
+            String::from("resourcePacks:[\"vanilla\",\"file/a.zip\"]"),
+        ];
+        assert_eq!(
+            get_list_option(&options, "resourcePacks"),
+            vec![String::from("vanilla"), String::from("file/a.zip")]
+        );
+        assert_eq!(get_option(&options, "version"), Some("1343"));
+        assert_eq!(get_option(&options, "versio"), None);
+
+        set_option(&mut options, "lang", ':', "en_us");
+        set_option(&mut options, "version", ':', "1976");
+        assert_eq!(options[0], "version:1976");
+        assert_eq!(options[2], "lang:en_us");
+    }
+}
@@ -11,6 +11,8 @@ pub struct FilePaths {
     pub forge_versions_cache: PathBuf,
     pub config: PathBuf,
     pub instances: PathBuf,
+    /// Every failed io event, appended to
+    pub error_log: PathBuf,
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +23,11 @@ pub struct DirectoryPaths {
     pub launcher_work: PathBuf,
     pub launcher_cache: PathBuf,
     pub forge_version_manifests_cache: PathBuf,
+    pub java_runtimes: PathBuf,
+    pub snapshots: PathBuf,
+    pub mod_store: PathBuf,
+    /// Custom themes, one json file each
+    pub themes: PathBuf,
 }
 
 #[derive(Debug, Clone)]
@@ -49,7 +56,11 @@ impl Paths {
 
         let directory_paths = DirectoryPaths {
             instances: data_directory.join("instances"),
+            snapshots: data_directory.join("snapshots"),
+            mod_store: data_directory.join("store"),
+            themes: data_directory.join("themes"),
             forge_version_manifests_cache: cache_directory.join("forge_version_manifests"),
+            java_runtimes: cache_directory.join("java_runtimes"),
             launcher_cache: cache_directory.join("launcher"),
             launcher_work: launcher_work_directory,
             cache: cache_directory,
@@ -64,6 +75,7 @@ impl Paths {
                 forge_versions_cache: directory_paths.cache.join("forge.json"),
                 instances: directory_paths.data.join("instances.json"),
                 config: directory_paths.data.join("config.json"),
+                error_log: directory_paths.data.join("errors.log"),
             },
             directory: directory_paths,
         })
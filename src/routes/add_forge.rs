@@ -1,8 +1,5 @@
 use async_trait::async_trait;
-use tui::{
-    layout::{Constraint, Rect},
-    widgets::Row,
-};
+use tui::layout::{Constraint, Rect};
 
 use super::*;
 use crate::{forge, util, Instance, IoEvent};
@@ -22,6 +19,8 @@ pub struct State {
     pub chosen_forge_version: Option<forge::VersionManifestVersion>,
     pub progress_main: Option<util::Progress>,
     pub progress_sub: Option<util::Progress>,
+    pub confirm_cancel: common::ConfirmCancel,
+    filter: common::Filter,
 }
 
 impl Default for State {
@@ -33,6 +32,8 @@ impl Default for State {
             chosen_forge_version: None,
             progress_main: None,
             progress_sub: None,
+            confirm_cancel: Default::default(),
+            filter: Default::default(),
         }
     }
 }
@@ -48,7 +49,6 @@ impl State {
 
 pub struct Impl {}
 
-#[async_trait]
 impl RouteImpl for Impl {
     fn is_modal(&self) -> bool {
         true
@@ -57,13 +57,13 @@ impl RouteImpl for Impl {
         match app.state.add_forge.inner {
             InnerState::ForgeNotice => vec![("⏎", "continue"), ("ESC", "cancel")],
             InnerState::FetchVersionManifests => Vec::new(),
-            InnerState::ChooseForgeVersion => vec![
+            InnerState::ChooseForgeVersion => app.state.add_forge.filter.get_help(vec![
                 ("ESC", "cancel"),
                 ("↑↓", "choose version"),
                 ("PgUp/PgDn", "move cursor 25"),
                 ("⏎", "select"),
-            ],
-            InnerState::Install => Vec::new(),
+            ]),
+            InnerState::Install => app.state.add_forge.confirm_cancel.get_help(),
         }
     }
     fn handle_key(&self, key: Key, app: &mut App) {
@@ -77,76 +77,73 @@ impl RouteImpl for Impl {
             },
             InnerState::FetchVersionManifests => {}
             InnerState::ChooseForgeVersion => {
-                let versions_len = app
+                let versions = app
                     .forge_version_manifest
                     .as_ref()
                     .unwrap()
-                    .versions
-                    .iter()
-                    .filter(|version| {
-                        version.game_version
-                            == app.state.add_forge.instance.as_ref().unwrap().version_id
-                    })
-                    .count();
-                match key {
-                    Key::Up => {
-                        app.state.add_forge.selected =
-                            util::wrap_dec(app.state.add_forge.selected, versions_len)
-                    }
-                    Key::Down => {
-                        app.state.add_forge.selected =
-                            util::wrap_inc(app.state.add_forge.selected, versions_len)
-                    }
-                    Key::PageUp => {
-                        app.state.add_forge.selected =
-                            util::wrap_sub(app.state.add_forge.selected, versions_len, 25)
-                    }
-                    Key::PageDown => {
-                        app.state.add_forge.selected =
-                            util::wrap_add(app.state.add_forge.selected, versions_len, 25)
-                    }
-                    Key::Enter => {
-                        app.state.add_forge.chosen_forge_version = Some(
-                            app.forge_version_manifest
-                                .as_ref()
-                                .unwrap()
-                                .versions
-                                .iter()
-                                .filter(|version| {
-                                    version.game_version
-                                        == app.state.add_forge.instance.as_ref().unwrap().version_id
-                                })
-                                .collect::<Vec<_>>()
-                                .get(app.state.add_forge.selected)
-                                .unwrap()
-                                .clone()
-                                .clone(),
-                        );
-                        app.dispatch(IoEvent::AddForge);
-                        app.state.add_forge.inner = InnerState::Install;
-                        app.state.add_forge.selected = 0;
-                    }
-                    _ => {}
+                    .for_game_version(&app.state.add_forge.instance.as_ref().unwrap().version_id);
+                let rows = common::forge_version_rows(&versions);
+                let state = &mut app.state.add_forge;
+                if state
+                    .filter
+                    .handle_key(key, &mut state.selected, &common::first_column(&rows))
+                {
+                    return;
+                }
+                if let Key::Enter = key {
+                    state.chosen_forge_version = versions.get(state.selected).cloned().cloned();
+                    state.selected = 0;
+                    state.filter = Default::default();
+                    state.inner = InnerState::Install;
+                    app.dispatch(IoEvent::AddForge);
                 }
             }
-            InnerState::Install => {}
+            InnerState::Install => app.state.add_forge.confirm_cancel.handle_key(key),
+        }
+    }
+    fn handle_esc(&self, app: &mut App) -> bool {
+        match app.state.add_forge.inner {
+            InnerState::ChooseForgeVersion => app.state.add_forge.filter.handle_esc(),
+            InnerState::Install => {
+                app.state.add_forge.confirm_cancel.toggle();
+                true
+            }
+            _ => false,
         }
     }
-    async fn draw(&self, f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
         match &app.state.add_forge.inner {
             InnerState::ForgeNotice => draw_forge_notice(f, app, chunk),
             InnerState::FetchVersionManifests => {
                 draw_loading(f, app, chunk, "Loading version manifests...").await
             }
             InnerState::ChooseForgeVersion => draw_choose_forge_version(f, app, chunk),
-            InnerState::Install => draw_loading(f, app, chunk, "Updating your instance.").await,
+            InnerState::Install => {
+                let state = &app.state.add_forge;
+                state
+                    .confirm_cancel
+                    .draw(
+                        f,
+                        &app.theme,
+                        chunk,
+                        "Updating your instance.",
+                        "Stop installing forge? The instance is left as it was.",
+                        &[state.progress_main.as_ref(), state.progress_sub.as_ref()],
+                    )
+                    .await
+            }
         }
     }
 }
 
-async fn draw_loading(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect, msg: &str) {
+async fn draw_loading<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect, msg: &str) {
     common::draw_loading_dialog(
         f,
+        &app.theme,
         chunk,
         msg,
         &[
@@ -157,61 +154,36 @@ async fn draw_loading(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect, msg: &str
     .await
 }
 
-pub fn draw_forge_notice(f: &mut UiFrame<'_>, _app: &mut App, chunk: Rect) {
+pub fn draw_forge_notice<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
     let text = "Forge is an open source project that mostly relies on ad revenue.
 By using Polyblock you bypass viewing these ads.
 Please strongly consider supporting the creator of Forge LexManos' Patreon.
 https://www.patreon.com/LexManos";
-    common::draw_button_dialog(f, chunk, 10, text, vec!["[ Ok ]"], 0)
+    common::draw_button_dialog(f, &app.theme, chunk, 10, text, vec!["[ Ok ]"], 0)
 }
 
-pub fn draw_choose_forge_version(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
+pub fn draw_choose_forge_version<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
     let rect = util::centered_rect_percentage(90, 75, chunk);
+    let state = &app.state.add_forge;
+    let versions = app
+        .forge_version_manifest
+        .as_ref()
+        .unwrap()
+        .for_game_version(&state.instance.as_ref().unwrap().version_id);
 
-    let versions = &(app.forge_version_manifest.as_ref().unwrap().versions);
-    // TODO: sort
-
-    let offset = app
-        .state
-        .add_forge
-        .selected
-        .saturating_sub((rect.height / 2) as usize)
-        .min(versions.len().saturating_sub((rect.height / 2) as usize));
-
-    let rows: Vec<_> = versions
-        .iter()
-        .filter(|version| {
-            version.game_version == app.state.add_forge.instance.as_ref().unwrap().version_id
-        })
-        .skip(offset)
-        .take(rect.height as usize)
-        .map(|version| {
-            Row::Data(
-                vec![
-                    format!(
-                        "{}{}{}",
-                        version.name.trim_start_matches("forge-"),
-                        if version.latest { " (latest)" } else { "" },
-                        if version.recommended {
-                            " (recommended)"
-                        } else {
-                            ""
-                        }
-                    ),
-                    version.date_modified.format("%b %e %Y").to_string(),
-                ]
-                .into_iter(),
-            )
-        })
-        .collect();
-
-    common::draw_table(
+    let cursor = common::draw_filtered_table(
         f,
+        &app.theme,
         rect,
         &["   Version id", "Release date"],
-        rows,
+        common::forge_version_rows(&versions),
         &[Constraint::Percentage(50), Constraint::Percentage(50)],
         Some("Choose forge version"),
-        Some(app.state.add_forge.selected - offset),
-    )
+        state.selected,
+        &[],
+        &state.filter,
+    );
+    if let Some(cursor) = cursor {
+        app.show_cursor(cursor);
+    }
 }
@@ -1,8 +1,5 @@
 use async_trait::async_trait;
-use tui::{
-    layout::{Constraint, Rect},
-    widgets::Row,
-};
+use tui::layout::Rect;
 
 use super::*;
 use crate::{minecraft, util, Instance, IoEvent};
@@ -19,19 +16,19 @@ pub enum InnerState {
 pub struct State {
     pub inner: InnerState,
     pub instance: Option<Instance>,
-    selected: usize,
     pub chosen_version: Option<minecraft::VersionManifestVersion>,
     pub progress: Option<util::Progress>,
+    version_picker: version_picker::VersionPicker,
 }
 
 impl Default for State {
     fn default() -> Self {
         Self {
             inner: InnerState::FetchVersionManifest,
-            selected: 0,
             chosen_version: None,
             progress: None,
             instance: None,
+            version_picker: Default::default(),
         }
     }
 }
@@ -47,7 +44,6 @@ impl State {
 
 pub struct Impl {}
 
-#[async_trait]
 impl RouteImpl for Impl {
     fn is_modal(&self) -> bool {
         true
@@ -56,12 +52,7 @@ impl RouteImpl for Impl {
         match app.state.change_version.inner {
             InnerState::FetchVersionManifest => Vec::new(),
             InnerState::ForgeWarning => vec![("⏎", "continue"), ("ESC", "cancel")],
-            InnerState::ChooseVersion => vec![
-                ("ESC", "cancel"),
-                ("↑↓", "choose version"),
-                ("PgUp/PgDn", "move cursor 25"),
-                ("⏎", "select"),
-            ],
+            InnerState::ChooseVersion => app.state.change_version.version_picker.get_help(),
             InnerState::Install => Vec::new(),
         }
     }
@@ -75,49 +66,28 @@ impl RouteImpl for Impl {
                 _ => {}
             },
             InnerState::ChooseVersion => {
-                let versions_len = app
-                    .minecraft_version_manifest
-                    .as_ref()
-                    .unwrap()
-                    .versions
-                    .len();
-                match key {
-                    Key::Up => {
-                        app.state.change_version.selected =
-                            util::wrap_dec(app.state.change_version.selected, versions_len)
-                    }
-                    Key::Down => {
-                        app.state.change_version.selected =
-                            util::wrap_inc(app.state.change_version.selected, versions_len)
-                    }
-                    Key::PageUp => {
-                        app.state.change_version.selected =
-                            util::wrap_sub(app.state.change_version.selected, versions_len, 25)
-                    }
-                    Key::PageDown => {
-                        app.state.change_version.selected =
-                            util::wrap_add(app.state.change_version.selected, versions_len, 25)
-                    }
-                    Key::Enter => {
-                        app.state.change_version.chosen_version = Some(
-                            app.minecraft_version_manifest
-                                .as_ref()
-                                .unwrap()
-                                .versions
-                                .get(app.state.change_version.selected)
-                                .unwrap()
-                                .clone(),
-                        );
-                        app.dispatch(IoEvent::ChangeVersion);
-                        app.state.change_version.inner = InnerState::Install;
-                    }
-                    _ => {}
+                let manifest = app.minecraft_version_manifest.as_ref().unwrap();
+                let state = &mut app.state.change_version;
+                if let Some(version) = state.version_picker.handle_key(key, manifest) {
+                    state.chosen_version = Some(version);
+                    state.inner = InnerState::Install;
+                    app.dispatch(IoEvent::ChangeVersion);
                 }
             }
             InnerState::Install => {}
         }
     }
-    async fn draw(&self, f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
+    fn handle_esc(&self, app: &mut App) -> bool {
+        match app.state.change_version.inner {
+            InnerState::ChooseVersion => app.state.change_version.version_picker.handle_esc(),
+            _ => false,
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
         match &app.state.change_version.inner {
             InnerState::FetchVersionManifest => {
                 draw_loading(f, app, chunk, "Loading minecraft version manifest...").await
@@ -129,63 +99,35 @@ impl RouteImpl for Impl {
     }
 }
 
-async fn draw_loading(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect, msg: &str) {
-    common::draw_loading_dialog(f, chunk, msg, &[app.state.change_version.progress.as_ref()]).await
+async fn draw_loading<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect, msg: &str) {
+    common::draw_loading_dialog(
+        f,
+        &app.theme,
+        chunk,
+        msg,
+        &[app.state.change_version.progress.as_ref()],
+    )
+    .await
 }
 
-pub fn draw_choose_minecraft_version(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
-    let rect = util::centered_rect_percentage(90, 75, chunk);
-
-    let versions = &(app.minecraft_version_manifest.as_ref().unwrap().versions);
-    // .iter()
-    // .filter(|version| match version.r#type {
-    //     minecraft::VersionManifestVersionType::Release => true,
-    //     minecraft::VersionManifestVersionType::Snapshot /* if snapshots */ => true,
-    //     minecraft::VersionManifestVersionType::OldBeta /* if historical */ => true,
-    //     minecraft::VersionManifestVersionType::OldAlpha /* if historical */ => true,
-    //     _ => false,
-    // })
-    // .collect::<Vec<_>>();
-
-    let offset = app
-        .state
-        .change_version
-        .selected
-        .saturating_sub((rect.height / 2) as usize)
-        .min(versions.len().saturating_sub((rect.height / 2) as usize));
-
-    let rows: Vec<_> = versions
-        .iter()
-        .skip(offset)
-        .take(rect.height as usize)
-        .map(|version| {
-            Row::Data(
-                vec![
-                    version.id.clone(),
-                    minecraft::version_ident(&version).to_string(),
-                    version.release_time.format("%b %e %Y").to_string(),
-                ]
-                .into_iter(),
-            )
-        })
-        .collect();
-
-    common::draw_table(
+pub fn draw_choose_minecraft_version<B: Backend>(
+    f: &mut UiFrame<'_, B>,
+    app: &mut App,
+    chunk: Rect,
+) {
+    let cursor = app.state.change_version.version_picker.draw(
         f,
-        rect,
-        &["   Version id", "Type", "Release date"],
-        rows,
-        &[
-            Constraint::Percentage(40),
-            Constraint::Percentage(30),
-            Constraint::Percentage(30),
-        ],
-        Some("Choose minecraft version"),
-        Some(app.state.change_version.selected - offset),
-    )
+        &app.theme,
+        chunk,
+        app.minecraft_version_manifest.as_ref().unwrap(),
+        |_| false,
+    );
+    if let Some(cursor) = cursor {
+        app.show_cursor(cursor);
+    }
 }
 
-pub fn draw_forge_warning(f: &mut UiFrame<'_>, _app: &mut App, chunk: Rect) {
+pub fn draw_forge_warning<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
     let text = "This instance currently has forge installed. Changing the minecraft version will remove forge. To reinstall it use the 'Add Forge' option in the menu.";
-    common::draw_button_dialog(f, chunk, 10, text, vec!["[ Ok ]"], 0)
+    common::draw_button_dialog(f, &app.theme, chunk, 10, text, vec!["[ Ok ]"], 0)
 }
@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use tui::{
+    layout::{Constraint, Rect},
+    widgets::Row,
+};
+
+use super::*;
+use crate::{util, util::java::Installation, IoEvent};
+
+#[derive(Default)]
+pub struct State {
+    installations: Vec<Installation>,
+    /// The major java version the instance's minecraft version needs, if known
+    major_version: Option<u64>,
+    selected: usize,
+}
+
+impl State {
+    pub fn new(major_version: Option<u64>) -> Self {
+        let installations = util::java::discover();
+        let selected = major_version
+            .and_then(|major_version| util::java::best_match(&installations, major_version))
+            .and_then(|best| installations.iter().position(|i| i == best))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        Self {
+            installations,
+            major_version,
+            selected,
+        }
+    }
+
+    /// Automatic first, then the installations, then a row for entering a path manually
+    fn len(&self) -> usize {
+        self.installations.len() + 2
+    }
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, _app: &App) -> Vec<(&'static str, &'static str)> {
+        vec![("ESC", "cancel"), ("↑/↓", "choose java"), ("⏎", "select")]
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let state = &mut app.state.choose_java;
+        match key {
+            Key::Up => state.selected = util::wrap_dec(state.selected, state.len()),
+            Key::Down => state.selected = util::wrap_inc(state.selected, state.len()),
+            Key::Enter => {
+                let selected = state.selected;
+                if selected == state.len() - 1 {
+                    app.pop_route();
+                    app.state.instance_settings.edit_selected(&app.settings);
+                    return;
+                }
+                let java_exec = if selected == 0 {
+                    None
+                } else {
+                    Some(state.installations[selected - 1].exec())
+                };
+                app.state
+                    .instance_settings
+                    .instance
+                    .as_mut()
+                    .unwrap()
+                    .java_exec = java_exec;
+                app.dispatch(IoEvent::SaveInstanceSettings);
+                app.pop_route();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let rect = util::centered_rect_percentage(80, 50, chunk);
+        let state = &app.state.choose_java;
+
+        let mut rows = vec![Row::Data(
+            vec![
+                String::from("(Automatic)"),
+                String::new(),
+                String::from("Mojang's runtime for this version"),
+            ]
+            .into_iter(),
+        )];
+        rows.extend(state.installations.iter().map(|installation| {
+            Row::Data(
+                vec![
+                    format!(
+                        "{}{}",
+                        installation.version,
+                        if Some(installation.major_version) == state.major_version {
+                            " (matches)"
+                        } else {
+                            ""
+                        }
+                    ),
+                    installation.vendor.clone().unwrap_or_default(),
+                    installation.home.display().to_string(),
+                ]
+                .into_iter(),
+            )
+        }));
+        rows.push(Row::Data(
+            vec![String::from("Other..."), String::new(), String::new()].into_iter(),
+        ));
+
+        let title = match state.major_version {
+            Some(major_version) => format!("Choose java (needs java {})", major_version),
+            None => String::from("Choose java"),
+        };
+
+        common::draw_table(
+            f,
+            &app.theme,
+            rect,
+            &["   Version", "Vendor", "Path"],
+            rows,
+            &[
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+                Constraint::Percentage(50),
+            ],
+            Some(&title),
+            Some(state.selected),
+        );
+    }
+}
@@ -1,21 +1,22 @@
-use crossterm::{cursor::MoveTo, execute};
 use log::debug;
-use std::{
-    io::{self, Write},
-    iter,
-};
+use std::iter;
 use tui::{
+    backend::Backend,
+    buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
-    widgets::{Block, BorderType, Borders, Clear, Gauge, Paragraph, Row, Table, TableState, Text},
+    style::{Color, Style},
+    widgets::{
+        Block, BorderType, Borders, Clear, Gauge, Paragraph, Row, Table, TableState, Text, Widget,
+    },
 };
 
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::{ui::UiFrame, util};
+use crate::{forge, theme::Theme, ui::UiFrame, util, Key};
 
-pub fn draw_button_dialog(
-    f: &mut UiFrame<'_>,
+pub fn draw_button_dialog<B: Backend>(
+    f: &mut UiFrame<'_, B>,
+    theme: &Theme,
     chunk: Rect,
     height: u16,
     text: &str,
@@ -27,7 +28,8 @@ pub fn draw_button_dialog(
 
     let block_widget = Block::default()
         .borders(Borders::ALL)
-        .border_type(BorderType::Plain);
+        .border_type(BorderType::Plain)
+        .border_style(theme.border);
     f.render_widget(Clear, rect);
     f.render_widget(block_widget, rect);
 
@@ -47,7 +49,7 @@ pub fn draw_button_dialog(
     f.render_widget(
         Paragraph::new([Text::raw(text)].iter())
             .alignment(Alignment::Center)
-            .style(Style::default().fg(Color::Yellow))
+            .style(theme.message)
             .wrap(true),
         layout[0],
     );
@@ -72,22 +74,24 @@ pub fn draw_button_dialog(
     for (i, button) in buttons.into_iter().enumerate() {
         f.render_widget(
             Paragraph::new(vec![Text::raw(button)].iter()).style(if selected_button_i == i {
-                Style::default().fg(Color::Blue).modifier(Modifier::BOLD)
+                theme.highlight
             } else {
-                Style::default().modifier(Modifier::DIM)
+                theme.dimmed
             }),
             button_layout[(i * 2) + 1],
         );
     }
 }
 
-pub fn draw_input_dialog(
-    f: &mut UiFrame<'_>,
+/// Returns where the cursor should be shown, at the end of the entered text
+pub fn draw_input_dialog<B: Backend>(
+    f: &mut UiFrame<'_, B>,
+    theme: &Theme,
     chunk: Rect,
     title: &str,
     entered_text: &str,
     error: Option<&str>,
-) {
+) -> (u16, u16) {
     let rect = util::centered_rect_percentage_dir(Direction::Horizontal, 30, chunk);
     let rect = util::centered_rect_dir(
         Direction::Vertical,
@@ -99,30 +103,114 @@ pub fn draw_input_dialog(
     let mut text = vec![Text::raw(entered_text)];
     if let Some(error) = error {
         text.push(Text::raw("\n\r"));
-        text.push(Text::styled(error, Style::default().fg(Color::Red)));
+        text.push(Text::styled(error, theme.error));
     }
 
     f.render_widget(
-        Paragraph::new(text.iter())
-            .style(Style::default().fg(Color::Yellow))
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Plain)
-                    .title(title),
-            ),
+        Paragraph::new(text.iter()).style(theme.message).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(theme.border)
+                .title(title),
+        ),
         rect,
     );
 
-    execute!(
-        io::stdout(),
-        MoveTo(rect.x + 1 + (entered_text.width() as u16), rect.y + 1)
-    )
-    .ok();
+    (rect.x + 1 + (entered_text.width() as u16), rect.y + 1)
+}
+
+/// Asks before cancelling a running install, which then stops at its next checkpoint
+#[derive(Clone, Default)]
+pub struct ConfirmCancel {
+    pub cancel: util::Cancel,
+    shown: bool,
+    selected: usize,
+}
+
+impl ConfirmCancel {
+    /// Esc asks, or takes the question back again
+    pub fn toggle(&mut self) {
+        if !self.cancel.is_cancelled() {
+            self.shown = !self.shown;
+            self.selected = 1;
+        }
+    }
+
+    pub fn get_help(&self) -> Vec<(&'static str, &'static str)> {
+        if self.shown {
+            vec![
+                ("←/→", "choose option"),
+                ("Y", "yes"),
+                ("N", "no"),
+                ("⏎", "select"),
+                ("ESC", "keep going"),
+            ]
+        } else if self.cancel.is_cancelled() {
+            Vec::new()
+        } else {
+            vec![("ESC", "cancel")]
+        }
+    }
+
+    pub fn handle_key(&mut self, key: Key) {
+        if !self.shown {
+            return;
+        }
+        match key {
+            Key::Left => self.selected = util::wrap_dec(self.selected, 2),
+            Key::Right => self.selected = util::wrap_inc(self.selected, 2),
+            Key::Char('y') => self.confirm(),
+            Key::Char('n') => self.shown = false,
+            Key::Enter => {
+                if self.selected == 0 {
+                    self.confirm();
+                } else {
+                    self.shown = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn confirm(&mut self) {
+        self.shown = false;
+        self.cancel.cancel();
+    }
+
+    /// The loading dialog of the install, with the question on top
+    pub async fn draw<B: Backend>(
+        &self,
+        f: &mut UiFrame<'_, B>,
+        theme: &Theme,
+        chunk: Rect,
+        msg: &str,
+        question: &str,
+        progress: &[Option<&util::Progress>],
+    ) {
+        let msg = if self.cancel.is_cancelled() {
+            "Cancelling..."
+        } else {
+            msg
+        };
+        draw_loading_dialog(f, theme, chunk, msg, progress).await;
+        if self.shown {
+            draw_button_dialog(
+                f,
+                theme,
+                chunk,
+                8,
+                question,
+                vec!["[ Yes ]", "[ No ]"],
+                self.selected,
+            );
+        }
+    }
 }
 
-pub async fn draw_loading_dialog(
-    f: &mut UiFrame<'_>,
+pub async fn draw_loading_dialog<B: Backend>(
+    f: &mut UiFrame<'_, B>,
+    theme: &Theme,
     chunk: Rect,
     msg: &str,
     progress: &[Option<&util::Progress>],
@@ -148,7 +236,8 @@ pub async fn draw_loading_dialog(
     f.render_widget(
         Block::default()
             .borders(Borders::ALL)
-            .border_type(BorderType::Plain),
+            .border_type(BorderType::Plain)
+            .border_style(theme.border),
         rect,
     );
 
@@ -164,7 +253,7 @@ pub async fn draw_loading_dialog(
 
     f.render_widget(
         Paragraph::new([Text::raw(msg)].iter())
-            .style(Style::default().fg(Color::Yellow))
+            .style(theme.message)
             .alignment(Alignment::Center),
         layout[0],
     );
@@ -176,12 +265,7 @@ pub async fn draw_loading_dialog(
         debug!("Ratio {:?}, msg: {:?}, label: {:?}", ratio, msg, label);
         f.render_widget(
             Gauge::default()
-                .style(
-                    Style::default()
-                        .fg(Color::White)
-                        .bg(Color::Black)
-                        .modifier(Modifier::ITALIC),
-                )
+                .style(theme.gauge)
                 .label(&label)
                 .ratio(ratio),
             layout[i],
@@ -191,7 +275,7 @@ pub async fn draw_loading_dialog(
             i += 1;
             f.render_widget(
                 Paragraph::new([Text::raw(msg)].iter())
-                    .style(Style::default().fg(Color::Yellow))
+                    .style(theme.message)
                     .alignment(Alignment::Center),
                 layout[i],
             );
@@ -201,8 +285,10 @@ pub async fn draw_loading_dialog(
     }
 }
 
-pub fn draw_table<D>(
-    f: &mut UiFrame<'_>,
+#[allow(clippy::too_many_arguments)]
+pub fn draw_table<B: Backend, D>(
+    f: &mut UiFrame<'_, B>,
+    theme: &Theme,
     chunk: Rect,
     header: &[&str],
     rows: Vec<Row<D>>,
@@ -219,10 +305,10 @@ pub fn draw_table<D>(
     state.select(selected);
 
     let mut table = Table::new(header.iter(), rows.into_iter())
-        .header_style(Style::default().fg(Color::Yellow).modifier(Modifier::BOLD))
+        .header_style(theme.header)
         .widths(widths)
-        .style(Style::default())
-        .highlight_style(Style::default().fg(Color::Blue).modifier(Modifier::BOLD))
+        .style(theme.text)
+        .highlight_style(theme.highlight)
         .highlight_symbol(">> ")
         .column_spacing(1)
         .header_gap(0);
@@ -232,9 +318,278 @@ pub fn draw_table<D>(
             Block::default()
                 .title(title)
                 .borders(Borders::ALL)
-                .border_type(BorderType::Plain),
+                .border_type(BorderType::Plain)
+                .border_style(theme.border),
         );
     }
 
     f.render_stateful_widget(table, chunk, &mut state);
 }
+
+/// Narrows a table down to the rows whose first column fuzzy matches a query, typed after `/`
+///
+/// Rows keep their order. The selection is an index into all rows, so it stays on the same row for
+/// as long as that one matches.
+#[derive(Clone, Default)]
+pub struct Filter {
+    query: String,
+    /// Whether typed characters go to the query
+    editing: bool,
+}
+
+impl Filter {
+    pub fn is_active(&self) -> bool {
+        self.editing || !self.query.is_empty()
+    }
+
+    /// The route's own help, with Esc clearing the filter while there is one
+    pub fn get_help(
+        &self,
+        mut help: Vec<(&'static str, &'static str)>,
+    ) -> Vec<(&'static str, &'static str)> {
+        if self.is_active() {
+            for (key, text) in &mut help {
+                if *key == "ESC" {
+                    *text = "clear filter";
+                }
+            }
+        } else {
+            help.push(("/", "filter"));
+        }
+        help
+    }
+
+    /// The matching rows, with the indices of the matched characters
+    pub fn matches<S: AsRef<str>>(&self, texts: &[S]) -> Vec<(usize, Vec<usize>)> {
+        texts
+            .iter()
+            .enumerate()
+            .filter_map(|(i, text)| {
+                util::fuzzy_match(&self.query, text.as_ref()).map(|indices| (i, indices))
+            })
+            .collect()
+    }
+
+    /// Typing, and moving the selection through the matching rows
+    ///
+    /// Returns whether the key was handled. Enter ends typing and is left to the route, unless the
+    /// selected row does not match.
+    pub fn handle_key<S: AsRef<str>>(
+        &mut self,
+        key: Key,
+        selected: &mut usize,
+        texts: &[S],
+    ) -> bool {
+        let visible: Vec<usize> = self.matches(texts).into_iter().map(|(i, _)| i).collect();
+        match key {
+            Key::Char('/') if !self.editing => self.editing = true,
+            Key::Char(c) if self.editing => self.query.push(c),
+            Key::Backspace if self.editing => {
+                self.query.pop();
+            }
+            Key::Up | Key::Down | Key::PageUp | Key::PageDown => {
+                if !visible.is_empty() {
+                    let len = visible.len();
+                    let position = visible.iter().position(|i| i == selected).unwrap_or(0);
+                    let position = match key {
+                        Key::Up => util::wrap_dec(position, len),
+                        Key::Down => util::wrap_inc(position, len),
+                        Key::PageUp => util::wrap_sub(position, len, 25),
+                        _ => util::wrap_add(position, len, 25),
+                    };
+                    *selected = visible[position];
+                }
+                return true;
+            }
+            Key::Enter => {
+                self.editing = false;
+                return !visible.contains(selected);
+            }
+            _ => return false,
+        }
+
+        self.reselect(selected, texts);
+        true
+    }
+
+    /// Stay on the selected row while it still matches, or move to the first one that does
+    pub fn reselect<S: AsRef<str>>(&self, selected: &mut usize, texts: &[S]) {
+        let visible = self.matches(texts);
+        if !visible.iter().any(|(i, _)| i == selected) {
+            if let Some((first, _)) = visible.first() {
+                *selected = *first;
+            }
+        }
+    }
+
+    /// Esc clears the filter first, returning whether there was one
+    pub fn handle_esc(&mut self) -> bool {
+        let was_active = self.is_active();
+        *self = Default::default();
+        was_active
+    }
+}
+
+/// Restyles the matched characters of a table after it was drawn
+struct Highlight(Style, Vec<(u16, u16, char)>);
+
+impl Widget for Highlight {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let Highlight(style, chars) = self;
+        for (x, y, c) in chars {
+            if x >= area.right() || y >= area.bottom() {
+                continue;
+            }
+            let cell = buf.get_mut(x, y);
+            // The column may have cut the text short
+            if cell.symbol != c.to_string() {
+                continue;
+            }
+            // Keeping what the row itself looks like unless the theme says otherwise
+            if style.fg != Color::Reset {
+                cell.style.fg = style.fg;
+            }
+            if style.bg != Color::Reset {
+                cell.style.bg = style.bg;
+            }
+            cell.style.modifier |= style.modifier;
+        }
+    }
+}
+
+/// [`draw_table`] showing the rows matching a [`Filter`], which are scrolled through by themselves
+///
+/// `selected` is an index into all rows, and rows marked in `dimmed` are greyed out. Returns where
+/// the cursor should be shown while the query is typed.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_filtered_table<B: Backend>(
+    f: &mut UiFrame<'_, B>,
+    theme: &Theme,
+    chunk: Rect,
+    header: &[&str],
+    rows: Vec<Vec<String>>,
+    widths: &[Constraint],
+    title: Option<&str>,
+    selected: usize,
+    dimmed: &[bool],
+    filter: &Filter,
+) -> Option<(u16, u16)> {
+    let texts = first_column(&rows);
+    let matches = filter.matches(&texts);
+
+    let (table_chunk, filter_chunk) = if filter.is_active() && chunk.height > 1 {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+            .split(chunk);
+        (layout[0], Some(layout[1]))
+    } else {
+        (chunk, None)
+    };
+
+    // Below the header, and within the borders that come with a title
+    let inner = if title.is_some() {
+        Rect {
+            x: table_chunk.x + 1,
+            y: table_chunk.y + 1,
+            width: table_chunk.width.saturating_sub(2),
+            height: table_chunk.height.saturating_sub(2),
+        }
+    } else {
+        table_chunk
+    };
+    let height = inner.height.saturating_sub(1) as usize;
+    let position = matches.iter().position(|(i, _)| *i == selected);
+    let offset = position
+        .unwrap_or(0)
+        .saturating_sub(height / 2)
+        .min(matches.len().saturating_sub(height));
+    let shown = &matches[offset.min(matches.len())..(offset + height).min(matches.len())];
+
+    let mut highlight = Vec::new();
+    for (row, (i, indices)) in shown.iter().enumerate() {
+        let y = inner.y + 1 + row as u16;
+        // Past the highlight symbol of the selected row
+        let mut x = inner.x + 3;
+        for (j, c) in texts[*i].chars().enumerate() {
+            if indices.contains(&j) {
+                highlight.push((x, y, c));
+            }
+            x += c.width().unwrap_or(0) as u16;
+        }
+    }
+
+    let table_rows = shown
+        .iter()
+        .map(|(i, _)| {
+            let cells = rows[*i].clone().into_iter();
+            if dimmed.get(*i) == Some(&true) {
+                Row::StyledData(cells, theme.unavailable)
+            } else {
+                Row::Data(cells)
+            }
+        })
+        .collect();
+    draw_table(
+        f,
+        theme,
+        table_chunk,
+        header,
+        table_rows,
+        widths,
+        title,
+        position.map(|position| position - offset),
+    );
+    f.render_widget(Highlight(theme.matched, highlight), inner);
+
+    let filter_chunk = filter_chunk?;
+    let count = format!("   {} of {}", matches.len(), rows.len());
+    f.render_widget(Clear, filter_chunk);
+    f.render_widget(
+        Paragraph::new(
+            [
+                Text::styled(format!("/{}", filter.query), theme.message),
+                Text::styled(count, theme.dimmed),
+            ]
+            .iter(),
+        ),
+        filter_chunk,
+    );
+    if filter.editing {
+        Some((
+            filter_chunk.x + 1 + filter.query.width() as u16,
+            filter_chunk.y,
+        ))
+    } else {
+        None
+    }
+}
+
+/// Rows for choosing a forge version, the name first
+pub fn forge_version_rows(versions: &[&forge::VersionManifestVersion]) -> Vec<Vec<String>> {
+    versions
+        .iter()
+        .map(|version| {
+            vec![
+                format!(
+                    "{}{}{}",
+                    version.name.trim_start_matches("forge-"),
+                    if version.latest { " (latest)" } else { "" },
+                    if version.recommended {
+                        " (recommended)"
+                    } else {
+                        ""
+                    }
+                ),
+                version.date_modified.format("%b %e %Y").to_string(),
+            ]
+        })
+        .collect()
+}
+
+/// The first column of rows, which is what a [`Filter`] matches
+pub fn first_column(rows: &[Vec<String>]) -> Vec<&str> {
+    rows.iter()
+        .map(|row| row.first().map(String::as_str).unwrap_or_default())
+        .collect()
+}
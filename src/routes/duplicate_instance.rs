@@ -0,0 +1,185 @@
+use async_trait::async_trait;
+use tui::{
+    layout::{Constraint, Direction, Rect},
+    widgets::Row,
+};
+
+use super::*;
+use crate::{util, Instance, IoEvent};
+
+/// Directories that are optionally left out of the copy
+pub const OPTIONAL_DIRECTORIES: [&str; 3] = ["saves", "screenshots", "logs"];
+
+#[derive(Clone)]
+pub enum InnerState {
+    EnterName,
+    ChooseContents,
+    Copy,
+}
+
+pub struct State {
+    pub inner: InnerState,
+    pub instance: Option<Instance>,
+    pub name_input: String,
+    error: Option<String>,
+    selected: usize,
+    /// Whether each of OPTIONAL_DIRECTORIES should be copied
+    pub include: [bool; 3],
+    pub progress: Option<util::Progress>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            inner: InnerState::EnterName,
+            instance: None,
+            name_input: String::new(),
+            error: None,
+            selected: 0,
+            include: [true, true, false],
+            progress: None,
+        }
+    }
+}
+
+impl State {
+    pub fn new(instance: Instance) -> Self {
+        Self {
+            name_input: format!("{} (copy)", instance.name),
+            instance: Some(instance),
+            ..Default::default()
+        }
+    }
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        match app.state.duplicate_instance.inner {
+            InnerState::EnterName => vec![("ESC", "cancel"), ("⏎", "continue")],
+            InnerState::ChooseContents => vec![
+                ("ESC", "cancel"),
+                ("↑/↓", "move cursor"),
+                ("space", "toggle"),
+                ("⏎", "duplicate"),
+            ],
+            InnerState::Copy => Vec::new(),
+        }
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        match app.state.duplicate_instance.inner {
+            InnerState::EnterName => {
+                let state = &mut app.state.duplicate_instance;
+                match key {
+                    Key::Char(c) => {
+                        state.name_input.push(c);
+                    }
+                    Key::Backspace => {
+                        state.name_input.pop();
+                    }
+                    _ => {}
+                }
+
+                // Validate before handling enter since the name is prefilled
+                if state.name_input.is_empty() {
+                    state.error = Some("You must enter a name!".to_string())
+                } else if app.instances.inner.contains_key(&state.name_input) {
+                    state.error = Some("An instance with that name already exists!".to_string())
+                } else {
+                    state.error = None;
+                }
+
+                if key == Key::Enter && state.error.is_none() {
+                    state.inner = InnerState::ChooseContents;
+                }
+            }
+            InnerState::ChooseContents => {
+                let state = &mut app.state.duplicate_instance;
+                match key {
+                    Key::Up => {
+                        state.selected = util::wrap_dec(state.selected, OPTIONAL_DIRECTORIES.len())
+                    }
+                    Key::Down => {
+                        state.selected = util::wrap_inc(state.selected, OPTIONAL_DIRECTORIES.len())
+                    }
+                    Key::Char(' ') => state.include[state.selected] ^= true,
+                    Key::Enter => {
+                        state.inner = InnerState::Copy;
+                        app.dispatch(IoEvent::DuplicateInstance);
+                    }
+                    _ => {}
+                }
+            }
+            InnerState::Copy => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        match &app.state.duplicate_instance.inner {
+            InnerState::EnterName => {
+                let state = &app.state.duplicate_instance;
+                let cursor = common::draw_input_dialog(
+                    f,
+                    &app.theme,
+                    chunk,
+                    "Enter name for the copy",
+                    &state.name_input,
+                    state.error.as_deref(),
+                );
+                app.show_cursor(cursor);
+            }
+            InnerState::ChooseContents => draw_choose_contents(f, app, chunk),
+            InnerState::Copy => {
+                common::draw_loading_dialog(
+                    f,
+                    &app.theme,
+                    chunk,
+                    "Duplicating instance...",
+                    &[app.state.duplicate_instance.progress.as_ref()],
+                )
+                .await
+            }
+        }
+    }
+}
+
+fn draw_choose_contents<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+    let state = &app.state.duplicate_instance;
+    let rect = util::centered_rect_dir(
+        Direction::Vertical,
+        OPTIONAL_DIRECTORIES.len() as u16 + 3,
+        util::centered_rect_percentage_dir(Direction::Horizontal, 40, chunk),
+    );
+
+    let rows: Vec<_> = OPTIONAL_DIRECTORIES
+        .iter()
+        .zip(state.include.iter())
+        .map(|(directory, include)| {
+            Row::Data(
+                vec![
+                    String::from(if *include { "[x]" } else { "[ ]" }),
+                    format!("{}/", directory),
+                ]
+                .into_iter(),
+            )
+        })
+        .collect();
+
+    common::draw_table(
+        f,
+        &app.theme,
+        rect,
+        &["   Copy", "Directory"],
+        rows,
+        &[Constraint::Length(6), Constraint::Min(10)],
+        Some("Choose what to copy"),
+        Some(state.selected),
+    )
+}
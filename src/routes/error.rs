@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use tui::{
+    layout::Rect,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Text},
+};
+use unicode_width::UnicodeWidthStr;
+
+use super::*;
+use crate::{jobs, util, IoEvent};
+
+/// An io event that failed
+pub struct Failure {
+    pub io_event: IoEvent,
+    /// The anyhow context chain, outermost first
+    pub chain: Vec<String>,
+    pub details: String,
+    /// Nothing on screen is waiting for the event, like for those the watcher sent
+    pub background: bool,
+    /// What the event read from the routes when it was sent, see [`jobs::inputs`]
+    pub inputs: String,
+}
+
+impl Failure {
+    pub fn new(io_event: IoEvent, e: &::anyhow::Error) -> Self {
+        Self {
+            background: matches!(io_event, IoEvent::ReloadInstances | IoEvent::RefreshMods(_)),
+            io_event,
+            chain: e.chain().map(|cause| cause.to_string()).collect(),
+            details: format!("{:?}", e),
+            inputs: String::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct State {
+    /// Shown one at a time, oldest first
+    pub failures: VecDeque<Failure>,
+    selected: usize,
+    details: bool,
+    scroll: u16,
+    /// A dismissed failure left a dialog waiting, to be closed along with this one
+    unwind: bool,
+}
+
+/// Show a failure, on top of any that are already shown
+pub fn show(app: &mut App, failure: Failure) {
+    let shown = matches!(app.get_current_routes().last(), Some(Route::Error));
+    app.state.error.failures.push_back(failure);
+    if !shown {
+        app.state.error.selected = 0;
+        app.state.error.details = false;
+        app.state.error.scroll = 0;
+        app.state.error.unwind = false;
+        app.push_route(Route::Error);
+    }
+}
+
+/// Take the shown failure away, returning it and whether that closed the dialog
+fn take(app: &mut App) -> Option<(Failure, bool)> {
+    let state = &mut app.state.error;
+    let failure = state.failures.pop_front()?;
+    state.selected = 0;
+    state.details = false;
+    state.scroll = 0;
+    let closed = state.failures.is_empty();
+    if closed {
+        app.pop_route();
+    }
+    Some((failure, closed))
+}
+
+/// Forget the shown failure
+///
+/// Whatever dialog was waiting on a dismissed action is closed too once the last failure is
+/// gone, so it isn't left loading forever.
+pub fn dismiss(app: &mut App) {
+    if let Some((failure, closed)) = take(app) {
+        app.state.error.unwind |= !failure.background;
+        if closed && app.state.error.unwind {
+            app.state.error.unwind = false;
+            let waiting = app
+                .get_current_routes()
+                .last()
+                .map(|route| route.get_impl().is_modal())
+                .unwrap_or(false);
+            if waiting {
+                app.pop_route();
+            }
+        }
+    }
+}
+
+/// Whether the shown failure can be sent again
+///
+/// Events read what they act on from the routes they were sent from. Once those were left and
+/// opened again for something else, a retry would act on that instead, so it is refused.
+fn can_retry(app: &App) -> bool {
+    match app.state.error.failures.front() {
+        Some(failure) => jobs::inputs(&failure.io_event, app) == failure.inputs,
+        None => false,
+    }
+}
+
+/// Send the failed event again, leaving whatever was waiting on it to wait some more
+fn retry(app: &mut App) {
+    if !can_retry(app) {
+        return;
+    }
+    if let Some((failure, closed)) = take(app) {
+        if closed {
+            app.state.error.unwind = false;
+        }
+        app.dispatch(failure.io_event);
+    }
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        if app.state.error.details {
+            vec![("↑/↓", "scroll"), ("D/⏎", "back"), ("ESC", "dismiss")]
+        } else if can_retry(app) {
+            vec![
+                ("←/→", "choose option"),
+                ("R", "retry"),
+                ("D", "details"),
+                ("⏎", "select"),
+                ("ESC", "dismiss"),
+            ]
+        } else {
+            vec![
+                ("←/→", "choose option"),
+                ("D", "details"),
+                ("⏎", "select"),
+                ("ESC", "dismiss"),
+            ]
+        }
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let state = &mut app.state.error;
+        if state.details {
+            match key {
+                Key::Up => state.scroll = state.scroll.saturating_sub(1),
+                Key::Down => state.scroll = state.scroll.saturating_add(1),
+                Key::Char('d') | Key::Enter => state.details = false,
+                _ => {}
+            }
+            return;
+        }
+        match key {
+            Key::Left => state.selected = util::wrap_dec(state.selected, 3),
+            Key::Right => state.selected = util::wrap_inc(state.selected, 3),
+            Key::Char('r') => retry(app),
+            Key::Char('d') => state.details = true,
+            Key::Enter => match state.selected {
+                0 => retry(app),
+                1 => state.details = true,
+                _ => dismiss(app),
+            },
+            _ => {}
+        }
+    }
+    fn handle_esc(&self, app: &mut App) -> bool {
+        dismiss(app);
+        true
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let state = &app.state.error;
+        let failure = match state.failures.front() {
+            Some(failure) => failure,
+            None => return,
+        };
+        let more = state.failures.len() - 1;
+
+        if state.details {
+            let rect = util::centered_rect_percentage(80, 80, chunk);
+            let text = [
+                Text::raw(format!("{:?} failed:\n\n", failure.io_event)),
+                Text::raw(&failure.details),
+                Text::styled(
+                    format!(
+                        "\n\nThis was also written to {}",
+                        app.paths.file.error_log.display()
+                    ),
+                    app.theme.message,
+                ),
+            ];
+            f.render_widget(Clear, rect);
+            f.render_widget(
+                Paragraph::new(text.iter())
+                    .style(app.theme.text)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_type(BorderType::Plain)
+                            .border_style(app.theme.border)
+                            .title("Error details"),
+                    )
+                    .wrap(true)
+                    .scroll(state.scroll),
+                rect,
+            );
+            return;
+        }
+
+        let mut msg = failure.chain[0].clone();
+        for cause in &failure.chain[1..] {
+            msg.push_str("\nCaused by: ");
+            msg.push_str(cause);
+        }
+        if !can_retry(app) {
+            msg.push_str(
+                "\nThis can't be retried, as it was sent from a dialog that changed since.",
+            );
+        }
+        if more > 0 {
+            msg.push_str(&format!("\n({} more errors after this one)", more));
+        }
+
+        // Fit the wrapped message, within the 60% wide dialog minus its borders and margins
+        let width = (chunk.width * 60 / 100).saturating_sub(4).max(1) as usize;
+        let lines: usize = msg
+            .lines()
+            .map(|line| (line.width().max(1) - 1) / width + 1)
+            .sum();
+        let height = (lines as u16 + 6).min(chunk.height);
+
+        common::draw_button_dialog(
+            f,
+            &app.theme,
+            chunk,
+            height,
+            &msg,
+            vec!["[ Retry ]", "[ Details ]", "[ Dismiss ]"],
+            state.selected,
+        );
+    }
+}
@@ -1,109 +1,294 @@
 use async_trait::async_trait;
-use tui::{
-    layout::{Constraint, Rect},
-    widgets::Row,
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
 };
+use tui::layout::{Constraint, Rect};
+use uuid::Uuid;
 
 use super::*;
-use crate::util;
+use crate::{settings::InstanceSort, util, Instance, IoEvent};
+
+/// A row of the home table
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry {
+    /// The heading of a group, followed by its instances unless collapsed
+    Group(String),
+    Instance(Uuid),
+}
 
 #[derive(Default)]
 pub struct State {
-    selected: usize,
+    /// Kept by what it is rather than where, so that it stays put when the list is sorted or reloaded
+    selected: Option<Entry>,
+    filter: common::Filter,
+    collapsed: HashSet<String>,
+}
+
+impl State {
+    fn position(&self, entries: &[Entry]) -> usize {
+        self.selected
+            .as_ref()
+            .and_then(|selected| entries.iter().position(|entry| entry == selected))
+            .unwrap_or(0)
+    }
+}
+
+/// The rows in order: ungrouped instances first, then each group with its instances
+///
+/// Groups are expanded while filtering, so that every instance can be found.
+fn entries(app: &App) -> Vec<Entry> {
+    let state = &app.state.home;
+    let sort = app.settings.instance_sort;
+    let mut instances: Vec<&Instance> = app.instances.inner.values().collect();
+    instances.sort_by(|a, b| compare(sort, a, b));
+
+    let mut entries = Vec::new();
+    let mut groups: BTreeMap<&str, Vec<Entry>> = BTreeMap::new();
+    for instance in instances {
+        let entry = Entry::Instance(instance.uuid);
+        match &instance.group {
+            Some(group) => groups.entry(group).or_default().push(entry),
+            None => entries.push(entry),
+        }
+    }
+    for (group, instances) in groups {
+        entries.push(Entry::Group(group.to_owned()));
+        if state.filter.is_active() || !state.collapsed.contains(group) {
+            entries.extend(instances);
+        }
+    }
+    entries
+}
+
+fn compare(sort: InstanceSort, a: &Instance, b: &Instance) -> Ordering {
+    let ordering = match sort {
+        InstanceSort::Name => Ordering::Equal,
+        // Never played last
+        InstanceSort::LastPlayed => b.last_played().cmp(&a.last_played()),
+        InstanceSort::MinecraftVersion => {
+            version_numbers(&b.version_id).cmp(&version_numbers(&a.version_id))
+        }
+        InstanceSort::ModCount => b.mods.len().cmp(&a.mods.len()),
+    };
+    ordering.then_with(|| a.name.cmp(&b.name))
+}
+
+/// The numbers in a version id, so that 1.16.5 comes after 1.9
+fn version_numbers(version_id: &str) -> Vec<u64> {
+    version_id
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+fn find_instance<'a>(app: &'a App, uuid: &Uuid) -> Option<&'a Instance> {
+    app.instances
+        .inner
+        .values()
+        .find(|instance| instance.uuid == *uuid)
+}
+
+/// The first column of a row, which is also what the filter matches
+fn label(app: &App, entry: &Entry) -> String {
+    match entry {
+        Entry::Group(group) => {
+            let count = app
+                .instances
+                .inner
+                .values()
+                .filter(|instance| instance.group.as_ref() == Some(group))
+                .count();
+            let collapsed =
+                !app.state.home.filter.is_active() && app.state.home.collapsed.contains(group);
+            format!(
+                "{} {} ({})",
+                if collapsed { "▸" } else { "▾" },
+                group,
+                count
+            )
+        }
+        Entry::Instance(uuid) => match find_instance(app, uuid) {
+            Some(instance) if !instance.tags.is_empty() => {
+                format!("{} #{}", instance.name, instance.tags.join(" #"))
+            }
+            Some(instance) => instance.name.clone(),
+            None => String::new(),
+        },
+    }
+}
+
+/// The instance on the selected row, if it is one
+fn selected_instance(app: &App) -> Option<Instance> {
+    let entries = entries(app);
+    match entries.get(app.state.home.position(&entries))? {
+        Entry::Instance(uuid) => find_instance(app, uuid).cloned(),
+        Entry::Group(_) => None,
+    }
 }
 
 pub struct Impl {}
 
-#[async_trait]
 impl RouteImpl for Impl {
     fn is_modal(&self) -> bool {
         false
     }
-    fn get_help(&self, _app: &App) -> Vec<(&'static str, &'static str)> {
-        vec![
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        app.state.home.filter.get_help(vec![
             ("ESC", "quit"),
             ("↑/↓", "choose instance"),
             ("⏎", "open menu"),
             ("ctrl+N", "new"),
+            ("ctrl+O", "import directory"),
             ("ctrl+R", "remove"),
             ("F2", "rename"),
-        ]
+            ("ctrl+S", "settings"),
+            ("ctrl+Z", "snapshots"),
+            ("ctrl+T", "jobs"),
+            (
+                "S",
+                match app.settings.instance_sort {
+                    InstanceSort::Name => "sorted by name",
+                    InstanceSort::LastPlayed => "sorted by last played",
+                    InstanceSort::MinecraftVersion => "sorted by version",
+                    InstanceSort::ModCount => "sorted by mods",
+                },
+            ),
+        ])
     }
     fn handle_key(&self, key: Key, app: &mut App) {
-        let mut state = &mut app.state.home;
+        let entries = entries(app);
+        let labels: Vec<String> = entries.iter().map(|entry| label(app, entry)).collect();
+        let state = &mut app.state.home;
+        let mut position = state.position(&entries);
+        if state.filter.handle_key(key, &mut position, &labels) {
+            state.selected = entries.get(position).cloned();
+            return;
+        }
         match key {
-            Key::Up => state.selected = util::wrap_dec(state.selected, app.instances.inner.len()),
-            Key::Down => state.selected = util::wrap_inc(state.selected, app.instances.inner.len()),
-            Key::Enter => {
-                let mut instances: Vec<_> = app.instances.inner.iter().collect();
-                instances.sort_by(|x, y| x.0.cmp(&y.0));
-                let instance = instances[state.selected];
-                app.state.instance_menu = instance_menu::State::new(instance.1.clone());
-                app.push_route(Route::InstanceMenu);
+            Key::Enter => match entries.get(position) {
+                Some(Entry::Group(group)) => {
+                    if state.collapsed.contains(group) {
+                        state.collapsed.remove(group);
+                    } else {
+                        state.collapsed.insert(group.clone());
+                    }
+                }
+                Some(Entry::Instance(_)) => {
+                    let instance = selected_instance(app).unwrap();
+                    app.state.instance_menu = instance_menu::State::new(instance);
+                    app.push_route(Route::InstanceMenu);
+                }
+                None => {}
+            },
+            Key::Char('s') => {
+                app.settings.instance_sort = app.settings.instance_sort.next();
+                app.dispatch(IoEvent::SaveSettings);
             }
             Key::Ctrl('n') => {
+                if !app.require_minecraft_version_manifest() {
+                    return;
+                }
                 app.state.new_instance = new_instance::State::default();
                 app.push_route(Route::NewInstance);
             }
+            Key::Ctrl('o') => {
+                app.state.import_directory = import_directory::State::default();
+                app.push_route(Route::ImportDirectory);
+            }
             Key::Ctrl('r') => {
-                let mut instances: Vec<_> = app.instances.inner.iter().collect();
-                instances.sort_by(|x, y| x.0.cmp(&y.0));
-                let instance = instances[state.selected];
-                app.state.remove_instance = remove_instance::State::new(instance.1.clone());
-                app.push_route(Route::RemoveInstance);
+                if let Some(instance) = selected_instance(app) {
+                    app.state.remove_instance = remove_instance::State::new(instance);
+                    app.push_route(Route::RemoveInstance);
+                }
+            }
+            Key::Ctrl('s') => {
+                app.push_route(Route::Settings);
+            }
+            Key::Ctrl('z') => {
+                app.state.snapshots = snapshots::State::new(None);
+                app.dispatch(IoEvent::LoadSnapshots);
+                app.push_route(Route::Snapshots);
+            }
+            Key::Ctrl('t') => {
+                app.state.jobs = Default::default();
+                app.push_route(Route::Jobs);
             }
             Key::F2 => {
-                let mut instances: Vec<_> = app.instances.inner.iter().collect();
-                instances.sort_by(|x, y| x.0.cmp(&y.0));
-                let instance = instances[state.selected];
-                app.state.rename_instance = rename_instance::State::new(instance.1.clone());
-                app.push_route(Route::RenameInstance);
+                if let Some(instance) = selected_instance(app) {
+                    app.state.rename_instance = rename_instance::State::new(instance);
+                    app.push_route(Route::RenameInstance);
+                }
             }
             _ => {}
         }
     }
-    async fn draw(&self, f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
-        let state = &app.state.home;
-
-        let mut instances: Vec<_> = app.instances.inner.iter().collect();
-
-        instances.sort_by(|x, y| x.0.cmp(&y.0));
+    fn handle_esc(&self, app: &mut App) -> bool {
+        app.state.home.filter.handle_esc()
+    }
+}
 
-        let rows: Vec<_> = instances
-            .into_iter()
-            .map(|(name, instance)| {
-                Row::Data(
-                    vec![
-                        name.clone(),
-                        instance.version_id.clone(),
-                        match instance.forge_name.as_ref() {
-                            Some(modloader) => modloader.clone(),
-                            None => String::from("(Vanilla)"),
-                        },
-                        if instance.mods.is_empty() {
-                            String::from("")
-                        } else {
-                            format!("{} mods", instance.mods.len())
-                        },
-                    ]
-                    .into_iter(),
-                )
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let entries = entries(app);
+        let rows: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                let instance = match entry {
+                    Entry::Instance(uuid) => find_instance(app, uuid),
+                    Entry::Group(_) => None,
+                };
+                let mut row = vec![label(app, entry)];
+                if let Some(instance) = instance {
+                    row.push(instance.version_id.clone());
+                    row.push(match instance.forge_name.as_ref() {
+                        Some(modloader) => modloader.clone(),
+                        None => String::from("(Vanilla)"),
+                    });
+                    row.push(if instance.mods.is_empty() {
+                        String::from("")
+                    } else {
+                        format!("{} mods", instance.mods.len())
+                    });
+                    let playtime = instance.playtime();
+                    row.push(if playtime > chrono::Duration::zero() {
+                        util::format_playtime(playtime)
+                    } else {
+                        String::new()
+                    });
+                }
+                row
             })
             .collect();
 
-        common::draw_table(
+        let state = &app.state.home;
+        let cursor = common::draw_filtered_table(
             f,
+            &app.theme,
             chunk,
-            &["   Name", "Minecraft version", "Modloader", "Mods"],
+            &[
+                "   Name",
+                "Minecraft version",
+                "Modloader",
+                "Mods",
+                "Played",
+            ],
             rows,
             &[
-                Constraint::Percentage(40),
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
+                Constraint::Percentage(32),
+                Constraint::Percentage(24),
+                Constraint::Percentage(18),
+                Constraint::Percentage(10),
+                Constraint::Percentage(16),
             ],
             None,
-            Some(state.selected),
+            state.position(&entries),
+            &[],
+            &state.filter,
         );
+        if let Some(cursor) = cursor {
+            app.show_cursor(cursor);
+        }
     }
 }
@@ -0,0 +1,317 @@
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tui::{
+    layout::{Constraint, Direction, Rect},
+    widgets::Row,
+};
+
+use super::*;
+use crate::{
+    import::{self, DetectedVersion, ImportMode},
+    util, IoEvent,
+};
+
+#[derive(Clone, PartialEq)]
+pub enum InnerState {
+    EnterPath,
+    ChooseVersion,
+    EnterName,
+    ChooseMode,
+    Import,
+}
+
+pub struct State {
+    pub inner: InnerState,
+    pub path_input: String,
+    pub versions: Vec<DetectedVersion>,
+    selected_version: usize,
+    pub name_input: String,
+    selected_mode: usize,
+    error: Option<String>,
+    pub progress: Option<util::Progress>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            inner: InnerState::EnterPath,
+            path_input: String::new(),
+            versions: Vec::new(),
+            selected_version: 0,
+            name_input: String::new(),
+            selected_mode: 0,
+            error: Some(String::from("Directory does not exist")),
+            progress: None,
+        }
+    }
+}
+
+impl State {
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(&self.path_input)
+    }
+
+    pub fn version(&self) -> &DetectedVersion {
+        &self.versions[self.selected_version]
+    }
+
+    /// The chosen version, if the versions were detected
+    pub fn chosen_version(&self) -> Option<&DetectedVersion> {
+        self.versions.get(self.selected_version)
+    }
+
+    pub fn mode(&self) -> ImportMode {
+        ImportMode::ALL[self.selected_mode]
+    }
+
+    fn validate_path(&self, app: &App) -> Option<String> {
+        let path = self.path();
+        if !path.is_dir() {
+            return Some(String::from("Directory does not exist"));
+        }
+        let path = path.canonicalize().unwrap_or(path);
+        if path.starts_with(&app.paths.directory.data) {
+            return Some(String::from("Directory is already managed by polyblock"));
+        }
+        if app
+            .instances
+            .inner
+            .values()
+            .any(|instance| instance.directory() == path)
+        {
+            return Some(String::from("Directory is already an instance"));
+        }
+        None
+    }
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        match app.state.import_directory.inner {
+            InnerState::EnterPath | InnerState::EnterName => {
+                vec![("ESC", "cancel"), ("⏎", "continue")]
+            }
+            InnerState::ChooseVersion => {
+                vec![("ESC", "cancel"), ("↑/↓", "move cursor"), ("⏎", "continue")]
+            }
+            InnerState::ChooseMode => {
+                vec![("ESC", "cancel"), ("↑/↓", "move cursor"), ("⏎", "import")]
+            }
+            InnerState::Import => Vec::new(),
+        }
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        match app.state.import_directory.inner {
+            InnerState::EnterPath => {
+                let state = &mut app.state.import_directory;
+                match key {
+                    Key::Char(c) => state.path_input.push(c),
+                    Key::Backspace => {
+                        state.path_input.pop();
+                    }
+                    Key::Enter => {
+                        if state.error.is_some() {
+                            return;
+                        }
+                        let path = state.path();
+                        state.versions = import::detect_versions(&path);
+                        if state.versions.is_empty() {
+                            state.error = Some(String::from(
+                                "Could not find out which version this directory was played with",
+                            ));
+                            return;
+                        }
+                        state.selected_version = 0;
+                        state.name_input = path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().trim_start_matches('.').to_owned())
+                            .unwrap_or_default();
+                        state.inner = InnerState::ChooseVersion;
+                        return;
+                    }
+                    _ => {}
+                }
+                app.state.import_directory.error = app.state.import_directory.validate_path(app);
+            }
+            InnerState::ChooseVersion => {
+                let state = &mut app.state.import_directory;
+                match key {
+                    Key::Up => {
+                        state.selected_version =
+                            util::wrap_dec(state.selected_version, state.versions.len())
+                    }
+                    Key::Down => {
+                        state.selected_version =
+                            util::wrap_inc(state.selected_version, state.versions.len())
+                    }
+                    Key::Enter => {
+                        state.error = None;
+                        state.inner = InnerState::EnterName;
+                    }
+                    _ => {}
+                }
+            }
+            InnerState::EnterName => {
+                let state = &mut app.state.import_directory;
+                match key {
+                    Key::Char(c) => {
+                        state.name_input.push(c);
+                    }
+                    Key::Backspace => {
+                        state.name_input.pop();
+                    }
+                    _ => {}
+                }
+
+                // Validate before handling enter since the name is prefilled
+                if state.name_input.is_empty() {
+                    state.error = Some("You must enter a name!".to_string())
+                } else if app.instances.inner.contains_key(&state.name_input) {
+                    state.error = Some("An instance with that name already exists!".to_string())
+                } else {
+                    state.error = None;
+                }
+
+                if key == Key::Enter && state.error.is_none() {
+                    state.selected_mode = 0;
+                    state.inner = InnerState::ChooseMode;
+                }
+            }
+            InnerState::ChooseMode => {
+                let state = &mut app.state.import_directory;
+                match key {
+                    Key::Up => {
+                        state.selected_mode =
+                            util::wrap_dec(state.selected_mode, ImportMode::ALL.len())
+                    }
+                    Key::Down => {
+                        state.selected_mode =
+                            util::wrap_inc(state.selected_mode, ImportMode::ALL.len())
+                    }
+                    Key::Enter => {
+                        state.inner = InnerState::Import;
+                        app.dispatch(IoEvent::ImportDirectory);
+                    }
+                    _ => {}
+                }
+            }
+            InnerState::Import => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        match &app.state.import_directory.inner {
+            InnerState::EnterPath => {
+                let state = &app.state.import_directory;
+                let cursor = common::draw_input_dialog(
+                    f,
+                    &app.theme,
+                    chunk,
+                    "Path of game directory to import",
+                    &state.path_input,
+                    state.error.as_deref(),
+                );
+                app.show_cursor(cursor);
+            }
+            InnerState::ChooseVersion => draw_choose_version(f, app, chunk),
+            InnerState::EnterName => {
+                let state = &app.state.import_directory;
+                let cursor = common::draw_input_dialog(
+                    f,
+                    &app.theme,
+                    chunk,
+                    "Enter name for the instance",
+                    &state.name_input,
+                    state.error.as_deref(),
+                );
+                app.show_cursor(cursor);
+            }
+            InnerState::ChooseMode => draw_choose_mode(f, app, chunk),
+            InnerState::Import => {
+                common::draw_loading_dialog(
+                    f,
+                    &app.theme,
+                    chunk,
+                    "Importing directory...",
+                    &[app.state.import_directory.progress.as_ref()],
+                )
+                .await
+            }
+        }
+    }
+}
+
+fn draw_choose_version<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+    let state = &app.state.import_directory;
+    let rect = util::centered_rect_dir(
+        Direction::Vertical,
+        state.versions.len() as u16 + 3,
+        util::centered_rect_percentage_dir(Direction::Horizontal, 60, chunk),
+    );
+
+    let rows: Vec<_> = state
+        .versions
+        .iter()
+        .map(|version| {
+            Row::Data(
+                vec![
+                    version.version_id.clone(),
+                    version
+                        .forge_name
+                        .clone()
+                        .unwrap_or_else(|| String::from("(Vanilla)")),
+                    version.source.clone(),
+                ]
+                .into_iter(),
+            )
+        })
+        .collect();
+
+    common::draw_table(
+        f,
+        &app.theme,
+        rect,
+        &["   Minecraft version", "Modloader", "Found in"],
+        rows,
+        &[
+            Constraint::Percentage(30),
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+        ],
+        Some("Choose version"),
+        Some(state.selected_version),
+    )
+}
+
+fn draw_choose_mode<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+    let state = &app.state.import_directory;
+    let rect = util::centered_rect_dir(
+        Direction::Vertical,
+        ImportMode::ALL.len() as u16 + 3,
+        util::centered_rect_percentage_dir(Direction::Horizontal, 40, chunk),
+    );
+
+    let rows: Vec<_> = ImportMode::ALL
+        .iter()
+        .map(|mode| Row::Data(vec![mode.description()].into_iter()))
+        .collect();
+
+    common::draw_table(
+        f,
+        &app.theme,
+        rect,
+        &["   Mode"],
+        rows,
+        &[Constraint::Min(10)],
+        Some("Choose how to import"),
+        Some(state.selected_mode),
+    )
+}
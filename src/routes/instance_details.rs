@@ -0,0 +1,185 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Local, Utc};
+use tui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Row, Text},
+};
+
+use super::*;
+use crate::{util, Instance};
+
+#[derive(Default)]
+pub struct State {
+    pub instance: Option<Instance>,
+    /// An index into the launches, newest first
+    selected: usize,
+}
+
+impl State {
+    pub fn new(instance: Instance) -> Self {
+        Self {
+            instance: Some(instance),
+            selected: 0,
+        }
+    }
+}
+
+/// The instance as it is now, since a session may end while looking at it
+fn current(app: &App) -> Option<&Instance> {
+    let uuid = app.state.instance_details.instance.as_ref()?.uuid;
+    app.instances
+        .inner
+        .values()
+        .find(|instance| instance.uuid == uuid)
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%b %e %Y %H:%M")
+        .to_string()
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, _app: &App) -> Vec<(&'static str, &'static str)> {
+        vec![("ESC", "back"), ("↑/↓", "choose launch")]
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let len = current(app)
+            .map(|instance| instance.sessions.len())
+            .unwrap_or(0);
+        if len == 0 {
+            return;
+        }
+        let state = &mut app.state.instance_details;
+        match key {
+            Key::Up => state.selected = util::wrap_dec(state.selected, len),
+            Key::Down => state.selected = util::wrap_inc(state.selected, len),
+            _ => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let instance = match current(app) {
+            Some(instance) => instance,
+            None => return,
+        };
+
+        let untracked = instance
+            .sessions
+            .iter()
+            .filter(|session| session.ended.is_none())
+            .count();
+        let mut played = format!(
+            "{} over {} launches",
+            util::format_playtime(instance.playtime()),
+            instance.sessions.len()
+        );
+        if untracked > 0 {
+            played.push_str(&format!(" ({} not tracked)", untracked));
+        }
+        let facts = [
+            ("Minecraft version", instance.version_id.clone()),
+            (
+                "Modloader",
+                instance
+                    .forge_name
+                    .clone()
+                    .unwrap_or_else(|| String::from("(Vanilla)")),
+            ),
+            ("Mods", instance.mods.len().to_string()),
+            (
+                "Group",
+                instance
+                    .group
+                    .clone()
+                    .unwrap_or_else(|| String::from("(None)")),
+            ),
+            ("Tags", instance.tags.join(", ")),
+            ("Played", played),
+            (
+                "Last played",
+                instance
+                    .last_played()
+                    .map(format_time)
+                    .unwrap_or_else(|| String::from("Never")),
+            ),
+        ];
+        let text: Vec<Text> = facts
+            .iter()
+            .map(|(name, value)| Text::raw(format!("{:<19}{}\n", name, value)))
+            .collect();
+
+        let rows: Vec<_> = instance
+            .sessions
+            .iter()
+            .rev()
+            .map(|session| {
+                Row::Data(
+                    vec![
+                        format_time(session.started),
+                        session
+                            .ended
+                            .map(format_time)
+                            .unwrap_or_else(|| String::from("(not tracked)")),
+                        session
+                            .duration()
+                            .map(util::format_playtime)
+                            .unwrap_or_default(),
+                    ]
+                    .into_iter(),
+                )
+            })
+            .collect();
+        let selected = if rows.is_empty() {
+            None
+        } else {
+            Some(app.state.instance_details.selected)
+        };
+
+        let rect = util::centered_rect_percentage(80, 70, chunk);
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(facts.len() as u16 + 2),
+                    Constraint::Min(0),
+                ]
+                .as_ref(),
+            )
+            .split(rect);
+
+        f.render_widget(Clear, layout[0]);
+        f.render_widget(
+            Paragraph::new(text.iter()).style(app.theme.text).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Plain)
+                    .border_style(app.theme.border)
+                    .title(&instance.name),
+            ),
+            layout[0],
+        );
+        common::draw_table(
+            f,
+            &app.theme,
+            layout[1],
+            &["   Started", "Ended", "Played"],
+            rows,
+            &[
+                Constraint::Percentage(40),
+                Constraint::Percentage(40),
+                Constraint::Percentage(20),
+            ],
+            Some("Launches"),
+            selected,
+        );
+    }
+}
@@ -2,7 +2,6 @@ use async_trait::async_trait;
 use std::fmt;
 use tui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
     widgets::{Block, BorderType, Borders, Clear, List, ListState, Text},
 };
 
@@ -11,13 +10,20 @@ use crate::{util, Instance, IoEvent};
 
 pub enum MenuOption {
     Play,
+    PlayStayOpen,
     PlayShowLog,            // TODO
     ManageMods,             // TODO
     ChangeMinecraftVersion, // TODO
     ChangeForgeVersion,
     AddForge,
     RemoveForge,
+    Details,
     OpenDirectory,
+    Worlds,
+    Packs,
+    Snapshots,
+    Settings,
+    Duplicate,
     Rename,
     Remove,
 }
@@ -26,13 +32,20 @@ impl fmt::Display for MenuOption {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             MenuOption::Play => write!(f, "Play"),
+            MenuOption::PlayStayOpen => write!(f, "Play (stay open)"),
             MenuOption::PlayShowLog => write!(f, "Play (show log)"),
             MenuOption::ManageMods => write!(f, "Manage mods"),
             MenuOption::ChangeMinecraftVersion => write!(f, "Change minecraft version"),
             MenuOption::ChangeForgeVersion => write!(f, "Change forge version"),
             MenuOption::AddForge => write!(f, "Add forge"),
             MenuOption::RemoveForge => write!(f, "Remove forge"),
+            MenuOption::Details => write!(f, "Details"),
             MenuOption::OpenDirectory => write!(f, "Open directory"),
+            MenuOption::Worlds => write!(f, "Worlds"),
+            MenuOption::Packs => write!(f, "Resource & shader packs"),
+            MenuOption::Snapshots => write!(f, "Snapshots"),
+            MenuOption::Settings => write!(f, "Java & window settings"),
+            MenuOption::Duplicate => write!(f, "Duplicate"),
             MenuOption::Rename => write!(f, "Rename"),
             MenuOption::Remove => write!(f, "Remove"),
         }
@@ -43,10 +56,17 @@ impl MenuOption {
     pub fn vanilla() -> Vec<Self> {
         vec![
             Self::Play,
+            Self::PlayStayOpen,
             Self::PlayShowLog,
             Self::ChangeMinecraftVersion,
             Self::AddForge,
+            Self::Details,
             Self::OpenDirectory,
+            Self::Worlds,
+            Self::Packs,
+            Self::Snapshots,
+            Self::Settings,
+            Self::Duplicate,
             Self::Rename,
             Self::Remove,
         ]
@@ -55,12 +75,19 @@ impl MenuOption {
     pub fn forge() -> Vec<Self> {
         vec![
             Self::Play,
+            Self::PlayStayOpen,
             Self::PlayShowLog,
             Self::ManageMods,
             Self::ChangeMinecraftVersion,
             Self::ChangeForgeVersion,
             Self::RemoveForge,
+            Self::Details,
             Self::OpenDirectory,
+            Self::Worlds,
+            Self::Packs,
+            Self::Snapshots,
+            Self::Settings,
+            Self::Duplicate,
             Self::Rename,
             Self::Remove,
         ]
@@ -72,6 +99,7 @@ pub struct State {
     pub selected: usize,
     pub options: Vec<MenuOption>,
     pub instance: Option<Instance>,
+    pub progress: Option<util::Progress>,
 }
 
 impl State {
@@ -84,21 +112,27 @@ impl State {
                 MenuOption::vanilla()
             },
             instance: Some(instance),
+            progress: None,
         }
     }
 }
 
 pub struct Impl {}
 
-#[async_trait]
 impl RouteImpl for Impl {
     fn is_modal(&self) -> bool {
         true
     }
-    fn get_help(&self, _app: &App) -> Vec<(&'static str, &'static str)> {
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        if app.state.instance_menu.progress.is_some() {
+            return Vec::new();
+        }
         vec![("ESC", "back"), ("↑/↓", "move cursor"), ("⏎", "select")]
     }
     fn handle_key(&self, key: Key, app: &mut App) {
+        if app.state.instance_menu.progress.is_some() {
+            return;
+        }
         match key {
             Key::Up => {
                 app.state.instance_menu.selected = util::wrap_dec(
@@ -114,12 +148,52 @@ impl RouteImpl for Impl {
             }
             Key::Enter => match app.state.instance_menu.options[app.state.instance_menu.selected] {
                 MenuOption::Play => app.dispatch(IoEvent::PlayThenQuit),
+                MenuOption::PlayStayOpen => app.dispatch(IoEvent::Play),
+                MenuOption::Details => {
+                    let instance = app.state.instance_menu.instance.clone().unwrap();
+                    app.state.instance_details = instance_details::State::new(instance);
+                    app.pop_route();
+                    app.push_route(Route::InstanceDetails);
+                }
                 MenuOption::OpenDirectory => {
                     let instance = app.state.instance_menu.instance.as_ref().unwrap();
                     let directory = instance.directory();
                     open::that(directory).unwrap();
                     app.pop_route();
                 }
+                MenuOption::Worlds => {
+                    let instance = app.state.instance_menu.instance.clone().unwrap();
+                    app.state.worlds = worlds::State::new(instance);
+                    app.dispatch(IoEvent::LoadWorlds);
+                    app.pop_route();
+                    app.push_route(Route::Worlds);
+                }
+                MenuOption::Packs => {
+                    let instance = app.state.instance_menu.instance.clone().unwrap();
+                    app.state.packs = packs::State::new(instance);
+                    app.dispatch(IoEvent::LoadPacks);
+                    app.pop_route();
+                    app.push_route(Route::Packs);
+                }
+                MenuOption::Snapshots => {
+                    let uuid = app.state.instance_menu.instance.as_ref().unwrap().uuid;
+                    app.state.snapshots = snapshots::State::new(Some(uuid));
+                    app.dispatch(IoEvent::LoadSnapshots);
+                    app.pop_route();
+                    app.push_route(Route::Snapshots);
+                }
+                MenuOption::Settings => {
+                    let instance = app.state.instance_menu.instance.clone().unwrap();
+                    app.state.instance_settings = instance_settings::State::new(instance);
+                    app.pop_route();
+                    app.push_route(Route::InstanceSettings);
+                }
+                MenuOption::Duplicate => {
+                    let instance = app.state.instance_menu.instance.clone().unwrap();
+                    app.state.duplicate_instance = duplicate_instance::State::new(instance);
+                    app.pop_route();
+                    app.push_route(Route::DuplicateInstance);
+                }
                 MenuOption::Rename => {
                     let instance = app.state.instance_menu.instance.clone().unwrap();
                     app.state.rename_instance = rename_instance::State::new(instance);
@@ -133,12 +207,18 @@ impl RouteImpl for Impl {
                     app.push_route(Route::RemoveInstance);
                 }
                 MenuOption::AddForge => {
+                    if !app.require_online("Installing forge") {
+                        return;
+                    }
                     let instance = app.state.instance_menu.instance.clone().unwrap();
                     app.state.add_forge = add_forge::State::new(instance);
                     app.pop_route();
                     app.push_route(Route::AddForge);
                 }
                 MenuOption::ChangeForgeVersion => {
+                    if !app.require_online("Installing forge") {
+                        return;
+                    }
                     let instance = app.state.instance_menu.instance.clone().unwrap();
                     app.state.add_forge = add_forge::State::new(instance);
                     app.pop_route();
@@ -148,6 +228,9 @@ impl RouteImpl for Impl {
                     app.dispatch(IoEvent::RemoveForge);
                 }
                 MenuOption::ChangeMinecraftVersion => {
+                    if !app.require_minecraft_version_manifest() {
+                        return;
+                    }
                     let instance = app.state.instance_menu.instance.clone().unwrap();
                     app.state.change_version = change_version::State::new(instance);
                     app.dispatch(IoEvent::ChangeVersionFetchMinecraftVersionManifest);
@@ -159,8 +242,23 @@ impl RouteImpl for Impl {
             _ => {}
         }
     }
-    async fn draw(&self, f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
         let state = &app.state.instance_menu;
+        if let Some(progress) = state.progress.as_ref() {
+            common::draw_loading_dialog(
+                f,
+                &app.theme,
+                chunk,
+                "Preparing java runtime...",
+                &[Some(progress)],
+            )
+            .await;
+            return;
+        }
         let instance = state.instance.as_ref().unwrap();
         let instance_name = instance.name.clone();
 
@@ -188,10 +286,11 @@ impl RouteImpl for Impl {
                     Block::default()
                         .title(&instance_name)
                         .borders(Borders::ALL)
-                        .border_type(BorderType::Plain),
+                        .border_type(BorderType::Plain)
+                        .border_style(app.theme.border),
                 )
-                .style(Style::default())
-                .highlight_style(Style::default().fg(Color::Blue).modifier(Modifier::BOLD))
+                .style(app.theme.text)
+                .highlight_style(app.theme.highlight)
                 .highlight_symbol(">> "),
             rect,
             &mut list_state,
@@ -0,0 +1,316 @@
+use async_trait::async_trait;
+use std::{fmt, path::PathBuf};
+use tui::{
+    layout::{Constraint, Rect},
+    widgets::Row,
+};
+
+use super::*;
+use crate::{instance::Resolution, settings::Settings, util, Instance, IoEvent};
+
+#[derive(Clone, Copy)]
+pub enum Field {
+    MemoryMax,
+    MemoryMin,
+    JavaArgs,
+    JavaExec,
+    Resolution,
+    Group,
+    Tags,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Field::MemoryMax => write!(f, "Maximum memory (MiB)"),
+            Field::MemoryMin => write!(f, "Minimum memory (MiB)"),
+            Field::JavaArgs => write!(f, "Extra JVM arguments"),
+            Field::JavaExec => write!(f, "Java executable"),
+            Field::Resolution => write!(f, "Window size"),
+            Field::Group => write!(f, "Group"),
+            Field::Tags => write!(f, "Tags (comma separated)"),
+        }
+    }
+}
+
+impl Field {
+    const ALL: [Field; 7] = [
+        Field::MemoryMax,
+        Field::MemoryMin,
+        Field::JavaArgs,
+        Field::JavaExec,
+        Field::Resolution,
+        Field::Group,
+        Field::Tags,
+    ];
+
+    fn value(&self, instance: &Instance, settings: &Settings) -> String {
+        match self {
+            Field::MemoryMax => match instance.memory_max {
+                Some(memory) => memory.to_string(),
+                None => format!("(Default: {})", settings.memory),
+            },
+            Field::MemoryMin => match instance.memory_min {
+                Some(memory) => memory.to_string(),
+                None => String::from("(None)"),
+            },
+            Field::JavaArgs => match &instance.java_args {
+                Some(java_args) => java_args.clone(),
+                None => String::from("(Launcher defaults)"),
+            },
+            Field::JavaExec => match &instance.java_exec {
+                Some(java_exec) => java_exec.display().to_string(),
+                None => String::from("(Automatic)"),
+            },
+            Field::Resolution => match instance.resolution {
+                Some(resolution) => format!("{}x{}", resolution.width, resolution.height),
+                None => String::from("(Launcher default)"),
+            },
+            Field::Group => match &instance.group {
+                Some(group) => group.clone(),
+                None => String::from("(None)"),
+            },
+            Field::Tags => {
+                if instance.tags.is_empty() {
+                    String::from("(None)")
+                } else {
+                    instance.tags.join(", ")
+                }
+            }
+        }
+    }
+
+    fn input(&self, instance: &Instance) -> String {
+        match self {
+            Field::MemoryMax => instance.memory_max.map(|m| m.to_string()),
+            Field::MemoryMin => instance.memory_min.map(|m| m.to_string()),
+            Field::JavaArgs => instance.java_args.clone(),
+            Field::JavaExec => instance.java_exec.as_ref().map(|p| p.display().to_string()),
+            Field::Resolution => instance
+                .resolution
+                .map(|r| format!("{}x{}", r.width, r.height)),
+            Field::Group => instance.group.clone(),
+            Field::Tags => Some(instance.tags.join(", ")),
+        }
+        .unwrap_or_default()
+    }
+
+    /// Validate the input against the rest of the instance, empty input always meaning "unset"
+    ///
+    /// The minimum memory is compared with the default when the maximum is unset, as that is what
+    /// the instance launches with.
+    fn validate(
+        &self,
+        input: &str,
+        instance: &Instance,
+        settings: &Settings,
+    ) -> Result<(), String> {
+        if input.is_empty() {
+            return Ok(());
+        }
+        match self {
+            Field::MemoryMax | Field::MemoryMin => {
+                let memory = input
+                    .parse::<u64>()
+                    .map_err(|_| String::from("Must be a whole number"))?;
+                if memory < Settings::MIN_MEMORY {
+                    return Err(format!("Must be at least {} MiB", Settings::MIN_MEMORY));
+                }
+                let max = instance.memory_max.unwrap_or(settings.memory);
+                match (self, instance.memory_min) {
+                    (Field::MemoryMax, Some(min)) if memory < min => {
+                        Err(String::from("Must not be less than the minimum memory"))
+                    }
+                    (Field::MemoryMin, _) if memory > max => Err(format!(
+                        "Must not be more than the maximum memory ({})",
+                        max
+                    )),
+                    _ => Ok(()),
+                }
+            }
+            Field::JavaArgs => {
+                if input.contains("-Xmx") || input.contains("-Xms") {
+                    Err(String::from("Set memory using the memory options instead"))
+                } else {
+                    Ok(())
+                }
+            }
+            Field::JavaExec => {
+                if PathBuf::from(input).is_file() {
+                    Ok(())
+                } else {
+                    Err(String::from("File does not exist"))
+                }
+            }
+            Field::Resolution => parse_resolution(input)
+                .map(|_| ())
+                .ok_or_else(|| String::from("Must be in the form WIDTHxHEIGHT")),
+            Field::Group | Field::Tags => Ok(()),
+        }
+    }
+
+    /// Apply an already validated input
+    fn apply(&self, input: &str, instance: &mut Instance) {
+        let input = if input.is_empty() { None } else { Some(input) };
+        match self {
+            Field::MemoryMax => instance.memory_max = input.map(|i| i.parse().unwrap()),
+            Field::MemoryMin => instance.memory_min = input.map(|i| i.parse().unwrap()),
+            Field::JavaArgs => instance.java_args = input.map(String::from),
+            Field::JavaExec => instance.java_exec = input.map(PathBuf::from),
+            Field::Resolution => instance.resolution = input.and_then(parse_resolution),
+            Field::Group => {
+                instance.group = input
+                    .map(str::trim)
+                    .filter(|group| !group.is_empty())
+                    .map(String::from)
+            }
+            Field::Tags => {
+                instance.tags.clear();
+                for tag in input.unwrap_or_default().split(',').map(str::trim) {
+                    if !tag.is_empty() && !instance.tags.iter().any(|t| t == tag) {
+                        instance.tags.push(tag.to_owned());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn parse_resolution(input: &str) -> Option<Resolution> {
+    let mut parts = input.splitn(2, 'x');
+    let width = parts.next()?.trim().parse().ok()?;
+    let height = parts.next()?.trim().parse().ok()?;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    Some(Resolution { width, height })
+}
+
+#[derive(Default)]
+pub struct State {
+    pub instance: Option<Instance>,
+    selected: usize,
+    editing: bool,
+    input: String,
+    error: Option<String>,
+}
+
+impl State {
+    pub fn new(instance: Instance) -> Self {
+        Self {
+            instance: Some(instance),
+            ..Default::default()
+        }
+    }
+
+    /// Start typing a new value for the selected field
+    pub fn edit_selected(&mut self, settings: &Settings) {
+        let field = Field::ALL[self.selected];
+        let instance = self.instance.as_ref().unwrap();
+        self.input = field.input(instance);
+        self.error = field.validate(&self.input, instance, settings).err();
+        self.editing = true;
+    }
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        if app.state.instance_settings.editing {
+            vec![("ESC", "back"), ("⏎", "save")]
+        } else {
+            vec![("ESC", "back"), ("↑/↓", "choose setting"), ("⏎", "edit")]
+        }
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let state = &mut app.state.instance_settings;
+        let field = Field::ALL[state.selected];
+        let instance = state.instance.as_mut().unwrap();
+        if state.editing {
+            match key {
+                Key::Char(c) => state.input.push(c),
+                Key::Backspace => {
+                    state.input.pop();
+                }
+                Key::Enter => {
+                    if state.error.is_none() {
+                        field.apply(&state.input, instance);
+                        state.editing = false;
+                        app.dispatch(IoEvent::SaveInstanceSettings);
+                    }
+                    return;
+                }
+                _ => {}
+            }
+            state.error = field.validate(&state.input, instance, &app.settings).err();
+        } else {
+            match key {
+                Key::Up => state.selected = util::wrap_dec(state.selected, Field::ALL.len()),
+                Key::Down => state.selected = util::wrap_inc(state.selected, Field::ALL.len()),
+                Key::Enter => match field {
+                    Field::JavaExec => {
+                        let major_version = app
+                            .launcher
+                            .java_version(&instance.version_id)
+                            .map(|java_version| java_version.major_version);
+                        app.state.choose_java = choose_java::State::new(major_version);
+                        app.push_route(Route::ChooseJava);
+                    }
+                    _ => state.edit_selected(&app.settings),
+                },
+                _ => {}
+            }
+        }
+    }
+    fn handle_esc(&self, app: &mut App) -> bool {
+        let state = &mut app.state.instance_settings;
+        if state.editing {
+            state.editing = false;
+            return true;
+        }
+        false
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let rect = util::centered_rect_percentage(80, 50, chunk);
+        let state = &app.state.instance_settings;
+        let instance = state.instance.as_ref().unwrap();
+
+        let rows: Vec<_> = Field::ALL
+            .iter()
+            .map(|field| {
+                Row::Data(vec![field.to_string(), field.value(instance, &app.settings)].into_iter())
+            })
+            .collect();
+
+        common::draw_table(
+            f,
+            &app.theme,
+            rect,
+            &["   Setting", "Value"],
+            rows,
+            &[Constraint::Percentage(40), Constraint::Percentage(60)],
+            Some(&instance.name),
+            Some(state.selected),
+        );
+
+        if state.editing {
+            let cursor = common::draw_input_dialog(
+                f,
+                &app.theme,
+                chunk,
+                &Field::ALL[state.selected].to_string(),
+                &state.input,
+                state.error.as_deref(),
+            );
+            app.show_cursor(cursor);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use tui::{
+    layout::{Constraint, Rect},
+    widgets::Row,
+};
+
+use super::*;
+use crate::{jobs::Status, util};
+
+#[derive(Default)]
+pub struct State {
+    pub selected: usize,
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, _app: &App) -> Vec<(&'static str, &'static str)> {
+        vec![("ESC", "back"), ("↑/↓", "choose job"), ("C", "clear ended")]
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let len = app.jobs.list().len();
+        let state = &mut app.state.jobs;
+        match key {
+            Key::Up => state.selected = util::wrap_dec(state.selected, len),
+            Key::Down => state.selected = util::wrap_inc(state.selected, len),
+            Key::Char('c') => {
+                app.jobs.clear_ended();
+                state.selected = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let mut rows = Vec::new();
+        // Newest first
+        for job in app.jobs.list().iter().rev() {
+            let status = match job.status {
+                Status::Queued => "Queued",
+                Status::Running => "Running",
+                Status::Finished => "Done",
+                Status::Failed => "Failed",
+                Status::Cancelled => "Cancelled",
+            };
+            let progress = match (&job.progress, job.status) {
+                (Some(pb), Status::Running) => {
+                    format!("{:>3.0}% {}", pb.get().await * 100.0, pb.get_msg().await)
+                }
+                _ => String::new(),
+            };
+            rows.push(Row::Data(
+                vec![
+                    job.started.format("%H:%M:%S").to_string(),
+                    status.to_owned(),
+                    job.title.clone(),
+                    progress,
+                ]
+                .into_iter(),
+            ));
+        }
+
+        let state = &mut app.state.jobs;
+        if state.selected >= rows.len() {
+            state.selected = rows.len().saturating_sub(1);
+        }
+        let selected = if rows.is_empty() {
+            None
+        } else {
+            Some(state.selected)
+        };
+
+        let title = format!("Jobs ({} running)", app.jobs.running());
+        let rect = util::centered_rect_percentage(90, 60, chunk);
+        common::draw_table(
+            f,
+            &app.theme,
+            rect,
+            &["   Started", "Status", "Job", "Progress"],
+            rows,
+            &[
+                Constraint::Length(11),
+                Constraint::Length(9),
+                Constraint::Percentage(35),
+                Constraint::Percentage(50),
+            ],
+            Some(&title),
+            selected,
+        );
+    }
+}
@@ -1,23 +1,46 @@
 pub mod add_forge;
 pub mod change_version;
+pub mod choose_java;
 mod common;
+pub mod duplicate_instance;
+pub mod error;
 pub mod home;
+pub mod import_directory;
+pub mod instance_details;
 pub mod instance_menu;
+pub mod instance_settings;
+pub mod jobs;
+pub mod mod_store;
 pub mod new_instance;
+pub mod packs;
 pub mod remove_instance;
 pub mod rename_instance;
+pub mod settings;
+pub mod snapshots;
+mod version_picker;
+pub mod world_backups;
+pub mod worlds;
 
 use async_trait::async_trait;
-use tui::layout::Rect;
+use tui::{backend::Backend, layout::Rect};
 
 use crate::{ui::UiFrame, App, Key};
 
-#[async_trait]
 pub trait RouteImpl {
     fn is_modal(&self) -> bool;
     fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)>;
     fn handle_key(&self, key: Key, app: &mut App);
-    async fn draw(&self, f: &mut UiFrame<'_>, app: &mut App, chunk: Rect);
+    /// Esc closes the route unless this handles it, returning true
+    fn handle_esc(&self, _app: &mut App) -> bool {
+        false
+    }
+}
+
+/// Drawing is kept apart from [`RouteImpl`] so that it can be generic over the backend, while key
+/// handling stays usable without one
+#[async_trait]
+pub trait RouteDraw<B: Backend + Send> {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect);
 }
 
 #[derive(Clone)]
@@ -25,10 +48,23 @@ pub enum Route {
     Home,
     RenameInstance,
     RemoveInstance,
+    DuplicateInstance,
     NewInstance,
     InstanceMenu,
+    InstanceDetails,
+    InstanceSettings,
     AddForge,
     ChangeVersion,
+    ChooseJava,
+    Settings,
+    Worlds,
+    WorldBackups,
+    Snapshots,
+    Packs,
+    ImportDirectory,
+    ModStore,
+    Jobs,
+    Error,
 }
 
 impl Route {
@@ -39,10 +75,50 @@ impl Route {
             Home => Box::new(home::Impl {}),
             RenameInstance => Box::new(rename_instance::Impl {}),
             RemoveInstance => Box::new(remove_instance::Impl {}),
+            DuplicateInstance => Box::new(duplicate_instance::Impl {}),
+            NewInstance => Box::new(new_instance::Impl {}),
+            InstanceMenu => Box::new(instance_menu::Impl {}),
+            InstanceDetails => Box::new(instance_details::Impl {}),
+            InstanceSettings => Box::new(instance_settings::Impl {}),
+            AddForge => Box::new(add_forge::Impl {}),
+            ChangeVersion => Box::new(change_version::Impl {}),
+            ChooseJava => Box::new(choose_java::Impl {}),
+            Settings => Box::new(settings::Impl {}),
+            Worlds => Box::new(worlds::Impl {}),
+            WorldBackups => Box::new(world_backups::Impl {}),
+            Snapshots => Box::new(snapshots::Impl {}),
+            Packs => Box::new(packs::Impl {}),
+            ImportDirectory => Box::new(import_directory::Impl {}),
+            ModStore => Box::new(mod_store::Impl {}),
+            Jobs => Box::new(jobs::Impl {}),
+            Error => Box::new(error::Impl {}),
+        }
+    }
+
+    pub fn get_draw<B: Backend + Send>(&self) -> Box<dyn RouteDraw<B>> {
+        use Route::*;
+
+        match self {
+            Home => Box::new(home::Impl {}),
+            RenameInstance => Box::new(rename_instance::Impl {}),
+            RemoveInstance => Box::new(remove_instance::Impl {}),
+            DuplicateInstance => Box::new(duplicate_instance::Impl {}),
             NewInstance => Box::new(new_instance::Impl {}),
             InstanceMenu => Box::new(instance_menu::Impl {}),
+            InstanceDetails => Box::new(instance_details::Impl {}),
+            InstanceSettings => Box::new(instance_settings::Impl {}),
             AddForge => Box::new(add_forge::Impl {}),
             ChangeVersion => Box::new(change_version::Impl {}),
+            ChooseJava => Box::new(choose_java::Impl {}),
+            Settings => Box::new(settings::Impl {}),
+            Worlds => Box::new(worlds::Impl {}),
+            WorldBackups => Box::new(world_backups::Impl {}),
+            Snapshots => Box::new(snapshots::Impl {}),
+            Packs => Box::new(packs::Impl {}),
+            ImportDirectory => Box::new(import_directory::Impl {}),
+            ModStore => Box::new(mod_store::Impl {}),
+            Jobs => Box::new(jobs::Impl {}),
+            Error => Box::new(error::Impl {}),
         }
     }
 }
@@ -52,8 +128,21 @@ pub struct State {
     pub home: home::State,
     pub rename_instance: rename_instance::State,
     pub remove_instance: remove_instance::State,
+    pub duplicate_instance: duplicate_instance::State,
     pub new_instance: new_instance::State,
     pub instance_menu: instance_menu::State,
+    pub instance_details: instance_details::State,
+    pub instance_settings: instance_settings::State,
     pub add_forge: add_forge::State,
     pub change_version: change_version::State,
+    pub choose_java: choose_java::State,
+    pub settings: settings::State,
+    pub worlds: worlds::State,
+    pub world_backups: world_backups::State,
+    pub snapshots: snapshots::State,
+    pub packs: packs::State,
+    pub import_directory: import_directory::State,
+    pub mod_store: mod_store::State,
+    pub jobs: jobs::State,
+    pub error: error::State,
 }
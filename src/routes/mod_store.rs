@@ -0,0 +1,133 @@
+use async_trait::async_trait;
+use tui::{
+    layout::{Constraint, Rect},
+    widgets::Row,
+};
+
+use super::*;
+use crate::{mods::ModStoreStats, util, IoEvent};
+
+#[derive(Clone, PartialEq)]
+pub enum InnerState {
+    Loading,
+    Overview,
+    Working,
+}
+
+pub struct State {
+    pub inner: InnerState,
+    pub stats: ModStoreStats,
+    /// Outcome of the last action
+    pub message: Option<String>,
+    pub progress: Option<util::Progress>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            inner: InnerState::Loading,
+            stats: Default::default(),
+            message: None,
+            progress: None,
+        }
+    }
+}
+
+impl State {
+    pub fn set_stats(&mut self, stats: ModStoreStats) {
+        self.stats = stats;
+        self.inner = InnerState::Overview;
+        self.progress = None;
+    }
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        match app.state.mod_store.inner {
+            InnerState::Overview => vec![
+                ("ESC", "back"),
+                ("A", "store all instances"),
+                ("G", "collect garbage"),
+                ("V", "verify"),
+            ],
+            InnerState::Loading | InnerState::Working => Vec::new(),
+        }
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let state = &mut app.state.mod_store;
+        if state.inner != InnerState::Overview {
+            return;
+        }
+        let io_event = match key {
+            Key::Char('a') => IoEvent::AddToModStore,
+            Key::Char('g') => IoEvent::CollectModStoreGarbage,
+            Key::Char('v') => IoEvent::VerifyModStore,
+            _ => return,
+        };
+        state.message = None;
+        state.inner = InnerState::Working;
+        app.dispatch(io_event);
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let state = &app.state.mod_store;
+        match state.inner {
+            InnerState::Loading => {
+                common::draw_loading_dialog(f, &app.theme, chunk, "Reading mod store...", &[])
+                    .await;
+                return;
+            }
+            InnerState::Working => {
+                common::draw_loading_dialog(
+                    f,
+                    &app.theme,
+                    chunk,
+                    "Working on mod store...",
+                    &[state.progress.as_ref()],
+                )
+                .await;
+                return;
+            }
+            InnerState::Overview => {}
+        }
+
+        let stats = &state.stats;
+        let mut rows = vec![
+            (
+                "Enabled",
+                String::from(if app.settings.mod_store { "Yes" } else { "No" }),
+            ),
+            ("Stored jars", stats.jars.to_string()),
+            ("Size", util::format_size(stats.size)),
+            ("References", stats.references.to_string()),
+            ("Saved", util::format_size(stats.saved)),
+        ];
+        if let Some(message) = &state.message {
+            rows.push(("Last result", message.clone()));
+        }
+        let rows: Vec<_> = rows
+            .into_iter()
+            .map(|(label, value)| Row::Data(vec![label.to_owned(), value].into_iter()))
+            .collect();
+
+        let rect = util::centered_rect_percentage(60, 40, chunk);
+        common::draw_table(
+            f,
+            &app.theme,
+            rect,
+            &["   Mod store", ""],
+            rows,
+            &[Constraint::Percentage(30), Constraint::Percentage(70)],
+            Some("Mod store"),
+            None,
+        );
+    }
+}
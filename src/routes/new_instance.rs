@@ -1,8 +1,5 @@
 use async_trait::async_trait;
-use tui::{
-    layout::{Constraint, Rect},
-    widgets::Row,
-};
+use tui::layout::{Constraint, Rect};
 
 use super::*;
 use crate::{forge, minecraft, util, IoEvent};
@@ -29,6 +26,9 @@ pub struct State {
     pub chosen_forge_version: Option<forge::VersionManifestVersion>,
     pub progress_main: Option<util::Progress>,
     pub progress_sub: Option<util::Progress>,
+    pub confirm_cancel: common::ConfirmCancel,
+    pub version_picker: version_picker::VersionPicker,
+    filter: common::Filter,
 }
 
 impl Default for State {
@@ -42,13 +42,15 @@ impl Default for State {
             chosen_forge_version: None,
             progress_main: None,
             progress_sub: None,
+            confirm_cancel: Default::default(),
+            version_picker: Default::default(),
+            filter: Default::default(),
         }
     }
 }
 
 pub struct Impl {}
 
-#[async_trait]
 impl RouteImpl for Impl {
     fn is_modal(&self) -> bool {
         true
@@ -61,12 +63,7 @@ impl RouteImpl for Impl {
                 ("⏎", "continue"),
             ],
             InnerState::FetchMinecraftVersionManifest => Vec::new(),
-            InnerState::ChooseMinecraftVersion => vec![
-                ("ESC", "cancel"),
-                ("↑↓", "choose version"),
-                ("PgUp/PgDn", "move cursor 25"),
-                ("⏎", "select"),
-            ],
+            InnerState::ChooseMinecraftVersion => app.state.new_instance.version_picker.get_help(),
             InnerState::ChooseForge => vec![
                 ("ESC", "cancel"),
                 ("←/→", "choose option"),
@@ -76,13 +73,13 @@ impl RouteImpl for Impl {
             ],
             InnerState::ForgeNotice => vec![("⏎", "continue"), ("ESC", "cancel")],
             InnerState::FetchForgeVersionManifest => Vec::new(),
-            InnerState::ChooseForgeVersion => vec![
+            InnerState::ChooseForgeVersion => app.state.new_instance.filter.get_help(vec![
                 ("ESC", "cancel"),
                 ("↑↓", "choose version"),
                 ("PgUp/PgDn", "move cursor 25"),
                 ("⏎", "select"),
-            ],
-            InnerState::Install => Vec::new(),
+            ]),
+            InnerState::Install => app.state.new_instance.confirm_cancel.get_help(),
         }
     }
     fn handle_key(&self, key: Key, app: &mut App) {
@@ -122,43 +119,12 @@ impl RouteImpl for Impl {
             }
             InnerState::FetchMinecraftVersionManifest => {}
             InnerState::ChooseMinecraftVersion => {
-                let versions_len = app
-                    .minecraft_version_manifest
-                    .as_ref()
-                    .unwrap()
-                    .versions
-                    .len();
-                match key {
-                    Key::Up => {
-                        app.state.new_instance.selected =
-                            util::wrap_dec(app.state.new_instance.selected, versions_len)
-                    }
-                    Key::Down => {
-                        app.state.new_instance.selected =
-                            util::wrap_inc(app.state.new_instance.selected, versions_len)
-                    }
-                    Key::PageUp => {
-                        app.state.new_instance.selected =
-                            util::wrap_sub(app.state.new_instance.selected, versions_len, 25)
-                    }
-                    Key::PageDown => {
-                        app.state.new_instance.selected =
-                            util::wrap_add(app.state.new_instance.selected, versions_len, 25)
-                    }
-                    Key::Enter => {
-                        app.state.new_instance.chosen_minecraft_version = Some(
-                            app.minecraft_version_manifest
-                                .as_ref()
-                                .unwrap()
-                                .versions
-                                .get(app.state.new_instance.selected)
-                                .unwrap()
-                                .clone(),
-                        );
-                        app.state.new_instance.selected = 0;
-                        app.state.new_instance.inner = InnerState::ChooseForge;
-                    }
-                    _ => {}
+                let manifest = app.minecraft_version_manifest.as_ref().unwrap();
+                let state = &mut app.state.new_instance;
+                if let Some(version) = state.version_picker.handle_key(key, manifest) {
+                    state.chosen_minecraft_version = Some(version);
+                    state.selected = 0;
+                    state.inner = InnerState::ChooseForge;
                 }
             }
             InnerState::ChooseForge => match key {
@@ -171,6 +137,9 @@ impl RouteImpl for Impl {
                         util::wrap_inc(app.state.new_instance.selected, 2)
                 }
                 Key::Char('y') => {
+                    if !app.require_online("Installing forge") || !has_forge_builds(app) {
+                        return;
+                    }
                     app.state.new_instance.inner = InnerState::ForgeNotice;
                     app.state.new_instance.selected = 0;
                 }
@@ -181,6 +150,9 @@ impl RouteImpl for Impl {
                 }
                 Key::Enter => {
                     if app.state.new_instance.selected == 0 {
+                        if !app.require_online("Installing forge") || !has_forge_builds(app) {
+                            return;
+                        }
                         app.state.new_instance.inner = InnerState::ForgeNotice;
                     } else {
                         app.dispatch(IoEvent::NewInstance);
@@ -199,74 +171,55 @@ impl RouteImpl for Impl {
             },
             InnerState::FetchForgeVersionManifest => {}
             InnerState::ChooseForgeVersion => {
-                let versions_len = app
+                let versions = app
                     .forge_version_manifest
                     .as_ref()
                     .unwrap()
-                    .versions
-                    .iter()
-                    .filter(|version| {
-                        version.game_version
-                            == app
-                                .state
-                                .new_instance
-                                .chosen_minecraft_version
-                                .as_ref()
-                                .unwrap()
-                                .id
-                    })
-                    .count();
-                match key {
-                    Key::Up => {
-                        app.state.new_instance.selected =
-                            util::wrap_dec(app.state.new_instance.selected, versions_len)
-                    }
-                    Key::Down => {
-                        app.state.new_instance.selected =
-                            util::wrap_inc(app.state.new_instance.selected, versions_len)
-                    }
-                    Key::PageUp => {
-                        app.state.new_instance.selected =
-                            util::wrap_sub(app.state.new_instance.selected, versions_len, 25)
-                    }
-                    Key::PageDown => {
-                        app.state.new_instance.selected =
-                            util::wrap_add(app.state.new_instance.selected, versions_len, 25)
-                    }
-                    Key::Enter => {
-                        app.state.new_instance.chosen_forge_version = Some(
-                            app.forge_version_manifest
-                                .as_ref()
-                                .unwrap()
-                                .versions
-                                .iter()
-                                .filter(|version| {
-                                    version.game_version
-                                        == app
-                                            .state
-                                            .new_instance
-                                            .chosen_minecraft_version
-                                            .as_ref()
-                                            .unwrap()
-                                            .id
-                                })
-                                .collect::<Vec<_>>()
-                                .get(app.state.new_instance.selected)
-                                .unwrap()
-                                .clone()
-                                .clone(),
-                        );
-                        app.dispatch(IoEvent::NewInstance);
-                        app.state.new_instance.inner = InnerState::Install;
-                        app.state.new_instance.selected = 0;
-                    }
-                    _ => {}
+                    .for_game_version(
+                        &app.state
+                            .new_instance
+                            .chosen_minecraft_version
+                            .as_ref()
+                            .unwrap()
+                            .id,
+                    );
+                let rows = common::forge_version_rows(&versions);
+                let state = &mut app.state.new_instance;
+                if state
+                    .filter
+                    .handle_key(key, &mut state.selected, &common::first_column(&rows))
+                {
+                    return;
+                }
+                if let Key::Enter = key {
+                    state.chosen_forge_version = versions.get(state.selected).cloned().cloned();
+                    state.selected = 0;
+                    state.filter = Default::default();
+                    state.inner = InnerState::Install;
+                    app.dispatch(IoEvent::NewInstance);
                 }
             }
-            InnerState::Install => {}
+            InnerState::Install => app.state.new_instance.confirm_cancel.handle_key(key),
         }
     }
-    async fn draw(&self, f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
+    fn handle_esc(&self, app: &mut App) -> bool {
+        match app.state.new_instance.inner {
+            InnerState::ChooseMinecraftVersion => {
+                app.state.new_instance.version_picker.handle_esc()
+            }
+            InnerState::ChooseForgeVersion => app.state.new_instance.filter.handle_esc(),
+            InnerState::Install => {
+                app.state.new_instance.confirm_cancel.toggle();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
         match &app.state.new_instance.inner {
             InnerState::EnterName => draw_enter_name(f, app, chunk),
             InnerState::FetchMinecraftVersionManifest => {
@@ -279,14 +232,28 @@ impl RouteImpl for Impl {
                 draw_loading(f, app, chunk, "Loading forge version manifest...").await
             }
             InnerState::ChooseForgeVersion => draw_choose_forge_version(f, app, chunk),
-            InnerState::Install => draw_loading(f, app, chunk, "Creating your new instance").await,
+            InnerState::Install => {
+                let state = &app.state.new_instance;
+                state
+                    .confirm_cancel
+                    .draw(
+                        f,
+                        &app.theme,
+                        chunk,
+                        "Creating your new instance",
+                        "Stop creating the instance? Nothing of it will be kept.",
+                        &[state.progress_main.as_ref(), state.progress_sub.as_ref()],
+                    )
+                    .await
+            }
         }
     }
 }
 
-async fn draw_loading(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect, msg: &str) {
+async fn draw_loading<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect, msg: &str) {
     common::draw_loading_dialog(
         f,
+        &app.theme,
         chunk,
         msg,
         &[
@@ -297,81 +264,58 @@ async fn draw_loading(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect, msg: &str
     .await
 }
 
-fn draw_enter_name(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
-    app.show_cursor();
-    common::draw_input_dialog(
+fn draw_enter_name<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+    let cursor = common::draw_input_dialog(
         f,
+        &app.theme,
         chunk,
         "Enter new instance name",
         &app.state.new_instance.name_input,
         app.state.new_instance.error.as_deref(),
-    )
+    );
+    app.show_cursor(cursor);
 }
 
-pub fn draw_choose_minecraft_version(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
-    let rect = util::centered_rect_percentage(90, 75, chunk);
-
-    let versions = &(app.minecraft_version_manifest.as_ref().unwrap().versions);
-    // .iter()
-    // .filter(|version| match version.r#type {
-    //     minecraft::VersionManifestVersionType::Release => true,
-    //     minecraft::VersionManifestVersionType::Snapshot /* if snapshots */ => true,
-    //     minecraft::VersionManifestVersionType::OldBeta /* if historical */ => true,
-    //     minecraft::VersionManifestVersionType::OldAlpha /* if historical */ => true,
-    //     _ => false,
-    // })
-    // .collect::<Vec<_>>();
-
-    let offset = app
-        .state
-        .new_instance
-        .selected
-        .saturating_sub((rect.height / 2) as usize)
-        .min(versions.len().saturating_sub((rect.height / 2) as usize));
-
-    let rows: Vec<_> = versions
-        .iter()
-        .skip(offset)
-        .take(rect.height as usize)
-        .map(|version| {
-            Row::Data(
-                vec![
-                    version.id.clone(),
-                    minecraft::version_ident(&version).to_string(),
-                    version.release_time.format("%b %e %Y").to_string(),
-                ]
-                .into_iter(),
-            )
-        })
-        .collect();
-
-    common::draw_table(
+/// Versions forge has no builds for are greyed out, if we know that from an earlier forge install
+pub fn draw_choose_minecraft_version<B: Backend>(
+    f: &mut UiFrame<'_, B>,
+    app: &mut App,
+    chunk: Rect,
+) {
+    let game_versions = app
+        .forge_version_manifest
+        .as_ref()
+        .map(forge::VersionManifest::game_versions);
+    let cursor = app.state.new_instance.version_picker.draw(
         f,
-        rect,
-        &["   Version id", "Type", "Release date"],
-        rows,
-        &[
-            Constraint::Percentage(40),
-            Constraint::Percentage(30),
-            Constraint::Percentage(30),
-        ],
-        Some("Choose minecraft version"),
-        Some(app.state.new_instance.selected - offset),
-    )
+        &app.theme,
+        chunk,
+        app.minecraft_version_manifest.as_ref().unwrap(),
+        |version| {
+            game_versions
+                .as_ref()
+                .map(|game_versions| !game_versions.contains(version.id.as_str()))
+                .unwrap_or(false)
+        },
+    );
+    if let Some(cursor) = cursor {
+        app.show_cursor(cursor);
+    }
 }
 
-pub fn draw_forge_notice(f: &mut UiFrame<'_>, _app: &mut App, chunk: Rect) {
+pub fn draw_forge_notice<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
     let text = "Forge is an open source project that mostly relies on ad revenue.
 By using Polyblock you bypass viewing these ads.
 Please strongly consider supporting the creator of Forge LexManos' Patreon.
 https://www.patreon.com/LexManos";
-    common::draw_button_dialog(f, chunk, 10, text, vec!["[ Ok ]"], 0)
+    common::draw_button_dialog(f, &app.theme, chunk, 10, text, vec!["[ Ok ]"], 0)
 }
 
-pub fn draw_choose_forge(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
+pub fn draw_choose_forge<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
     let text = "You will need a forge version to be able to install mods. Using the recommended version is usually a good idea unless you know you need another version. Would you like to install forge for this instance?";
     common::draw_button_dialog(
         f,
+        &app.theme,
         chunk,
         10,
         text,
@@ -380,60 +324,41 @@ pub fn draw_choose_forge(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
     )
 }
 
-pub fn draw_choose_forge_version(f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
+pub fn draw_choose_forge_version<B: Backend>(f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
     let rect = util::centered_rect_percentage(90, 75, chunk);
+    let state = &app.state.new_instance;
+    let versions = app
+        .forge_version_manifest
+        .as_ref()
+        .unwrap()
+        .for_game_version(&state.chosen_minecraft_version.as_ref().unwrap().id);
 
-    let versions = &(app.forge_version_manifest.as_ref().unwrap().versions);
-    // TODO: sort
-
-    let offset = app
-        .state
-        .new_instance
-        .selected
-        .saturating_sub((rect.height / 2) as usize)
-        .min(versions.len().saturating_sub((rect.height / 2) as usize));
-
-    let rows: Vec<_> = versions
-        .iter()
-        .filter(|version| {
-            version.game_version
-                == app
-                    .state
-                    .new_instance
-                    .chosen_minecraft_version
-                    .as_ref()
-                    .unwrap()
-                    .id
-        })
-        .skip(offset)
-        .take(rect.height as usize)
-        .map(|version| {
-            Row::Data(
-                vec![
-                    format!(
-                        "{}{}{}",
-                        version.name.trim_start_matches("forge-"),
-                        if version.latest { " (latest)" } else { "" },
-                        if version.recommended {
-                            " (recommended)"
-                        } else {
-                            ""
-                        }
-                    ),
-                    version.date_modified.format("%b %e %Y").to_string(),
-                ]
-                .into_iter(),
-            )
-        })
-        .collect();
-
-    common::draw_table(
+    let cursor = common::draw_filtered_table(
         f,
+        &app.theme,
         rect,
         &["   Version id", "Release date"],
-        rows,
+        common::forge_version_rows(&versions),
         &[Constraint::Percentage(50), Constraint::Percentage(50)],
         Some("Choose forge version"),
-        Some(app.state.new_instance.selected - offset),
-    )
+        state.selected,
+        &[],
+        &state.filter,
+    );
+    if let Some(cursor) = cursor {
+        app.show_cursor(cursor);
+    }
+}
+
+/// Whether forge can be installed for the chosen version, telling the user if it cannot
+fn has_forge_builds(app: &mut App) -> bool {
+    let version = app.state.new_instance.chosen_minecraft_version.as_ref();
+    let no_builds = match (&app.forge_version_manifest, version) {
+        (Some(manifest), Some(version)) => manifest.for_game_version(&version.id).is_empty(),
+        _ => false,
+    };
+    if no_builds {
+        app.status = Some(format!("Forge has no builds for {}.", version.unwrap().id));
+    }
+    !no_builds
 }
@@ -0,0 +1,233 @@
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tui::{
+    layout::{Constraint, Rect},
+    widgets::Row,
+};
+
+use super::*;
+use crate::{
+    packs::{self, Pack, PackKind},
+    util, Instance, IoEvent,
+};
+
+#[derive(Clone, PartialEq)]
+pub enum InnerState {
+    Loading,
+    List,
+    Import,
+}
+
+pub struct State {
+    pub inner: InnerState,
+    pub instance: Option<Instance>,
+    pub kind: PackKind,
+    pub packs: Vec<Pack>,
+    pub selected: usize,
+    /// The pack_format the instance's minecraft version expects
+    pub pack_format: Option<u64>,
+    pub import_input: String,
+    error: Option<String>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            inner: InnerState::Loading,
+            instance: None,
+            kind: PackKind::Resource,
+            packs: Vec::new(),
+            selected: 0,
+            pack_format: None,
+            import_input: String::new(),
+            error: None,
+        }
+    }
+}
+
+impl State {
+    pub fn new(instance: Instance) -> Self {
+        Self {
+            pack_format: packs::pack_format_for_version(&instance.version_id),
+            instance: Some(instance),
+            ..Default::default()
+        }
+    }
+
+    pub fn selected_pack(&self) -> Option<&Pack> {
+        self.packs.get(self.selected)
+    }
+
+    pub fn set_packs(&mut self, packs: Vec<Pack>) {
+        self.packs = packs;
+        if self.selected >= self.packs.len() {
+            self.selected = self.packs.len().saturating_sub(1);
+        }
+        self.inner = InnerState::List;
+    }
+
+    fn validate_import(&self) -> Option<String> {
+        let path = PathBuf::from(&self.import_input);
+        if !path.is_file() {
+            return Some(String::from("File does not exist"));
+        }
+        if path.extension().and_then(|e| e.to_str()) != Some("zip") {
+            return Some(String::from("Must be a .zip file"));
+        }
+        let target = self
+            .instance
+            .as_ref()
+            .unwrap()
+            .directory()
+            .join(self.kind.directory_name())
+            .join(path.file_name().unwrap());
+        if target.exists() {
+            return Some(String::from("A pack with that name already exists"));
+        }
+        None
+    }
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        match app.state.packs.inner {
+            InnerState::List => vec![
+                ("ESC", "back"),
+                ("↑/↓", "move cursor"),
+                ("tab", "switch resource/shader"),
+                ("space", "enable/disable"),
+                ("I", "import"),
+            ],
+            InnerState::Import => vec![("ESC", "back"), ("⏎", "import")],
+            InnerState::Loading => Vec::new(),
+        }
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let state = &mut app.state.packs;
+        match state.inner {
+            InnerState::List => match key {
+                Key::Up if !state.packs.is_empty() => {
+                    state.selected = util::wrap_dec(state.selected, state.packs.len())
+                }
+                Key::Down if !state.packs.is_empty() => {
+                    state.selected = util::wrap_inc(state.selected, state.packs.len())
+                }
+                Key::Tab => {
+                    state.kind = match state.kind {
+                        PackKind::Resource => PackKind::Shader,
+                        PackKind::Shader => PackKind::Resource,
+                    };
+                    state.selected = 0;
+                    state.inner = InnerState::Loading;
+                    app.dispatch(IoEvent::LoadPacks);
+                }
+                Key::Char(' ') if state.selected_pack().is_some() => {
+                    state.inner = InnerState::Loading;
+                    app.dispatch(IoEvent::TogglePack);
+                }
+                Key::Char('i') => {
+                    state.import_input.clear();
+                    state.error = state.validate_import();
+                    state.inner = InnerState::Import;
+                }
+                _ => {}
+            },
+            InnerState::Import => {
+                match key {
+                    Key::Char(c) => state.import_input.push(c),
+                    Key::Backspace => {
+                        state.import_input.pop();
+                    }
+                    Key::Enter => {
+                        if state.error.is_none() {
+                            state.inner = InnerState::Loading;
+                            app.dispatch(IoEvent::ImportPack);
+                        }
+                        return;
+                    }
+                    _ => {}
+                }
+                state.error = state.validate_import();
+            }
+            InnerState::Loading => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let state = &app.state.packs;
+        if state.inner == InnerState::Loading {
+            common::draw_loading_dialog(f, &app.theme, chunk, "Reading packs...", &[]).await;
+            return;
+        }
+
+        let rect = util::centered_rect_percentage(90, 60, chunk);
+        let rows: Vec<_> = state
+            .packs
+            .iter()
+            .map(|pack| {
+                Row::Data(
+                    vec![
+                        String::from(if pack.enabled { "[x]" } else { "[ ]" }),
+                        pack.file_name(),
+                        match pack.is_compatible(state.pack_format) {
+                            Some(true) => pack.pack_format.unwrap().to_string(),
+                            Some(false) => format!("{} (incompatible)", pack.pack_format.unwrap()),
+                            None => pack
+                                .pack_format
+                                .map(|pack_format| pack_format.to_string())
+                                .unwrap_or_default(),
+                        },
+                        pack.description.clone().unwrap_or_default(),
+                    ]
+                    .into_iter(),
+                )
+            })
+            .collect();
+
+        let title = match (state.kind, state.pack_format) {
+            (PackKind::Resource, Some(pack_format)) => {
+                format!("{} (pack format {})", state.kind, pack_format)
+            }
+            _ => state.kind.to_string(),
+        };
+        common::draw_table(
+            f,
+            &app.theme,
+            rect,
+            &["   On", "Name", "Format", "Description"],
+            rows,
+            &[
+                Constraint::Length(6),
+                Constraint::Percentage(30),
+                Constraint::Length(18),
+                Constraint::Percentage(50),
+            ],
+            Some(&title),
+            if state.packs.is_empty() {
+                None
+            } else {
+                Some(state.selected)
+            },
+        );
+
+        if state.inner == InnerState::Import {
+            let cursor = common::draw_input_dialog(
+                f,
+                &app.theme,
+                chunk,
+                "Path of pack zip to import",
+                &state.import_input,
+                state.error.as_deref(),
+            );
+            app.show_cursor(cursor);
+        }
+    }
+}
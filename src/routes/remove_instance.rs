@@ -29,7 +29,6 @@ impl State {
 
 pub struct Impl {}
 
-#[async_trait]
 impl RouteImpl for Impl {
     fn is_modal(&self) -> bool {
         true
@@ -68,21 +67,30 @@ impl RouteImpl for Impl {
             _ => {}
         }
     }
-    async fn draw(&self, f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let instance = app.state.remove_instance.instance.as_ref().unwrap();
+        let msg = if instance.external_directory.is_some() {
+            format!(
+                "Are you sure to want to remove this instance? The directory {} will be kept.",
+                instance.directory().display()
+            )
+        } else {
+            format!(
+                "Are you sure to want to remove this instance? This will also remove the directory {}.",
+                instance.directory().display()
+            )
+        };
         let state = &app.state.remove_instance;
         common::draw_button_dialog(
             f,
+            &app.theme,
             chunk,
             10,
-            &format!(
-                "Are you sure to want to remove this instance? This will also remove the directory {}.",
-                state
-                    .instance
-                    .as_ref()
-                    .unwrap()
-                    .directory()
-                    .display()
-            ),
+            &msg,
             vec!["[ Yes ]", "[ No ]"],
             state.selected,
         )
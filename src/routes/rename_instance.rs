@@ -23,7 +23,6 @@ impl State {
     }
 }
 
-#[async_trait]
 impl RouteImpl for Impl {
     fn is_modal(&self) -> bool {
         true
@@ -66,15 +65,20 @@ impl RouteImpl for Impl {
             state.error = None;
         }
     }
-    async fn draw(&self, f: &mut UiFrame<'_>, app: &mut App, chunk: Rect) {
-        app.show_cursor();
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
         let state = &app.state.rename_instance;
-        common::draw_input_dialog(
+        let cursor = common::draw_input_dialog(
             f,
+            &app.theme,
             chunk,
             "Enter new instance name",
             &state.name_input,
             state.error.as_deref(),
-        )
+        );
+        app.show_cursor(cursor);
     }
 }
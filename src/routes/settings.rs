@@ -0,0 +1,312 @@
+use async_trait::async_trait;
+use std::{fmt, path::PathBuf};
+use tui::{
+    layout::{Constraint, Rect},
+    widgets::Row,
+};
+
+use super::*;
+use crate::{
+    settings::Settings,
+    theme::{self, Theme},
+    util, IoEvent,
+};
+
+#[derive(Clone, Copy)]
+pub enum Field {
+    JavaHome,
+    Memory,
+    DownloadConcurrency,
+    ModSource,
+    ShowReleases,
+    ShowBetas,
+    ShowAlphas,
+    SnapshotBudget,
+    ModStore,
+    ManifestMaxAge,
+    Theme,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Field::JavaHome => write!(f, "Default java home"),
+            Field::Memory => write!(f, "Default memory (MiB)"),
+            Field::DownloadConcurrency => write!(f, "Download concurrency"),
+            Field::ModSource => write!(f, "Preferred mod source"),
+            Field::ShowReleases => write!(f, "Show release mod files"),
+            Field::ShowBetas => write!(f, "Show beta mod files"),
+            Field::ShowAlphas => write!(f, "Show alpha mod files"),
+            Field::SnapshotBudget => write!(f, "Snapshot budget (MiB, 0 = off)"),
+            Field::ModStore => write!(f, "Share mod jars between instances"),
+            Field::ManifestMaxAge => write!(f, "Manifest cache freshness (minutes)"),
+            Field::Theme => write!(f, "Theme"),
+        }
+    }
+}
+
+impl Field {
+    const ALL: [Field; 11] = [
+        Field::JavaHome,
+        Field::Memory,
+        Field::DownloadConcurrency,
+        Field::ModSource,
+        Field::ShowReleases,
+        Field::ShowBetas,
+        Field::ShowAlphas,
+        Field::SnapshotBudget,
+        Field::ModStore,
+        Field::ManifestMaxAge,
+        Field::Theme,
+    ];
+
+    fn value(&self, app: &App) -> String {
+        let settings = &app.settings;
+        match self {
+            Field::JavaHome => match (&app.java_home_overwrite, &settings.java_home) {
+                (Some(overwrite), _) => format!("{} (from --java)", overwrite.display()),
+                (None, Some(java_home)) => java_home.display().to_string(),
+                (None, None) => String::from("(Automatic)"),
+            },
+            Field::Memory => settings.memory.to_string(),
+            Field::DownloadConcurrency => settings.download_concurrency.to_string(),
+            Field::ModSource => settings.mod_source.to_string(),
+            Field::ShowReleases => yes_no(settings.release_types.release),
+            Field::ShowBetas => yes_no(settings.release_types.beta),
+            Field::ShowAlphas => yes_no(settings.release_types.alpha),
+            Field::SnapshotBudget => settings.snapshot_budget.to_string(),
+            Field::ModStore => yes_no(settings.mod_store),
+            Field::ManifestMaxAge => settings.manifest_max_age.to_string(),
+            Field::Theme => settings.theme.replace('_', " "),
+        }
+    }
+
+    /// The text to prefill the input with, or None if the field is toggled instead of typed
+    fn input(&self, settings: &Settings) -> Option<String> {
+        match self {
+            Field::JavaHome => Some(
+                settings
+                    .java_home
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default(),
+            ),
+            Field::Memory => Some(settings.memory.to_string()),
+            Field::DownloadConcurrency => Some(settings.download_concurrency.to_string()),
+            Field::SnapshotBudget => Some(settings.snapshot_budget.to_string()),
+            Field::ManifestMaxAge => Some(settings.manifest_max_age.to_string()),
+            _ => None,
+        }
+    }
+
+    fn toggle(&self, app: &mut App) {
+        let settings = &mut app.settings;
+        match self {
+            Field::ModSource => settings.mod_source = settings.mod_source.next(),
+            Field::ShowReleases => settings.release_types.release ^= true,
+            Field::ShowBetas => settings.release_types.beta ^= true,
+            Field::ShowAlphas => settings.release_types.alpha ^= true,
+            Field::ModStore => settings.mod_store ^= true,
+            Field::Theme => {
+                let names = theme::names(&app.paths.directory.themes);
+                let next = names
+                    .iter()
+                    .position(|name| *name == settings.theme)
+                    .map(|i| (i + 1) % names.len())
+                    .unwrap_or(0);
+                settings.theme = names[next].clone();
+                match Theme::load(&app.paths.directory.themes, &settings.theme) {
+                    Ok(theme) => app.theme = theme,
+                    Err(e) => {
+                        app.status =
+                            Some(format!("Failed to load theme {}: {:#}", settings.theme, e))
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn validate(&self, input: &str, app: &App) -> Result<(), String> {
+        match self {
+            Field::JavaHome => {
+                if input.is_empty() {
+                    Ok(())
+                } else {
+                    util::java::find_exec(Some(PathBuf::from(input)))
+                        .map(|_| ())
+                        .map_err(|_| String::from("No java executable found in bin/"))
+                }
+            }
+            Field::Memory => {
+                let memory = input
+                    .parse::<u64>()
+                    .map_err(|_| String::from("Must be a whole number"))?;
+                if memory < Settings::MIN_MEMORY {
+                    return Err(format!("Must be at least {} MiB", Settings::MIN_MEMORY));
+                }
+                // Instances without a maximum of their own launch with the default
+                match app.instances.inner.values().find(|instance| {
+                    instance.memory_max.is_none() && instance.memory_min > Some(memory)
+                }) {
+                    Some(instance) => Err(format!(
+                        "Must not be less than the minimum memory of {}",
+                        instance.name
+                    )),
+                    None => Ok(()),
+                }
+            }
+            Field::DownloadConcurrency => match input.parse::<usize>() {
+                Ok(n) if (1..=Settings::MAX_DOWNLOAD_CONCURRENCY).contains(&n) => Ok(()),
+                _ => Err(format!(
+                    "Must be between 1 and {}",
+                    Settings::MAX_DOWNLOAD_CONCURRENCY
+                )),
+            },
+            Field::SnapshotBudget | Field::ManifestMaxAge => input
+                .parse::<u64>()
+                .map(|_| ())
+                .map_err(|_| String::from("Must be a whole number")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply an already validated input
+    fn apply(&self, input: &str, settings: &mut Settings) {
+        match self {
+            Field::JavaHome => {
+                settings.java_home = if input.is_empty() {
+                    None
+                } else {
+                    Some(PathBuf::from(input))
+                }
+            }
+            Field::Memory => settings.memory = input.parse().unwrap(),
+            Field::DownloadConcurrency => settings.download_concurrency = input.parse().unwrap(),
+            Field::SnapshotBudget => settings.snapshot_budget = input.parse().unwrap(),
+            Field::ManifestMaxAge => settings.manifest_max_age = input.parse().unwrap(),
+            _ => {}
+        }
+    }
+}
+
+fn yes_no(b: bool) -> String {
+    String::from(if b { "Yes" } else { "No" })
+}
+
+#[derive(Default)]
+pub struct State {
+    selected: usize,
+    editing: bool,
+    input: String,
+    error: Option<String>,
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        false
+    }
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        if app.state.settings.editing {
+            vec![("ESC", "back"), ("⏎", "save")]
+        } else {
+            vec![
+                ("ESC", "back"),
+                ("↑/↓", "choose setting"),
+                ("⏎", "edit"),
+                ("M", "mod store"),
+            ]
+        }
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let field = Field::ALL[app.state.settings.selected];
+        if app.state.settings.editing {
+            let state = &mut app.state.settings;
+            match key {
+                Key::Char(c) => state.input.push(c),
+                Key::Backspace => {
+                    state.input.pop();
+                }
+                Key::Enter => {
+                    if state.error.is_none() {
+                        field.apply(&state.input, &mut app.settings);
+                        state.editing = false;
+                        app.dispatch(IoEvent::SaveSettings);
+                    }
+                    return;
+                }
+                _ => {}
+            }
+            app.state.settings.error = field.validate(&app.state.settings.input, app).err();
+        } else {
+            let state = &mut app.state.settings;
+            match key {
+                Key::Up => state.selected = util::wrap_dec(state.selected, Field::ALL.len()),
+                Key::Down => state.selected = util::wrap_inc(state.selected, Field::ALL.len()),
+                Key::Enter => match field.input(&app.settings) {
+                    Some(input) => {
+                        let error = field.validate(&input, app).err();
+                        let state = &mut app.state.settings;
+                        state.error = error;
+                        state.input = input;
+                        state.editing = true;
+                    }
+                    None => {
+                        field.toggle(app);
+                        app.dispatch(IoEvent::SaveSettings);
+                    }
+                },
+                Key::Char('m') => {
+                    app.state.mod_store = mod_store::State::default();
+                    app.dispatch(IoEvent::LoadModStore);
+                    app.push_route(Route::ModStore);
+                }
+                _ => {}
+            }
+        }
+    }
+    fn handle_esc(&self, app: &mut App) -> bool {
+        let state = &mut app.state.settings;
+        if state.editing {
+            state.editing = false;
+            return true;
+        }
+        false
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let rows: Vec<_> = Field::ALL
+            .iter()
+            .map(|field| Row::Data(vec![field.to_string(), field.value(app)].into_iter()))
+            .collect();
+
+        common::draw_table(
+            f,
+            &app.theme,
+            chunk,
+            &["   Setting", "Value"],
+            rows,
+            &[Constraint::Percentage(40), Constraint::Percentage(60)],
+            Some("Settings"),
+            Some(app.state.settings.selected),
+        );
+
+        if app.state.settings.editing {
+            let state = &app.state.settings;
+            let cursor = common::draw_input_dialog(
+                f,
+                &app.theme,
+                chunk,
+                &Field::ALL[state.selected].to_string(),
+                &state.input,
+                state.error.as_deref(),
+            );
+            app.show_cursor(cursor);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use async_trait::async_trait;
+use tui::{
+    layout::{Constraint, Rect},
+    widgets::Row,
+};
+use uuid::Uuid;
+
+use super::*;
+use crate::{snapshots::Snapshot, util, IoEvent};
+
+#[derive(Clone, PartialEq)]
+pub enum InnerState {
+    Loading,
+    List,
+    ConfirmRollback,
+    Rollback,
+}
+
+pub struct State {
+    pub inner: InnerState,
+    /// Only show the snapshots of this instance
+    pub uuid: Option<Uuid>,
+    pub snapshots: Vec<Snapshot>,
+    pub selected: usize,
+    /// Selected button of the rollback confirmation
+    confirm_selected: usize,
+    pub progress: Option<util::Progress>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            inner: InnerState::Loading,
+            uuid: None,
+            snapshots: Vec::new(),
+            selected: 0,
+            confirm_selected: 1,
+            progress: None,
+        }
+    }
+}
+
+impl State {
+    pub fn new(uuid: Option<Uuid>) -> Self {
+        Self {
+            uuid,
+            ..Default::default()
+        }
+    }
+
+    pub fn selected_snapshot(&self) -> Option<&Snapshot> {
+        self.snapshots.get(self.selected)
+    }
+
+    pub fn set_snapshots(&mut self, snapshots: Vec<Snapshot>) {
+        self.snapshots = match self.uuid {
+            Some(uuid) => snapshots
+                .into_iter()
+                .filter(|snapshot| snapshot.instance.uuid == uuid)
+                .collect(),
+            None => snapshots,
+        };
+        if self.selected >= self.snapshots.len() {
+            self.selected = self.snapshots.len().saturating_sub(1);
+        }
+        self.inner = InnerState::List;
+        self.progress = None;
+    }
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        match app.state.snapshots.inner {
+            InnerState::List => vec![("ESC", "back"), ("↑/↓", "move cursor"), ("⏎", "roll back")],
+            InnerState::ConfirmRollback => vec![
+                ("←/→", "choose option"),
+                ("Y", "yes"),
+                ("N", "no"),
+                ("⏎", "select"),
+            ],
+            InnerState::Loading | InnerState::Rollback => Vec::new(),
+        }
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let state = &mut app.state.snapshots;
+        match state.inner {
+            InnerState::List => {
+                if state.snapshots.is_empty() {
+                    return;
+                }
+                match key {
+                    Key::Up => {
+                        state.selected = util::wrap_dec(state.selected, state.snapshots.len())
+                    }
+                    Key::Down => {
+                        state.selected = util::wrap_inc(state.selected, state.snapshots.len())
+                    }
+                    Key::Enter => {
+                        state.confirm_selected = 1;
+                        state.inner = InnerState::ConfirmRollback;
+                    }
+                    _ => {}
+                }
+            }
+            InnerState::ConfirmRollback => match key {
+                Key::Left => state.confirm_selected = util::wrap_dec(state.confirm_selected, 2),
+                Key::Right => state.confirm_selected = util::wrap_inc(state.confirm_selected, 2),
+                Key::Char('y') => {
+                    state.inner = InnerState::Rollback;
+                    app.dispatch(IoEvent::RollbackSnapshot);
+                }
+                Key::Char('n') => state.inner = InnerState::List,
+                Key::Enter => {
+                    if state.confirm_selected == 0 {
+                        state.inner = InnerState::Rollback;
+                        app.dispatch(IoEvent::RollbackSnapshot);
+                    } else {
+                        state.inner = InnerState::List;
+                    }
+                }
+                _ => {}
+            },
+            InnerState::Loading | InnerState::Rollback => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let state = &app.state.snapshots;
+        match state.inner {
+            InnerState::Loading => {
+                common::draw_loading_dialog(f, &app.theme, chunk, "Reading snapshots...", &[])
+                    .await;
+                return;
+            }
+            InnerState::Rollback => {
+                common::draw_loading_dialog(
+                    f,
+                    &app.theme,
+                    chunk,
+                    "Rolling back...",
+                    &[state.progress.as_ref()],
+                )
+                .await;
+                return;
+            }
+            _ => {}
+        }
+
+        let rect = util::centered_rect_percentage(90, 60, chunk);
+        let rows: Vec<_> = state
+            .snapshots
+            .iter()
+            .map(|snapshot| {
+                Row::Data(
+                    vec![
+                        snapshot.instance.name.clone(),
+                        snapshot.instance.full_version_id(),
+                        snapshot.reason.clone(),
+                        snapshot.created.format("%Y-%m-%d %H:%M:%S").to_string(),
+                        util::format_size(snapshot.size),
+                    ]
+                    .into_iter(),
+                )
+            })
+            .collect();
+
+        common::draw_table(
+            f,
+            &app.theme,
+            rect,
+            &["   Instance", "Version", "Taken", "Created", "Size"],
+            rows,
+            &[
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(30),
+                Constraint::Percentage(18),
+                Constraint::Percentage(12),
+            ],
+            Some(if state.snapshots.is_empty() {
+                "No snapshots"
+            } else {
+                "Snapshots"
+            }),
+            if state.snapshots.is_empty() {
+                None
+            } else {
+                Some(state.selected)
+            },
+        );
+
+        if state.inner == InnerState::ConfirmRollback {
+            let snapshot = state.selected_snapshot().unwrap();
+            common::draw_button_dialog(
+                f,
+                &app.theme,
+                chunk,
+                10,
+                &format!(
+                    "Are you sure you want to roll back {} to {}? Changes since then are lost.",
+                    snapshot.instance.name,
+                    snapshot.created.format("%Y-%m-%d %H:%M:%S")
+                ),
+                vec!["[ Yes ]", "[ No ]"],
+                state.confirm_selected,
+            )
+        }
+    }
+}
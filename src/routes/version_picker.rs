@@ -0,0 +1,182 @@
+use tui::layout::{Constraint, Rect};
+
+use super::*;
+use crate::{
+    minecraft::{self, VersionManifest, VersionManifestVersion, VersionManifestVersionType},
+    theme::Theme,
+    util,
+};
+
+/// A row to choose, either a version or the latest release or snapshot
+pub struct Choice<'a> {
+    pub label: String,
+    pub version: &'a VersionManifestVersion,
+}
+
+/// Choosing a minecraft version, with old and unstable versions hidden unless asked for
+#[derive(Clone, Default)]
+pub struct VersionPicker {
+    /// An index into the choices
+    selected: usize,
+    filter: common::Filter,
+    snapshots: bool,
+    old_betas: bool,
+    old_alphas: bool,
+}
+
+impl VersionPicker {
+    pub fn choices<'a>(&self, manifest: &'a VersionManifest) -> Vec<Choice<'a>> {
+        let find = |id: &str| manifest.versions.iter().find(|version| version.id == id);
+        let mut choices = Vec::new();
+        if let Some(version) = find(&manifest.latest.release) {
+            choices.push(Choice {
+                label: format!("Latest release ({})", version.id),
+                version,
+            });
+        }
+        // Between snapshot cycles the latest snapshot is just the latest release
+        if self.snapshots && manifest.latest.snapshot != manifest.latest.release {
+            if let Some(version) = find(&manifest.latest.snapshot) {
+                choices.push(Choice {
+                    label: format!("Latest snapshot ({})", version.id),
+                    version,
+                });
+            }
+        }
+        choices.extend(
+            manifest
+                .versions
+                .iter()
+                .filter(|version| match version.r#type {
+                    VersionManifestVersionType::Release => true,
+                    VersionManifestVersionType::Snapshot => self.snapshots,
+                    VersionManifestVersionType::OldBeta => self.old_betas,
+                    VersionManifestVersionType::OldAlpha => self.old_alphas,
+                })
+                .map(|version| Choice {
+                    label: version.id.clone(),
+                    version,
+                }),
+        );
+        choices
+    }
+
+    pub fn get_help(&self) -> Vec<(&'static str, &'static str)> {
+        self.filter.get_help(vec![
+            ("ESC", "cancel"),
+            ("↑↓", "choose version"),
+            ("PgUp/PgDn", "move cursor 25"),
+            ("⏎", "select"),
+            (
+                "S",
+                if self.snapshots {
+                    "hide snapshots"
+                } else {
+                    "show snapshots"
+                },
+            ),
+            (
+                "B",
+                if self.old_betas {
+                    "hide betas"
+                } else {
+                    "show betas"
+                },
+            ),
+            (
+                "A",
+                if self.old_alphas {
+                    "hide alphas"
+                } else {
+                    "show alphas"
+                },
+            ),
+        ])
+    }
+
+    /// Returns the version chosen with Enter
+    pub fn handle_key(
+        &mut self,
+        key: Key,
+        manifest: &VersionManifest,
+    ) -> Option<VersionManifestVersion> {
+        let labels = choice_labels(&self.choices(manifest));
+        if self.filter.handle_key(key, &mut self.selected, &labels) {
+            return None;
+        }
+        match key {
+            Key::Char('s') => self.snapshots = !self.snapshots,
+            Key::Char('b') => self.old_betas = !self.old_betas,
+            Key::Char('a') => self.old_alphas = !self.old_alphas,
+            Key::Enter => {
+                return self
+                    .choices(manifest)
+                    .get(self.selected)
+                    .map(|choice| choice.version.clone())
+            }
+            _ => return None,
+        }
+
+        // Stay on the same row if it is still listed
+        let selected = labels.get(self.selected);
+        let labels = choice_labels(&self.choices(manifest));
+        self.selected = selected
+            .and_then(|selected| labels.iter().position(|label| label == selected))
+            .unwrap_or(0);
+        self.filter.reselect(&mut self.selected, &labels);
+        None
+    }
+
+    /// Esc clears the filter first, returning whether there was one
+    pub fn handle_esc(&mut self) -> bool {
+        self.filter.handle_esc()
+    }
+
+    /// Versions for which `unavailable` is true are greyed out. Returns where the cursor should be.
+    pub fn draw<B: Backend>(
+        &self,
+        f: &mut UiFrame<'_, B>,
+        theme: &Theme,
+        chunk: Rect,
+        manifest: &VersionManifest,
+        unavailable: impl Fn(&VersionManifestVersion) -> bool,
+    ) -> Option<(u16, u16)> {
+        let rect = util::centered_rect_percentage(90, 75, chunk);
+        let choices = self.choices(manifest);
+        let rows = choices
+            .iter()
+            .map(|choice| {
+                vec![
+                    choice.label.clone(),
+                    minecraft::version_ident(choice.version).to_string(),
+                    choice.version.release_time.format("%b %e %Y").to_string(),
+                ]
+            })
+            .collect();
+        let dimmed: Vec<bool> = choices
+            .iter()
+            .map(|choice| unavailable(choice.version))
+            .collect();
+
+        common::draw_filtered_table(
+            f,
+            theme,
+            rect,
+            &["   Version id", "Type", "Release date"],
+            rows,
+            &[
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+            ],
+            Some("Choose minecraft version"),
+            self.selected,
+            &dimmed,
+            &self.filter,
+        )
+    }
+}
+
+fn choice_labels(choices: &[Choice]) -> Vec<String> {
+    choices.iter().map(|choice| choice.label.clone()).collect()
+}
@@ -0,0 +1,202 @@
+use async_trait::async_trait;
+use tui::{
+    layout::{Constraint, Rect},
+    widgets::Row,
+};
+
+use super::*;
+use crate::{
+    util,
+    worlds::{Backup, World},
+    Instance, IoEvent,
+};
+
+#[derive(Clone, PartialEq)]
+pub enum InnerState {
+    Loading,
+    List,
+    ConfirmRestore,
+    Restore,
+}
+
+pub struct State {
+    pub inner: InnerState,
+    pub instance: Option<Instance>,
+    pub world: Option<World>,
+    pub backups: Vec<Backup>,
+    pub selected: usize,
+    /// Selected button of the restore confirmation
+    confirm_selected: usize,
+    pub progress: Option<util::Progress>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            inner: InnerState::Loading,
+            instance: None,
+            world: None,
+            backups: Vec::new(),
+            selected: 0,
+            confirm_selected: 1,
+            progress: None,
+        }
+    }
+}
+
+impl State {
+    pub fn new(instance: Instance, world: World) -> Self {
+        Self {
+            instance: Some(instance),
+            world: Some(world),
+            ..Default::default()
+        }
+    }
+
+    pub fn selected_backup(&self) -> Option<&Backup> {
+        self.backups.get(self.selected)
+    }
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        match app.state.world_backups.inner {
+            InnerState::List => vec![("ESC", "back"), ("↑/↓", "move cursor"), ("⏎", "restore")],
+            InnerState::ConfirmRestore => vec![
+                ("←/→", "choose option"),
+                ("Y", "yes"),
+                ("N", "no"),
+                ("⏎", "select"),
+            ],
+            InnerState::Loading | InnerState::Restore => Vec::new(),
+        }
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let state = &mut app.state.world_backups;
+        match state.inner {
+            InnerState::List => {
+                if state.backups.is_empty() {
+                    return;
+                }
+                match key {
+                    Key::Up => state.selected = util::wrap_dec(state.selected, state.backups.len()),
+                    Key::Down => {
+                        state.selected = util::wrap_inc(state.selected, state.backups.len())
+                    }
+                    Key::Enter => {
+                        state.confirm_selected = 1;
+                        state.inner = InnerState::ConfirmRestore;
+                    }
+                    _ => {}
+                }
+            }
+            InnerState::ConfirmRestore => match key {
+                Key::Left => state.confirm_selected = util::wrap_dec(state.confirm_selected, 2),
+                Key::Right => state.confirm_selected = util::wrap_inc(state.confirm_selected, 2),
+                Key::Char('y') => {
+                    state.inner = InnerState::Restore;
+                    app.dispatch(IoEvent::RestoreWorld);
+                }
+                Key::Char('n') => state.inner = InnerState::List,
+                Key::Enter => {
+                    if state.confirm_selected == 0 {
+                        state.inner = InnerState::Restore;
+                        app.dispatch(IoEvent::RestoreWorld);
+                    } else {
+                        state.inner = InnerState::List;
+                    }
+                }
+                _ => {}
+            },
+            InnerState::Loading | InnerState::Restore => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let state = &app.state.world_backups;
+        match state.inner {
+            InnerState::Loading => {
+                common::draw_loading_dialog(f, &app.theme, chunk, "Reading backups...", &[]).await;
+                return;
+            }
+            InnerState::Restore => {
+                common::draw_loading_dialog(
+                    f,
+                    &app.theme,
+                    chunk,
+                    "Restoring backup...",
+                    &[state.progress.as_ref()],
+                )
+                .await;
+                return;
+            }
+            _ => {}
+        }
+
+        let world = state.world.as_ref().unwrap();
+        let rect = util::centered_rect_percentage(60, 50, chunk);
+        let rows: Vec<_> = state
+            .backups
+            .iter()
+            .map(|backup| {
+                Row::Data(
+                    vec![
+                        backup.created.format("%Y-%m-%d %H:%M:%S").to_string(),
+                        util::format_size(backup.size),
+                    ]
+                    .into_iter(),
+                )
+            })
+            .collect();
+
+        let title = if state.backups.is_empty() {
+            format!("No backups of {} (press B in the world list)", world.name)
+        } else {
+            format!("Backups of {}", world.name)
+        };
+        common::draw_table(
+            f,
+            &app.theme,
+            rect,
+            &["   Created", "Size"],
+            rows,
+            &[Constraint::Percentage(70), Constraint::Percentage(30)],
+            Some(&title),
+            if state.backups.is_empty() {
+                None
+            } else {
+                Some(state.selected)
+            },
+        );
+
+        if state.inner == InnerState::ConfirmRestore {
+            common::draw_button_dialog(
+                f,
+                &app.theme,
+                chunk,
+                10,
+                &if world.deleted {
+                    format!(
+                        "Are you sure you want to restore {} from this backup?",
+                        world.name
+                    )
+                } else {
+                    format!(
+                        "Are you sure you want to restore this backup? The current {} will be replaced.",
+                        world.name
+                    )
+                },
+                vec!["[ Yes ]", "[ No ]"],
+                state.confirm_selected,
+            )
+        }
+    }
+}
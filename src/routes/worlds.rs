@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use tui::{
+    layout::{Constraint, Rect},
+    widgets::Row,
+};
+
+use super::*;
+use crate::{util, worlds::World, Instance, IoEvent};
+
+#[derive(Clone, PartialEq)]
+pub enum InnerState {
+    Loading,
+    List,
+    ConfirmDelete,
+    Working,
+}
+
+pub struct State {
+    pub inner: InnerState,
+    pub instance: Option<Instance>,
+    pub worlds: Vec<World>,
+    pub selected: usize,
+    /// Selected button of the delete confirmation
+    confirm_selected: usize,
+    pub progress: Option<util::Progress>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            inner: InnerState::Loading,
+            instance: None,
+            worlds: Vec::new(),
+            selected: 0,
+            confirm_selected: 1,
+            progress: None,
+        }
+    }
+}
+
+impl State {
+    pub fn new(instance: Instance) -> Self {
+        Self {
+            instance: Some(instance),
+            ..Default::default()
+        }
+    }
+
+    pub fn selected_world(&self) -> Option<&World> {
+        self.worlds.get(self.selected)
+    }
+
+    pub fn set_worlds(&mut self, worlds: Vec<World>) {
+        self.worlds = worlds;
+        if self.selected >= self.worlds.len() {
+            self.selected = self.worlds.len().saturating_sub(1);
+        }
+        self.inner = InnerState::List;
+        self.progress = None;
+    }
+}
+
+pub struct Impl {}
+
+impl RouteImpl for Impl {
+    fn is_modal(&self) -> bool {
+        true
+    }
+    fn get_help(&self, app: &App) -> Vec<(&'static str, &'static str)> {
+        match app.state.worlds.inner {
+            InnerState::List => match app.state.worlds.selected_world() {
+                Some(world) if world.deleted => {
+                    vec![
+                        ("ESC", "back"),
+                        ("↑/↓", "move cursor"),
+                        ("⏎", "restore backup"),
+                    ]
+                }
+                _ => vec![
+                    ("ESC", "back"),
+                    ("↑/↓", "move cursor"),
+                    ("B", "backup"),
+                    ("⏎", "restore backup"),
+                    ("DEL", "delete"),
+                ],
+            },
+            InnerState::ConfirmDelete => vec![
+                ("←/→", "choose option"),
+                ("Y", "yes"),
+                ("N", "no"),
+                ("⏎", "select"),
+            ],
+            InnerState::Loading | InnerState::Working => Vec::new(),
+        }
+    }
+    fn handle_key(&self, key: Key, app: &mut App) {
+        let state = &mut app.state.worlds;
+        match state.inner {
+            InnerState::List => {
+                let deleted = match state.selected_world() {
+                    Some(world) => world.deleted,
+                    None => return,
+                };
+                match key {
+                    Key::Up => state.selected = util::wrap_dec(state.selected, state.worlds.len()),
+                    Key::Down => {
+                        state.selected = util::wrap_inc(state.selected, state.worlds.len())
+                    }
+                    // Only backups are left of a deleted world
+                    Key::Char('b') if !deleted => {
+                        state.inner = InnerState::Working;
+                        app.dispatch(IoEvent::BackupWorld);
+                    }
+                    Key::Enter => {
+                        let instance = state.instance.clone().unwrap();
+                        let world = state.selected_world().unwrap().clone();
+                        app.state.world_backups = world_backups::State::new(instance, world);
+                        app.push_route(Route::WorldBackups);
+                        app.dispatch(IoEvent::LoadWorldBackups);
+                    }
+                    Key::Delete if !deleted => {
+                        state.confirm_selected = 1;
+                        state.inner = InnerState::ConfirmDelete;
+                    }
+                    _ => {}
+                }
+            }
+            InnerState::ConfirmDelete => match key {
+                Key::Left => state.confirm_selected = util::wrap_dec(state.confirm_selected, 2),
+                Key::Right => state.confirm_selected = util::wrap_inc(state.confirm_selected, 2),
+                Key::Char('y') => {
+                    state.inner = InnerState::Working;
+                    app.dispatch(IoEvent::DeleteWorld);
+                }
+                Key::Char('n') => state.inner = InnerState::List,
+                Key::Enter => {
+                    if state.confirm_selected == 0 {
+                        state.inner = InnerState::Working;
+                        app.dispatch(IoEvent::DeleteWorld);
+                    } else {
+                        state.inner = InnerState::List;
+                    }
+                }
+                _ => {}
+            },
+            InnerState::Loading | InnerState::Working => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Backend + Send> RouteDraw<B> for Impl {
+    async fn draw(&self, f: &mut UiFrame<'_, B>, app: &mut App, chunk: Rect) {
+        let state = &app.state.worlds;
+        match state.inner {
+            InnerState::Loading => {
+                common::draw_loading_dialog(f, &app.theme, chunk, "Reading worlds...", &[]).await;
+                return;
+            }
+            InnerState::Working => {
+                common::draw_loading_dialog(
+                    f,
+                    &app.theme,
+                    chunk,
+                    "Working...",
+                    &[state.progress.as_ref()],
+                )
+                .await;
+                return;
+            }
+            _ => {}
+        }
+
+        let rect = util::centered_rect_percentage(90, 60, chunk);
+        let rows: Vec<_> = state
+            .worlds
+            .iter()
+            .map(|world| {
+                Row::Data(
+                    vec![
+                        world.name.clone(),
+                        world
+                            .last_played
+                            .map(|last_played| last_played.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_default(),
+                        if world.deleted {
+                            String::from("Deleted")
+                        } else {
+                            world.game_mode_string()
+                        },
+                        world.version.clone().unwrap_or_default(),
+                        if world.deleted {
+                            String::new()
+                        } else {
+                            util::format_size(world.size)
+                        },
+                    ]
+                    .into_iter(),
+                )
+            })
+            .collect();
+
+        let title = format!(
+            "Worlds of {}",
+            state
+                .instance
+                .as_ref()
+                .map(|i| i.name.as_str())
+                .unwrap_or("")
+        );
+        common::draw_table(
+            f,
+            &app.theme,
+            rect,
+            &["   Name", "Last played", "Game mode", "Version", "Size"],
+            rows,
+            &[
+                Constraint::Percentage(35),
+                Constraint::Percentage(20),
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
+            ],
+            Some(&title),
+            if state.worlds.is_empty() {
+                None
+            } else {
+                Some(state.selected)
+            },
+        );
+
+        if state.inner == InnerState::ConfirmDelete {
+            let world = state.selected_world().unwrap();
+            common::draw_button_dialog(
+                f,
+                &app.theme,
+                chunk,
+                10,
+                &format!(
+                    "Are you sure you want to delete {}? Its backups are kept.",
+                    world.name
+                ),
+                vec!["[ Yes ]", "[ No ]"],
+                state.confirm_selected,
+            )
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use ::anyhow::Context;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use crate::{endpoints::Endpoints, theme};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModSource {
+    Curse,
+}
+
+impl ModSource {
+    pub fn next(self) -> Self {
+        match self {
+            ModSource::Curse => ModSource::Curse,
+        }
+    }
+}
+
+impl fmt::Display for ModSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModSource::Curse => write!(f, "CurseForge"),
+        }
+    }
+}
+
+/// Which release types of mod files to show, matching curse's release_type 1, 2 and 3
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct ReleaseTypes {
+    pub release: bool,
+    pub beta: bool,
+    pub alpha: bool,
+}
+
+impl Default for ReleaseTypes {
+    fn default() -> Self {
+        Self {
+            release: true,
+            beta: true,
+            alpha: false,
+        }
+    }
+}
+
+impl ReleaseTypes {
+    pub fn allows(&self, release_type: u64) -> bool {
+        match release_type {
+            1 => self.release,
+            2 => self.beta,
+            3 => self.alpha,
+            _ => true,
+        }
+    }
+}
+
+/// How the home screen orders instances
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceSort {
+    Name,
+    /// Most recently played first
+    LastPlayed,
+    /// Newest first
+    MinecraftVersion,
+    /// Most mods first
+    ModCount,
+}
+
+impl InstanceSort {
+    pub fn next(self) -> Self {
+        match self {
+            InstanceSort::Name => InstanceSort::LastPlayed,
+            InstanceSort::LastPlayed => InstanceSort::MinecraftVersion,
+            InstanceSort::MinecraftVersion => InstanceSort::ModCount,
+            InstanceSort::ModCount => InstanceSort::Name,
+        }
+    }
+}
+
+impl fmt::Display for InstanceSort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InstanceSort::Name => write!(f, "name"),
+            InstanceSort::LastPlayed => write!(f, "last played"),
+            InstanceSort::MinecraftVersion => write!(f, "minecraft version"),
+            InstanceSort::ModCount => write!(f, "mod count"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub java_home: Option<PathBuf>,
+    /// Default maximum memory in MiB
+    pub memory: u64,
+    pub download_concurrency: usize,
+    pub mod_source: ModSource,
+    pub release_types: ReleaseTypes,
+    /// Total size of instance snapshots in MiB, 0 disabling them
+    pub snapshot_budget: u64,
+    /// Share identical mod jars between instances through the mod store
+    pub mod_store: bool,
+    /// Minutes a cached manifest is used without asking the server whether it changed
+    pub manifest_max_age: u64,
+    /// Remote services, before overrides from the environment
+    pub endpoints: Endpoints,
+    pub instance_sort: InstanceSort,
+    /// A built-in theme or the name of a file in the themes directory
+    pub theme: String,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            java_home: None,
+            memory: 2048,
+            download_concurrency: 8,
+            mod_source: ModSource::Curse,
+            release_types: Default::default(),
+            snapshot_budget: 2048,
+            mod_store: false,
+            manifest_max_age: 15,
+            endpoints: Default::default(),
+            instance_sort: InstanceSort::Name,
+            theme: String::from(theme::BUILT_IN[0]),
+            path: PathBuf::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub const MIN_MEMORY: u64 = 512;
+    pub const MAX_DOWNLOAD_CONCURRENCY: usize = 64;
+
+    pub fn from_file<P: Into<PathBuf>>(path: P) -> ::anyhow::Result<Self> {
+        let path = path.into();
+        match fs::File::open(&path) {
+            Ok(file) => {
+                let reader = BufReader::new(file);
+                let settings: Self =
+                    serde_json::from_reader(reader).context("Got malformed settings.")?;
+                Ok(Self { path, ..settings }.clamped())
+            }
+            _ => {
+                debug!("No settings file found.");
+                Ok(Self {
+                    path,
+                    ..Default::default()
+                })
+            }
+        }
+    }
+
+    /// Values the settings route would not accept brought within its limits, as they may come
+    /// from an edited file
+    fn clamped(mut self) -> Self {
+        if self.memory < Self::MIN_MEMORY {
+            warn!(
+                "Default memory of {} MiB is too low, using {} MiB.",
+                self.memory,
+                Self::MIN_MEMORY
+            );
+            self.memory = Self::MIN_MEMORY;
+        }
+        let download_concurrency = self
+            .download_concurrency
+            .clamp(1, Self::MAX_DOWNLOAD_CONCURRENCY);
+        if download_concurrency != self.download_concurrency {
+            warn!(
+                "Download concurrency of {} is out of range, using {}.",
+                self.download_concurrency, download_concurrency
+            );
+            self.download_concurrency = download_concurrency;
+        }
+        self
+    }
+
+    /// Atomically replace the settings file by writing a temporary file and renaming it over
+    pub fn save(&self) -> ::anyhow::Result<()> {
+        let temp_path = self.path.with_extension("json.tmp");
+        {
+            let mut writer = BufWriter::new(
+                fs::File::create(&temp_path).context("Could not create settings file.")?,
+            );
+            serde_json::to_writer_pretty(&mut writer, self).context("Failed to write settings.")?;
+            writer
+                .into_inner()
+                .context("Failed to write settings.")?
+                .sync_all()
+                .context("Failed to write settings.")?;
+        }
+        fs::rename(&temp_path, &self.path).context("Failed to replace settings file.")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+
+    #[test]
+    fn test_clamped() {
+        let temp = fixture::TempDir::new();
+        let path = temp.path().join("config.json");
+        fs::write(&path, r#"{"memory": 128, "download_concurrency": 0}"#).unwrap();
+
+        let settings = Settings::from_file(&path).unwrap();
+        assert_eq!(settings.memory, Settings::MIN_MEMORY);
+        assert_eq!(settings.download_concurrency, 1);
+    }
+
+    #[test]
+    fn test_save() {
+        let temp = fixture::TempDir::new();
+        let path = temp.path().join("config.json");
+        let mut settings = Settings::from_file(&path).unwrap();
+        settings.release_types.alpha = true;
+        settings.save().unwrap();
+
+        let settings = Settings::from_file(&path).unwrap();
+        assert!(settings.release_types.alpha);
+        assert!(!temp.path().join("config.json.tmp").exists());
+    }
+}
@@ -0,0 +1,305 @@
+use ::anyhow::{anyhow, Context};
+use chrono::{DateTime, Local};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::{instance::FileInstance, util, Instance};
+
+/// The entry holding the SnapshotInfo
+const INFO_NAME: &str = "snapshot.json";
+/// Prefix of the entries holding the instance directory
+const DIRECTORY_PREFIX: &str = "instance/";
+/// Directories of an instance that are not worth snapshotting
+const EXCLUDED_DIRECTORIES: [&str; 2] = ["logs", "backups"];
+
+#[derive(Deserialize, Serialize)]
+struct SnapshotInfo {
+    reason: String,
+    instance: FileInstance,
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub reason: String,
+    /// The instance as it was when the snapshot was taken
+    pub instance: Instance,
+    pub created: DateTime<Local>,
+    pub size: u64,
+}
+
+/// Compressed copies of instances (both the directory and the record) taken before risky
+/// operations, stored as <directory>/<instance uuid>/<timestamp>.zip
+#[derive(Clone)]
+pub struct Snapshots {
+    directory: PathBuf,
+}
+
+impl Snapshots {
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    fn zip_paths(&self) -> ::anyhow::Result<Vec<PathBuf>> {
+        if !self.directory.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(&self.directory).context("Failed to read snapshots directory.")? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(entry.path())? {
+                let path = entry?.path();
+                if path.extension().and_then(|e| e.to_str()) == Some("zip") {
+                    paths.push(path);
+                }
+            }
+        }
+        Ok(paths)
+    }
+
+    /// All snapshots of all instances, newest first
+    pub fn list(&self, instances_directory: &Path) -> ::anyhow::Result<Vec<Snapshot>> {
+        let mut snapshots = Vec::new();
+        for path in self.zip_paths()? {
+            match read_snapshot(&path, instances_directory) {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => warn!("Skipping snapshot {:?}: {:?}", path, e),
+            }
+        }
+        snapshots.sort_by_key(|snapshot| Reverse(snapshot.created));
+        Ok(snapshots)
+    }
+
+    /// Snapshot the instance, then remove the oldest snapshots until they fit in the budget
+    ///
+    /// The budget is in MiB, with 0 disabling snapshots entirely.
+    pub async fn create(
+        &self,
+        pb: &util::Progress,
+        instance: &Instance,
+        reason: &str,
+        budget: u64,
+    ) -> ::anyhow::Result<()> {
+        self.create_keeping(pb, instance, reason, budget, None)
+            .await
+    }
+
+    /// Like create, but never removing the kept snapshot to stay within the budget
+    async fn create_keeping(
+        &self,
+        pb: &util::Progress,
+        instance: &Instance,
+        reason: &str,
+        budget: u64,
+        keep: Option<&Path>,
+    ) -> ::anyhow::Result<()> {
+        if budget == 0 {
+            debug!("Snapshots are disabled.");
+            return Ok(());
+        }
+
+        let directory = self
+            .directory
+            .join(instance.uuid.to_hyphenated().to_string());
+        fs::create_dir_all(&directory).context("Failed to create snapshot directory!")?;
+
+        // Snapshots taken within the same second, such as before a rollback, must not overwrite
+        // each other
+        let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+        let mut path = directory.join(format!("{}.zip", timestamp));
+        let mut n = 2;
+        while path.exists() {
+            path = directory.join(format!("{}_{}.zip", timestamp, n));
+            n += 1;
+        }
+        let partial_path = path.with_extension("zip.part");
+
+        pb.set_msg(format!("Snapshotting {}.", instance.name)).await;
+        if let Err(e) = write_snapshot(pb, instance, reason, &partial_path).await {
+            let _ = fs::remove_file(&partial_path);
+            return Err(e);
+        }
+        fs::rename(&partial_path, &path).context("Failed to move snapshot into place!")?;
+
+        self.prune(budget * 1024 * 1024, &path, keep)
+    }
+
+    /// Remove the oldest snapshots until the total size is within the budget
+    fn prune(&self, budget: u64, newest: &Path, keep: Option<&Path>) -> ::anyhow::Result<()> {
+        let mut snapshots: Vec<(PathBuf, DateTime<Local>, u64)> = Vec::new();
+        for path in self.zip_paths()? {
+            let metadata = fs::metadata(&path)?;
+            snapshots.push((path, metadata.modified()?.into(), metadata.len()));
+        }
+        snapshots.sort_by_key(|(_, modified, _)| *modified);
+
+        let mut total: u64 = snapshots.iter().map(|(_, _, size)| size).sum();
+        for (path, _, size) in snapshots {
+            if total <= budget {
+                break;
+            }
+            // Never remove the snapshot that was just taken
+            if path == newest || Some(path.as_path()) == keep {
+                continue;
+            }
+            debug!("Removing snapshot {:?} to stay within budget.", path);
+            fs::remove_file(&path).context("Failed to remove old snapshot.")?;
+            total -= size;
+        }
+        if total > budget {
+            warn!("Snapshot {:?} alone exceeds the snapshot budget.", newest);
+        }
+        Ok(())
+    }
+
+    /// Replace the directory of the snapshot's instance with the snapshotted one
+    ///
+    /// The instance as it is now, unless it was removed, is snapshotted first so that the rollback
+    /// can be undone. What snapshots leave out (logs and world backups) is kept as it is. Restoring
+    /// the instance record is left to the caller.
+    pub async fn rollback(
+        &self,
+        pb: &util::Progress,
+        snapshot: &Snapshot,
+        current: Option<&Instance>,
+        budget: u64,
+    ) -> ::anyhow::Result<()> {
+        if let Some(current) = current {
+            let reason = format!(
+                "Before rolling back to {}",
+                snapshot.created.format("%Y-%m-%d %H:%M:%S")
+            );
+            self.create_keeping(pb, current, &reason, budget, Some(&snapshot.path))
+                .await
+                .context("Failed to snapshot instance before rolling back")?;
+        }
+
+        let mut archive = ZipArchive::new(BufReader::new(
+            File::open(&snapshot.path).context("Failed to open snapshot!")?,
+        ))?;
+        util::archive::replace_directory(
+            pb,
+            &mut archive,
+            DIRECTORY_PREFIX,
+            &snapshot.instance.directory(),
+            &EXCLUDED_DIRECTORIES,
+        )
+        .await
+        .context("Failed to roll back instance directory")
+    }
+}
+
+async fn write_snapshot(
+    pb: &util::Progress,
+    instance: &Instance,
+    reason: &str,
+    path: &Path,
+) -> ::anyhow::Result<()> {
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(path)?));
+
+    zip.start_file(INFO_NAME, FileOptions::default())?;
+    let info = SnapshotInfo {
+        reason: reason.to_owned(),
+        instance: instance.clone().to_file_instance(),
+    };
+    zip.write_all(&serde_json::to_vec_pretty(&info)?)?;
+
+    zip.add_directory(DIRECTORY_PREFIX, FileOptions::default())?;
+    let directory = instance.directory();
+    if directory.is_dir() {
+        let exclude: Vec<PathBuf> = EXCLUDED_DIRECTORIES
+            .iter()
+            .map(|excluded| directory.join(excluded))
+            .collect();
+        pb.set_length(util::directory_size(&directory, &exclude)?)
+            .await;
+        util::archive::add_directory(pb, &mut zip, &directory, DIRECTORY_PREFIX, &exclude)
+            .await
+            .context("Failed to snapshot instance directory")?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn read_snapshot(path: &Path, instances_directory: &Path) -> ::anyhow::Result<Snapshot> {
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let mut archive = ZipArchive::new(BufReader::new(file))?;
+    let info: SnapshotInfo = serde_json::from_reader(
+        archive
+            .by_name(INFO_NAME)
+            .map_err(|_| anyhow!("No {} in snapshot.", INFO_NAME))?,
+    )
+    .context("Got malformed snapshot info.")?;
+
+    Ok(Snapshot {
+        path: path.to_path_buf(),
+        reason: info.reason,
+        instance: Instance::from_file_instance(info.instance, instances_directory),
+        created: metadata.modified()?.into(),
+        size: metadata.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+
+    #[tokio::test]
+    async fn test_rollback_keeps_backups() {
+        let temp = fixture::TempDir::new();
+        let instance = Instance {
+            name: String::from("Survival"),
+            instances_directory: temp.path().join("instances"),
+            ..Default::default()
+        };
+        let directory = instance.directory();
+        fs::create_dir_all(directory.join("backups")).unwrap();
+        fs::write(directory.join("options.txt"), "old").unwrap();
+        fs::write(directory.join("backups").join("World.zip"), "world").unwrap();
+
+        let snapshots = Snapshots::new(temp.path().join("snapshots"));
+        let pb = util::Progress::new();
+        snapshots
+            .create(&pb, &instance, "Before testing", 1024)
+            .await
+            .unwrap();
+        fs::write(directory.join("options.txt"), "new").unwrap();
+
+        let list = snapshots.list(&instance.instances_directory).unwrap();
+        assert_eq!(list.len(), 1);
+        snapshots
+            .rollback(&pb, &list[0], Some(&instance), 1024)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(directory.join("options.txt")).unwrap(),
+            "old"
+        );
+        assert_eq!(
+            fs::read_to_string(directory.join("backups").join("World.zip")).unwrap(),
+            "world"
+        );
+        // The state before the rollback can be restored in turn
+        let list = snapshots.list(&instance.instances_directory).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(list
+            .iter()
+            .any(|snapshot| snapshot.reason.starts_with("Before rolling back to")));
+    }
+}
@@ -0,0 +1,381 @@
+use ::anyhow::{anyhow, Context};
+use serde::Deserialize;
+use std::{
+    convert::TryFrom,
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+use tui::style::{Color, Modifier, Style};
+
+/// The themes that need no file, the first being the default
+pub const BUILT_IN: [&str; 3] = ["dark", "light", "high_contrast"];
+
+/// How the ui draws text, by the role it plays
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Plain text, such as table cells and lists
+    pub text: Style,
+    /// The selected row or button
+    pub highlight: Style,
+    /// Table headers
+    pub header: Style,
+    /// Dialog text, loading messages and the status line
+    pub message: Style,
+    /// Invalid input
+    pub error: Style,
+    /// Things to keep in mind, such as running offline
+    pub warning: Style,
+    /// Buttons that are not selected and other secondary text
+    pub dimmed: Style,
+    /// Rows that cannot be chosen
+    pub unavailable: Style,
+    /// The characters matching a filter
+    pub matched: Style,
+    pub border: Style,
+    /// The help line at the bottom
+    pub help: Style,
+    pub help_key: Style,
+    /// Progress bars, the foreground being the filled part
+    pub gauge: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// For terminals with a dark background
+    pub fn dark() -> Self {
+        Self {
+            text: Style::default(),
+            highlight: Style::default().fg(Color::Blue).modifier(Modifier::BOLD),
+            header: Style::default().fg(Color::Yellow).modifier(Modifier::BOLD),
+            message: Style::default().fg(Color::Yellow),
+            error: Style::default().fg(Color::Red),
+            warning: Style::default().fg(Color::Red).modifier(Modifier::BOLD),
+            dimmed: Style::default().modifier(Modifier::DIM),
+            unavailable: Style::default().fg(Color::DarkGray),
+            matched: Style::default()
+                .fg(Color::Yellow)
+                .modifier(Modifier::UNDERLINED),
+            border: Style::default(),
+            help: Style::default().fg(Color::White),
+            help_key: Style::default().fg(Color::White).modifier(Modifier::BOLD),
+            gauge: Style::default()
+                .fg(Color::White)
+                .bg(Color::Black)
+                .modifier(Modifier::ITALIC),
+        }
+    }
+
+    /// For terminals with a light background, where yellow and white cannot be read
+    pub fn light() -> Self {
+        Self {
+            text: Style::default(),
+            highlight: Style::default().fg(Color::Blue).modifier(Modifier::BOLD),
+            header: Style::default().fg(Color::Magenta).modifier(Modifier::BOLD),
+            message: Style::default().fg(Color::Magenta),
+            error: Style::default().fg(Color::Red),
+            warning: Style::default().fg(Color::Red).modifier(Modifier::BOLD),
+            dimmed: Style::default().modifier(Modifier::DIM),
+            unavailable: Style::default().fg(Color::DarkGray),
+            matched: Style::default()
+                .fg(Color::Magenta)
+                .modifier(Modifier::UNDERLINED),
+            border: Style::default(),
+            help: Style::default().fg(Color::Black),
+            help_key: Style::default().fg(Color::Black).modifier(Modifier::BOLD),
+            gauge: Style::default()
+                .fg(Color::Blue)
+                .bg(Color::Gray)
+                .modifier(Modifier::ITALIC),
+        }
+    }
+
+    /// Bold colours on black, telling the selection apart by more than its colour
+    pub fn high_contrast() -> Self {
+        Self {
+            text: Style::default().fg(Color::White).bg(Color::Black),
+            highlight: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .modifier(Modifier::BOLD),
+            header: Style::default()
+                .fg(Color::White)
+                .modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            message: Style::default().fg(Color::White).modifier(Modifier::BOLD),
+            error: Style::default()
+                .fg(Color::LightRed)
+                .modifier(Modifier::BOLD),
+            warning: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightRed)
+                .modifier(Modifier::BOLD),
+            dimmed: Style::default().fg(Color::Gray),
+            unavailable: Style::default()
+                .fg(Color::Gray)
+                .modifier(Modifier::CROSSED_OUT),
+            matched: Style::default()
+                .fg(Color::LightCyan)
+                .modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            border: Style::default().fg(Color::White),
+            help: Style::default().fg(Color::White).bg(Color::Black),
+            help_key: Style::default()
+                .fg(Color::Yellow)
+                .bg(Color::Black)
+                .modifier(Modifier::BOLD),
+            gauge: Style::default()
+                .fg(Color::Yellow)
+                .bg(Color::Black)
+                .modifier(Modifier::BOLD),
+        }
+    }
+
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high_contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// A built-in theme, or the one in `<name>.json` in the themes directory
+    pub fn load(themes_directory: &Path, name: &str) -> ::anyhow::Result<Self> {
+        if let Some(theme) = Self::built_in(name) {
+            return Ok(theme);
+        }
+        let path = file(themes_directory, name);
+        let file = fs::File::open(&path).with_context(|| format!("Could not open {:?}", path))?;
+        let theme_file: ThemeFile = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Got malformed theme {:?}", path))?;
+        theme_file.apply()
+    }
+}
+
+fn file(themes_directory: &Path, name: &str) -> PathBuf {
+    themes_directory.join(format!("{}.json", name))
+}
+
+/// The built-in themes followed by the ones in the themes directory, by name
+pub fn names(themes_directory: &Path) -> Vec<String> {
+    let mut custom: Vec<String> = fs::read_dir(themes_directory)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            let name = path.file_stem()?.to_str()?.to_owned();
+            Some(name).filter(|name| !BUILT_IN.contains(&name.as_str()))
+        })
+        .collect();
+    custom.sort();
+    BUILT_IN
+        .iter()
+        .map(|name| String::from(*name))
+        .chain(custom)
+        .collect()
+}
+
+/// A theme file, overriding roles of a built-in theme
+///
+/// A role given replaces the whole style of that role, e.g.
+/// `{"base": "light", "highlight": {"fg": "#d75f00", "modifiers": ["bold"]}}`
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    /// Dark unless given
+    base: Option<String>,
+    text: Option<RoleStyle>,
+    highlight: Option<RoleStyle>,
+    header: Option<RoleStyle>,
+    message: Option<RoleStyle>,
+    error: Option<RoleStyle>,
+    warning: Option<RoleStyle>,
+    dimmed: Option<RoleStyle>,
+    unavailable: Option<RoleStyle>,
+    matched: Option<RoleStyle>,
+    border: Option<RoleStyle>,
+    help: Option<RoleStyle>,
+    help_key: Option<RoleStyle>,
+    gauge: Option<RoleStyle>,
+}
+
+impl ThemeFile {
+    fn apply(self) -> ::anyhow::Result<Theme> {
+        let base = self.base.as_deref().unwrap_or(BUILT_IN[0]);
+        let base = Theme::built_in(base).ok_or_else(|| {
+            anyhow!(
+                "Unknown base theme {}, expected one of {}",
+                base,
+                BUILT_IN.join(", ")
+            )
+        })?;
+        let or = |role: Option<RoleStyle>, base: Style| role.map(Style::from).unwrap_or(base);
+        Ok(Theme {
+            text: or(self.text, base.text),
+            highlight: or(self.highlight, base.highlight),
+            header: or(self.header, base.header),
+            message: or(self.message, base.message),
+            error: or(self.error, base.error),
+            warning: or(self.warning, base.warning),
+            dimmed: or(self.dimmed, base.dimmed),
+            unavailable: or(self.unavailable, base.unavailable),
+            matched: or(self.matched, base.matched),
+            border: or(self.border, base.border),
+            help: or(self.help, base.help),
+            help_key: or(self.help_key, base.help_key),
+            gauge: or(self.gauge, base.gauge),
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RoleStyle {
+    #[serde(default)]
+    fg: Option<ThemeColor>,
+    #[serde(default)]
+    bg: Option<ThemeColor>,
+    #[serde(default)]
+    modifiers: Vec<ThemeModifier>,
+}
+
+impl From<RoleStyle> for Style {
+    fn from(role: RoleStyle) -> Self {
+        let mut style = Style::default();
+        if let Some(ThemeColor(fg)) = role.fg {
+            style = style.fg(fg);
+        }
+        if let Some(ThemeColor(bg)) = role.bg {
+            style = style.bg(bg);
+        }
+        style.modifier(
+            role.modifiers
+                .into_iter()
+                .fold(Modifier::empty(), |modifiers, modifier| {
+                    modifiers | modifier.into()
+                }),
+        )
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ThemeModifier {
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    Reversed,
+    CrossedOut,
+}
+
+impl From<ThemeModifier> for Modifier {
+    fn from(modifier: ThemeModifier) -> Self {
+        match modifier {
+            ThemeModifier::Bold => Modifier::BOLD,
+            ThemeModifier::Dim => Modifier::DIM,
+            ThemeModifier::Italic => Modifier::ITALIC,
+            ThemeModifier::Underlined => Modifier::UNDERLINED,
+            ThemeModifier::Reversed => Modifier::REVERSED,
+            ThemeModifier::CrossedOut => Modifier::CROSSED_OUT,
+        }
+    }
+}
+
+/// A colour by name (e.g. `light_blue`), as `#rrggbb`, or as an index into the 256 colours
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "String")]
+struct ThemeColor(Color);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let color = match s.as_str() {
+            "reset" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" => Color::Gray,
+            "dark_gray" => Color::DarkGray,
+            "light_red" => Color::LightRed,
+            "light_green" => Color::LightGreen,
+            "light_yellow" => Color::LightYellow,
+            "light_blue" => Color::LightBlue,
+            "light_magenta" => Color::LightMagenta,
+            "light_cyan" => Color::LightCyan,
+            "white" => Color::White,
+            _ => {
+                if let Some(hex) = s.strip_prefix('#') {
+                    let channel = |i: usize| {
+                        hex.get(i..i + 2)
+                            .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+                    };
+                    match (hex.len(), channel(0), channel(2), channel(4)) {
+                        (6, Some(r), Some(g), Some(b)) => Color::Rgb(r, g, b),
+                        _ => return Err(format!("{} is not a #rrggbb colour", s)),
+                    }
+                } else {
+                    Color::Indexed(
+                        s.parse()
+                            .map_err(|_| format!("{} is not a known colour", s))?,
+                    )
+                }
+            }
+        };
+        Ok(ThemeColor(color))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+
+    #[test]
+    fn test_colors() {
+        let color = |s: &str| ThemeColor::try_from(String::from(s)).map(|ThemeColor(c)| c);
+        assert_eq!(color("dark_gray"), Ok(Color::DarkGray));
+        assert_eq!(color("#d75f00"), Ok(Color::Rgb(0xd7, 0x5f, 0x00)));
+        assert_eq!(color("208"), Ok(Color::Indexed(208)));
+        assert!(color("#d75f0").is_err());
+        assert!(color("orange").is_err());
+    }
+
+    #[test]
+    fn test_load() {
+        let temp = fixture::TempDir::new();
+        fs::write(
+            file(temp.path(), "solarized"),
+            r##"{"base": "light", "highlight": {"fg": "#268bd2", "modifiers": ["bold", "reversed"]}}"##,
+        )
+        .unwrap();
+        fs::write(file(temp.path(), "broken"), r#"{"hilight": {}}"#).unwrap();
+
+        let theme = Theme::load(temp.path(), "solarized").unwrap();
+        assert_eq!(
+            theme.highlight,
+            Style::default()
+                .fg(Color::Rgb(0x26, 0x8b, 0xd2))
+                .modifier(Modifier::BOLD | Modifier::REVERSED)
+        );
+        assert_eq!(theme.header, Theme::light().header);
+        assert!(Theme::load(temp.path(), "broken").is_err());
+        assert!(Theme::load(temp.path(), "missing").is_err());
+        assert_eq!(
+            names(temp.path()),
+            ["dark", "light", "high_contrast", "broken", "solarized"]
+        );
+    }
+}
@@ -1,18 +1,20 @@
 use log::trace;
-use std::{io::Stdout, time::Instant};
+use std::time::Instant;
 use tui::{
-    backend::CrosstermBackend,
+    backend::Backend,
     layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
     widgets::{Paragraph, Text},
     Frame,
 };
 
 use crate::App;
 
-pub type UiFrame<'a> = Frame<'a, CrosstermBackend<Stdout>>;
+pub type UiFrame<'a, B> = Frame<'a, B>;
 
-pub async fn draw_layout(f: &mut UiFrame<'_>, app: &mut App) -> ::anyhow::Result<()> {
+pub async fn draw_layout<B: Backend + Send>(
+    f: &mut UiFrame<'_, B>,
+    app: &mut App,
+) -> ::anyhow::Result<()> {
     let instant = Instant::now();
     let routes: Vec<_> = app.get_current_routes().into_iter().cloned().collect();
 
@@ -24,25 +26,46 @@ pub async fn draw_layout(f: &mut UiFrame<'_>, app: &mut App) -> ::anyhow::Result
 
     for (i, route) in routes.iter().enumerate() {
         let implementation = route.get_impl();
-        Some(implementation.draw(f, app, parent_layout[0]).await);
+        // Only the topmost route gets to show the cursor
+        app.cursor = None;
+        route.get_draw().draw(f, app, parent_layout[0]).await;
 
         if i == routes.len() - 1 {
-            let raw_help = implementation.get_help(app);
+            let mut line = Vec::new();
+            if app.offline {
+                line.push(Text::styled("OFFLINE", app.theme.warning));
+                line.push(Text::raw("   "));
+            }
+
+            if let Some(status) = &app.status {
+                line.push(Text::styled(status.clone(), app.theme.message));
+                f.render_widget(
+                    Paragraph::new(line.iter()).style(app.theme.help),
+                    parent_layout[1],
+                );
+                continue;
+            }
+
+            let mut raw_help = implementation.get_help(app);
+            if app.jobs.shown_on(route).is_some() {
+                raw_help.push(("ctrl+B", "background"));
+            }
 
             let help = raw_help
                 .iter()
                 .map(|(key, text)| {
                     vec![
-                        Text::styled(key.clone(), Style::default().modifier(Modifier::BOLD)),
+                        Text::styled(key.clone(), app.theme.help_key),
                         Text::raw(" "),
                         Text::raw(text.clone()),
                     ]
                 })
                 .collect::<Vec<_>>()
                 .join(&Text::raw("   "));
+            line.extend(help);
 
             f.render_widget(
-                Paragraph::new(help.iter()).style(Style::default().fg(Color::White)),
+                Paragraph::new(line.iter()).style(app.theme.help),
                 parent_layout[1],
             );
         }
@@ -52,3 +75,204 @@ pub async fn draw_layout(f: &mut UiFrame<'_>, app: &mut App) -> ::anyhow::Result
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        jobs::Status,
+        minecraft,
+        routes::{error, instance_menu, new_instance, remove_instance, rename_instance},
+        util::{self, fixture},
+        IoEvent, Key, Route,
+    };
+    use chrono::{Local, TimeZone};
+    use std::{env, fs, path::PathBuf};
+    use tui::{backend::TestBackend, Terminal};
+
+    /// Draw the app the way the main loop does and return the text on screen
+    ///
+    /// Trailing spaces are trimmed and the cursor, if shown, is noted on the last line. Styling is
+    /// not part of the snapshot.
+    async fn render(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        app.cursor = None;
+        let mut frame = terminal.get_frame();
+        draw_layout(&mut frame, app).await.unwrap();
+        terminal.draw(|_| {}).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let mut text = String::new();
+        for y in 0..buffer.area.height {
+            let line: String = (0..buffer.area.width)
+                .map(|x| buffer.get(x, y).symbol.as_str())
+                .collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        if let Some((x, y)) = app.cursor {
+            text.push_str(&format!("cursor at {}, {}\n", x, y));
+        }
+        text
+    }
+
+    /// Compare against resources/test/snapshots/<name>.txt
+    ///
+    /// Run with POLYBLOCK_UPDATE_SNAPSHOTS=1 to accept the current rendering instead.
+    fn assert_snapshot(name: &str, text: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/test/snapshots")
+            .join(format!("{}.txt", name));
+        if env::var_os("POLYBLOCK_UPDATE_SNAPSHOTS").is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, text).unwrap();
+            return;
+        }
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            expected == text,
+            "{} does not match its snapshot, run with POLYBLOCK_UPDATE_SNAPSHOTS=1 if this is \
+             intended.\n--- expected\n{}--- actual\n{}",
+            name,
+            expected,
+            text
+        );
+    }
+
+    fn instance(app: &App, name: &str) -> crate::Instance {
+        app.instances.inner[name].clone()
+    }
+
+    #[tokio::test]
+    async fn test_home() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        assert_snapshot("home", &render(&mut app).await);
+
+        app.offline = true;
+        app.status = Some(String::from("Adding forge needs the network."));
+        assert_snapshot("home_offline_status", &render(&mut app).await);
+    }
+
+    #[tokio::test]
+    async fn test_new_instance() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        app.push_route(Route::NewInstance);
+        app.state.new_instance.name_input = String::from("Creative");
+        assert_snapshot("new_instance_enter_name", &render(&mut app).await);
+
+        let manifest = fs::read(fixture::path(
+            "https://launchermeta.mojang.com/mc/game/version_manifest.json",
+        ))
+        .unwrap();
+        let manifest: minecraft::VersionManifest = serde_json::from_slice(&manifest).unwrap();
+        app.minecraft_version_manifest = Some(manifest);
+        app.state.new_instance.inner = new_instance::InnerState::ChooseMinecraftVersion;
+        assert_snapshot(
+            "new_instance_choose_minecraft_version",
+            &render(&mut app).await,
+        );
+
+        for key in "/1.16".chars().map(Key::Char) {
+            crate::input::handle(key, &mut app);
+        }
+        assert_snapshot(
+            "new_instance_filter_minecraft_version",
+            &render(&mut app).await,
+        );
+    }
+
+    #[tokio::test]
+    async fn test_progress() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        app.push_route(Route::NewInstance);
+
+        let main_pb = util::Progress::new();
+        main_pb.set_length(4).await;
+        main_pb
+            .inc_with_msg(2, "Downloading minecraft version.")
+            .await;
+        let sub_pb = util::Progress::new();
+        sub_pb.set_msg("Downloading version jar.").await;
+        sub_pb.set_length(200).await;
+        sub_pb.set_value(50).await;
+        app.state.new_instance.inner = new_instance::InnerState::Install;
+        app.state.new_instance.progress_main = Some(main_pb);
+        app.state.new_instance.progress_sub = Some(sub_pb);
+        assert_snapshot("new_instance_install", &render(&mut app).await);
+
+        crate::input::handle(Key::Esc, &mut app);
+        assert_snapshot("new_instance_confirm_cancel", &render(&mut app).await);
+    }
+
+    #[tokio::test]
+    async fn test_instance_menu() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        app.state.instance_menu = instance_menu::State::new(instance(&app, "Survival"));
+        app.push_route(Route::InstanceMenu);
+        assert_snapshot("instance_menu", &render(&mut app).await);
+    }
+
+    #[tokio::test]
+    async fn test_dialogs() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+
+        // The dialog shows the directory, which would otherwise differ between runs
+        let mut vanilla = instance(&app, "Vanilla");
+        vanilla.instances_directory = PathBuf::from("/home/steve/.local/share/polyblock/instances");
+        app.state.remove_instance = remove_instance::State::new(vanilla);
+        app.push_route(Route::RemoveInstance);
+        assert_snapshot("remove_instance", &render(&mut app).await);
+        app.pop_route();
+
+        app.state.rename_instance = rename_instance::State::new(instance(&app, "Vanilla"));
+        app.push_route(Route::RenameInstance);
+        assert_snapshot("rename_instance", &render(&mut app).await);
+    }
+
+    #[tokio::test]
+    async fn test_error() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        app.push_route(Route::NewInstance);
+        let e = ::anyhow::anyhow!("connection refused")
+            .context("Failed to get minecraft version manifest.");
+        error::show(
+            &mut app,
+            error::Failure::new(IoEvent::NewInstanceFetchMinecraftVersionManifest, &e),
+        );
+        assert_snapshot("error", &render(&mut app).await);
+    }
+
+    #[tokio::test]
+    async fn test_jobs() {
+        let temp = fixture::TempDir::new();
+        let (mut app, _io_rx) = App::fake(temp.path());
+        let jobs = [
+            ("Verify the mod store", Status::Finished),
+            ("Back up world New World", Status::Failed),
+            ("Create instance Creative", Status::Running),
+            ("Add mods to the mod store", Status::Queued),
+        ];
+        for (title, status) in jobs.iter().cloned() {
+            let id = app.jobs.add(String::from(title), None);
+            app.jobs.set_status(id, status);
+            // Start times would otherwise differ between runs
+            let job = app.jobs.get_mut(id).unwrap();
+            job.started = Local.ymd(2020, 5, 1).and_hms(12, 0, id as u32);
+            if status == Status::Running {
+                let pb = util::Progress::new();
+                pb.set_length(4).await;
+                pb.inc_with_msg(1, "Downloading minecraft version.").await;
+                job.progress = Some(pb);
+            }
+        }
+
+        app.push_route(Route::Jobs);
+        assert_snapshot("jobs", &render(&mut app).await);
+    }
+}
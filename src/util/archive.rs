@@ -0,0 +1,150 @@
+use ::anyhow::{anyhow, Context};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read, Seek, Write},
+    path::{Component, Path, PathBuf},
+};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use super::{PartialDirectory, Progress};
+
+/// Recursively add a directory to a zip, with entry names relative to the directory
+///
+/// Entries are put below prefix (which should be empty or end in a /), and the progress is
+/// incremented by the number of bytes read.
+pub async fn add_directory<W: Write + Seek>(
+    pb: &Progress,
+    zip: &mut ZipWriter<W>,
+    directory: &Path,
+    prefix: &str,
+    exclude: &[PathBuf],
+) -> ::anyhow::Result<()> {
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut stack = vec![directory.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let path = entry.path();
+            if exclude.contains(&path) {
+                continue;
+            }
+            let name = format!(
+                "{}{}",
+                prefix,
+                path.strip_prefix(directory)?
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            );
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                zip.add_directory(name, options)?;
+                stack.push(path);
+            } else if file_type.is_file() {
+                zip.start_file(name, options)?;
+                let copied = io::copy(&mut BufReader::new(File::open(&path)?), zip)
+                    .with_context(|| format!("Failed to add {:?} to archive", path))?;
+                pb.inc(copied).await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Extract the entries below prefix into a directory, stripping the prefix
+pub async fn extract<R: Read + Seek>(
+    pb: &Progress,
+    archive: &mut ZipArchive<R>,
+    prefix: &str,
+    directory: &Path,
+) -> ::anyhow::Result<()> {
+    let mut total = 0;
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        if file.name().starts_with(prefix) {
+            total += file.size();
+        }
+    }
+    pb.set_length(total).await;
+
+    fs::create_dir_all(directory)?;
+    for i in 0..archive.len() {
+        // Entries are not Send, so none may be held across an await
+        let copied = {
+            let mut file = archive.by_index(i)?;
+            let name = match file.name().strip_prefix(prefix) {
+                Some(name) if !name.is_empty() => PathBuf::from(name),
+                _ => continue,
+            };
+            if name
+                .components()
+                .any(|component| !matches!(component, Component::Normal(_)))
+            {
+                return Err(anyhow!("Archive contains unsafe path {:?}", name));
+            }
+            let path = directory.join(name);
+            if file.is_dir() {
+                fs::create_dir_all(&path)?;
+                0
+            } else {
+                fs::create_dir_all(path.parent().unwrap())?;
+                io::copy(&mut file, &mut File::create(&path)?)
+                    .with_context(|| format!("Failed to extract {:?}", path))?
+            }
+        };
+        pb.inc(copied).await;
+    }
+
+    Ok(())
+}
+
+/// Replace a directory with the entries below prefix of an archive
+///
+/// Everything is extracted next to the directory first, so a broken archive leaves it untouched.
+/// The kept entries of the old directory, which the archive does not hold, are moved over rather
+/// than removed with it.
+pub async fn replace_directory<R: Read + Seek>(
+    pb: &Progress,
+    archive: &mut ZipArchive<R>,
+    prefix: &str,
+    directory: &Path,
+    keep: &[&str],
+) -> ::anyhow::Result<()> {
+    let partial = PartialDirectory::new(directory);
+    extract(pb, archive, prefix, partial.path()).await?;
+
+    // The old directory stays aside until the kept entries are out of it
+    let mut old_directory = directory.as_os_str().to_owned();
+    old_directory.push(".old");
+    let old_directory = PathBuf::from(old_directory);
+    let _ = fs::remove_dir_all(&old_directory);
+    if !directory.exists() {
+        return partial.finish();
+    }
+    fs::rename(directory, &old_directory)
+        .with_context(|| format!("Failed to move old {:?} aside", directory))?;
+    if let Err(e) = partial.finish() {
+        let _ = fs::rename(&old_directory, directory);
+        return Err(e);
+    }
+
+    for name in keep {
+        let kept = old_directory.join(name);
+        if !kept.exists() {
+            continue;
+        }
+        let target = directory.join(name);
+        if target.is_dir() {
+            fs::remove_dir_all(&target)?;
+        } else if target.exists() {
+            fs::remove_file(&target)?;
+        }
+        fs::rename(&kept, &target)
+            .with_context(|| format!("Failed to keep {:?}, it is left in {:?}", name, kept))?;
+    }
+    fs::remove_dir_all(&old_directory)
+        .with_context(|| format!("Failed to remove old {:?}", old_directory))
+}
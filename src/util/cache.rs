@@ -0,0 +1,34 @@
+use ::anyhow::anyhow;
+use std::{fs, path::Path, time::Duration};
+
+/// How fetches may use what is cached on disk
+#[derive(Debug, Clone, Copy)]
+pub struct CachePolicy {
+    /// Never contact the network, only use what is cached
+    pub offline: bool,
+    /// Cached data younger than this is used without asking the server whether it changed
+    pub max_age: Duration,
+}
+
+impl CachePolicy {
+    /// Whether the cache file can be used as is
+    pub fn is_fresh(&self, path: &Path) -> bool {
+        if self.offline {
+            return path.is_file();
+        }
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age < self.max_age)
+            .unwrap_or(false)
+    }
+
+    /// The error for something that is neither cached nor allowed to be fetched
+    pub fn not_cached(&self, what: &str) -> ::anyhow::Error {
+        anyhow!(
+            "No cached {} - run polyblock without --offline once to download it.",
+            what
+        )
+    }
+}
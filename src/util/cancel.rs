@@ -0,0 +1,91 @@
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// How often a guarded future looks for a cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Lets the ui stop a long running operation, which gives up at its next checkpoint
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// A checkpoint - fails with [`Cancelled`] once cancelled
+    pub fn check(&self) -> ::anyhow::Result<()> {
+        if self.is_cancelled() {
+            Err(Cancelled.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Run a future, dropping it as soon as we are cancelled
+    pub async fn guard<F: Future>(&self, future: F) -> ::anyhow::Result<F::Output> {
+        futures::pin_mut!(future);
+        loop {
+            self.check()?;
+            match futures::future::select(future, tokio::time::delay_for(POLL_INTERVAL)).await {
+                futures::future::Either::Left((output, _)) => return Ok(output),
+                futures::future::Either::Right((_, unfinished)) => future = unfinished,
+            }
+        }
+    }
+}
+
+/// The error an operation gives up with when cancelled
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cancelled.")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Whether an error comes from an operation being cancelled, rather than anything going wrong
+pub fn is_cancelled(e: &::anyhow::Error) -> bool {
+    e.chain().any(|cause| cause.is::<Cancelled>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_guard() {
+        let cancel = Cancel::new();
+        assert_eq!(cancel.guard(async { 1 }).await.unwrap(), 1);
+
+        let canceller = cancel.clone();
+        let e = cancel
+            .guard(async move {
+                tokio::time::delay_for(POLL_INTERVAL).await;
+                canceller.cancel();
+                futures::future::pending::<()>().await
+            })
+            .await
+            .unwrap_err();
+        assert!(is_cancelled(&e.context("Failed to wait")));
+        assert!(cancel.check().is_err());
+    }
+}
@@ -0,0 +1,294 @@
+use ::anyhow::{anyhow, Context};
+use bytes::Buf;
+use futures::stream::StreamExt;
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use reqwest::{header, StatusCode};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    sync::{Semaphore, SemaphorePermit},
+};
+
+use super::{is_cancelled, sha1_file, Cancel, Progress};
+use crate::endpoints;
+
+/// How many times a failed download is retried before giving up
+const RETRIES: u32 = 4;
+
+/// Delay before the first retry, doubled for every following one
+const BACKOFF: Duration = Duration::from_millis(500);
+
+const DEFAULT_CONCURRENCY: usize = 8;
+
+lazy_static! {
+    /// The one client for every request, so that they all go out the same way. Without gzip, as
+    /// downloads resume with range requests and check their length.
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .build()
+        .unwrap();
+    /// Shared by every download, so the concurrency limit holds across installers running at once
+    static ref LIMIT: Semaphore = Semaphore::new(DEFAULT_CONCURRENCY);
+}
+
+static CONCURRENCY: AtomicUsize = AtomicUsize::new(DEFAULT_CONCURRENCY);
+
+/// Permits still to be taken out of circulation after lowering the concurrency while they were
+/// in use
+static DEBT: AtomicUsize = AtomicUsize::new(0);
+
+/// Set when running with --offline, making every download fail
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// The client every request is made with, cheap to clone as it shares its connection pool
+pub fn client() -> reqwest::Client {
+    CLIENT.clone()
+}
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::SeqCst);
+}
+
+/// Change how many downloads may run at once
+pub fn set_concurrency(concurrency: usize) {
+    let current = CONCURRENCY.swap(concurrency, Ordering::SeqCst);
+    if concurrency > current {
+        let mut grow = concurrency - current;
+        while grow > 0 {
+            let debt = DEBT.load(Ordering::SeqCst);
+            if debt == 0 {
+                break;
+            }
+            let paid = debt.min(grow);
+            if DEBT
+                .compare_exchange(debt, debt - paid, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                grow -= paid;
+            }
+        }
+        LIMIT.add_permits(grow);
+    } else {
+        let mut shrink = current - concurrency;
+        while shrink > 0 {
+            match LIMIT.try_acquire() {
+                Ok(permit) => permit.forget(),
+                Err(_) => break,
+            }
+            shrink -= 1;
+        }
+        DEBT.fetch_add(shrink, Ordering::SeqCst);
+    }
+}
+
+/// A download slot, returned to the pool (or retired, if the limit was lowered) when dropped
+struct Slot(Option<SemaphorePermit<'static>>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let permit = self.0.take().unwrap();
+        let retire = DEBT
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |debt| {
+                debt.checked_sub(1)
+            })
+            .is_ok();
+        if retire {
+            permit.forget();
+        }
+    }
+}
+
+async fn acquire_slot() -> Slot {
+    Slot(Some(LIMIT.acquire().await))
+}
+
+/// What a download is known to look like before it starts
+#[derive(Debug, Clone, Default)]
+pub struct Expected {
+    pub size: Option<u64>,
+    pub sha1: Option<String>,
+}
+
+enum Failure {
+    Retry(::anyhow::Error),
+    Fatal(::anyhow::Error),
+}
+
+impl From<reqwest::Error> for Failure {
+    fn from(e: reqwest::Error) -> Self {
+        // Client errors will not get better by asking again
+        match e.status() {
+            Some(status) if status.is_client_error() => Failure::Fatal(e.into()),
+            _ => Failure::Retry(e.into()),
+        }
+    }
+}
+
+impl From<std::io::Error> for Failure {
+    fn from(e: std::io::Error) -> Self {
+        Failure::Fatal(e.into())
+    }
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap().to_os_string();
+    file_name.push(".part");
+    path.with_file_name(file_name)
+}
+
+/// Download to a .part file next to the target, resuming what an earlier attempt left there
+async fn fetch(
+    pb: Option<&Progress>,
+    url: &str,
+    part_path: &Path,
+    expected: &Expected,
+    cancel: &Cancel,
+) -> Result<(), Failure> {
+    let _slot = cancel.guard(acquire_slot()).await.map_err(Failure::Fatal)?;
+
+    let mut offset = fs::metadata(part_path).map(|m| m.len()).unwrap_or(0);
+    if let Some(size) = expected.size {
+        if offset > size {
+            fs::remove_file(part_path)?;
+            offset = 0;
+        } else if offset == size {
+            return Ok(());
+        }
+    }
+
+    let mut request = CLIENT.get(url);
+    if offset > 0 {
+        debug!("Resuming {} from byte {}.", url, offset);
+        request = request.header(header::RANGE, format!("bytes={}-", offset));
+    }
+    let response = request.send().await?;
+    match response.status() {
+        StatusCode::PARTIAL_CONTENT => {}
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // Whatever we have does not belong to this file - start over
+            fs::remove_file(part_path)?;
+            return Err(Failure::Retry(anyhow!("Could not resume {}", url)));
+        }
+        // The server ignored the range and sent the whole file
+        _ => offset = 0,
+    }
+    let response = response.error_for_status()?;
+
+    if let Some(pb) = pb {
+        if let Some(content_length) = response.content_length() {
+            pb.set_length(offset + content_length).await;
+        }
+        pb.set_value(offset).await;
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(offset > 0)
+        .truncate(offset == 0)
+        .open(part_path)
+        .await?;
+    let mut stream = response.bytes_stream();
+    while let Some(v) = stream.next().await {
+        cancel.check().map_err(Failure::Fatal)?;
+        let mut v = v?;
+        while v.has_remaining() {
+            // Writes some prefix of the byte string, not necessarily all of it
+            let written = file.write_buf(&mut v).await?;
+            if let Some(pb) = pb {
+                pb.inc(written as u64).await;
+            }
+        }
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+/// Check a finished .part file, removing it if it is not what we expected
+fn verify(part_path: &Path, expected: &Expected) -> Result<(), Failure> {
+    if let Some(size) = expected.size {
+        let actual = fs::metadata(part_path)?.len();
+        if actual != size {
+            fs::remove_file(part_path)?;
+            return Err(Failure::Retry(anyhow!(
+                "Expected {} bytes but got {}.",
+                size,
+                actual
+            )));
+        }
+    }
+    if let Some(sha1) = &expected.sha1 {
+        let actual = sha1_file(part_path).map_err(Failure::Fatal)?;
+        if &actual != sha1 {
+            fs::remove_file(part_path)?;
+            return Err(Failure::Retry(anyhow!(
+                "Checksum doesn't match. {} != {}",
+                actual,
+                sha1
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Download a file, only moving it into place once it is complete and verified
+///
+/// Interrupted downloads are resumed with a range request and failures are retried with
+/// exponential backoff. At most the configured number of downloads run at once. A cancelled
+/// download removes what it fetched so far instead of leaving it to be resumed.
+pub async fn download(
+    pb: Option<&Progress>,
+    url: &str,
+    path: &Path,
+    expected: &Expected,
+    cancel: &Cancel,
+) -> ::anyhow::Result<()> {
+    if OFFLINE.load(Ordering::SeqCst) {
+        return Err(anyhow!(
+            "Cannot download {} while offline - restart without --offline.",
+            url
+        ));
+    }
+    fs::create_dir_all(path.parent().unwrap()).context("Couldn't create parent folder.")?;
+    let part_path = part_path(path);
+    let url = &endpoints::current().rewrite(url);
+
+    let mut attempt = 0;
+    loop {
+        let result = match fetch(pb, url, &part_path, expected, cancel).await {
+            Ok(()) => verify(&part_path, expected),
+            Err(failure) => Err(failure),
+        };
+        match result {
+            Ok(()) => break,
+            Err(Failure::Retry(e)) if attempt < RETRIES => {
+                let delay = BACKOFF * 2u32.pow(attempt);
+                warn!(
+                    "Download of {} failed, retrying in {:?}: {:?}",
+                    url, delay, e
+                );
+                cancel.guard(tokio::time::delay_for(delay)).await?;
+                attempt += 1;
+            }
+            Err(Failure::Fatal(e)) if is_cancelled(&e) => {
+                let _ = fs::remove_file(&part_path);
+                return Err(e);
+            }
+            Err(Failure::Retry(e)) | Err(Failure::Fatal(e)) => {
+                return Err(e.context(format!("Failed to download {}", url)));
+            }
+        }
+    }
+
+    fs::rename(&part_path, path).with_context(|| format!("Failed to move {:?} into place!", path))
+}
@@ -0,0 +1,196 @@
+//! Local stand-ins for the remote services, so fetches and installs can be tested offline
+//!
+//! The server answers requests for `https://<host>/<path>` with the recorded file at
+//! `resources/test/fixtures/<host>/<path>`, or `<path>/index.json` if that is a directory.
+//! Every request is logged so tests can check what was actually asked for.
+
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+};
+use uuid::Uuid;
+
+use super::sha1_file;
+use crate::endpoints::{self, Endpoints, Mirror};
+
+/// Hosts served by the fixture server instead of the internet
+const HOSTS: &[&str] = &[
+    "launchermeta.mojang.com",
+    "launcher.mojang.com",
+    "libraries.minecraft.net",
+    "maven.minecraftforge.net",
+    "addons-ecs.forgesvc.net",
+];
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub path: String,
+    /// Keyed by lowercase header name
+    pub headers: HashMap<String, String>,
+    pub status: u16,
+}
+
+pub struct Server {
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+lazy_static! {
+    static ref SERVER: Server = Server::start();
+}
+
+/// The fixture server, started on first use with every endpoint mirrored to it
+///
+/// It is shared by all tests, which is why tests should only look at requests for urls no other
+/// test fetches.
+pub fn server() -> &'static Server {
+    &SERVER
+}
+
+/// The recorded file served for a remote url
+pub fn path(url: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("resources/test/fixtures")
+        .join(url_path(url).trim_start_matches('/'));
+    if path.is_dir() {
+        path.join("index.json")
+    } else {
+        path
+    }
+}
+
+/// The path a remote url is requested as from the fixture server
+fn url_path(url: &str) -> String {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("/"))
+        .unwrap_or(url);
+    format!("/{}", rest)
+}
+
+impl Server {
+    fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        {
+            let requests = Arc::clone(&requests);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let stream = stream.unwrap();
+                    let requests = Arc::clone(&requests);
+                    thread::spawn(move || handle(stream, &requests));
+                }
+            });
+        }
+
+        endpoints::set(Endpoints {
+            mirrors: HOSTS
+                .iter()
+                .map(|host| Mirror {
+                    from: format!("https://{}/", host),
+                    to: format!("http://{}/{}/", address, host),
+                })
+                .collect(),
+            ..Default::default()
+        });
+
+        Self { requests }
+    }
+
+    /// Requests made for a remote url, oldest first
+    pub fn requests(&self, url: &str) -> Vec<Request> {
+        let path = url_path(url);
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.path == path)
+            .cloned()
+            .collect()
+    }
+}
+
+fn handle(stream: TcpStream, requests: &Mutex<Vec<Request>>) {
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let path = line.split_whitespace().nth(1).unwrap_or("/").to_owned();
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).unwrap() == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some(i) = line.find(':') {
+            headers.insert(
+                line[..i].trim().to_lowercase(),
+                line[i + 1..].trim().to_owned(),
+            );
+        }
+    }
+
+    let file = self::path(&path);
+    let (status, head, body) = if file.is_file() {
+        let etag = format!("\"{}\"", sha1_file(&file).unwrap());
+        if headers.get("if-none-match") == Some(&etag) {
+            (304, format!("ETag: {}\r\n", etag), Vec::new())
+        } else {
+            (200, format!("ETag: {}\r\n", etag), fs::read(&file).unwrap())
+        }
+    } else {
+        (404, String::new(), Vec::new())
+    };
+
+    requests.lock().unwrap().push(Request {
+        path,
+        headers,
+        status,
+    });
+
+    let mut stream = reader.into_inner();
+    let reason = match status {
+        200 => "OK",
+        304 => "Not Modified",
+        _ => "Not Found",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        head,
+        body.len()
+    )
+    .unwrap();
+    stream.write_all(&body).unwrap();
+}
+
+/// A fresh directory under the system temp directory, removed again when dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        let path = env::temp_dir().join(format!("polyblock-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
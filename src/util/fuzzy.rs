@@ -0,0 +1,52 @@
+/// Where the characters of a query appear in order in a text, ignoring case
+///
+/// Returns the indices of the matched characters, preferring the query as one piece over it
+/// spread out, or nothing if the text does not contain it.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<Vec<usize>> {
+    // One char for one char, so that indices stay those of the text
+    let lowercase = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let query: Vec<char> = query.chars().map(lowercase).collect();
+    let text: Vec<char> = text.chars().map(lowercase).collect();
+    if query.is_empty() {
+        return Some(Vec::new());
+    }
+
+    if let Some(start) = text
+        .windows(query.len())
+        .position(|window| window == query.as_slice())
+    {
+        return Some((start..start + query.len()).collect());
+    }
+
+    let mut indices = Vec::with_capacity(query.len());
+    let mut query = query.iter().peekable();
+    for (i, c) in text.iter().enumerate() {
+        match query.peek() {
+            Some(wanted) if *wanted == c => {
+                indices.push(i);
+                query.next();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    if query.peek().is_none() {
+        Some(indices)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(fuzzy_match("", "1.16.5"), Some(Vec::new()));
+        assert_eq!(fuzzy_match("16.5", "1.16.5"), Some(vec![2, 3, 4, 5]));
+        assert_eq!(fuzzy_match("SURV", "Survival"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(fuzzy_match("1165", "1.16.5"), Some(vec![0, 2, 3, 5]));
+        assert_eq!(fuzzy_match("1.17", "1.16.5"), None);
+    }
+}
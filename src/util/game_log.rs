@@ -0,0 +1,76 @@
+use std::{
+    fs,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    time::SystemTime,
+};
+
+/// How much of the end of the log to look at for the game shutting down
+const TAIL_LENGTH: u64 = 8 * 1024;
+
+/// What the game logs when closed and when crashed respectively
+const STOPPED_MARKERS: [&str; 2] = ["Stopping!", "Game crashed!"];
+
+/// When the game last wrote to its log, if there is one
+pub fn modified(log: &Path) -> Option<SystemTime> {
+    fs::metadata(log)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Whether the game writing the log has shut down
+///
+/// The game starts a new log on every launch, so this only tells about the latest one.
+pub fn has_stopped(log: &Path) -> io::Result<bool> {
+    let mut file = fs::File::open(log)?;
+    let length = file.metadata()?.len();
+    file.seek(SeekFrom::Start(length.saturating_sub(TAIL_LENGTH)))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    let tail = String::from_utf8_lossy(&tail);
+    // Mods may still log while shutting down, so the marker need not be the last line
+    Ok(tail
+        .lines()
+        .any(|line| STOPPED_MARKERS.iter().any(|marker| line.contains(marker))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+
+    #[test]
+    fn test_has_stopped() {
+        let temp = fixture::TempDir::new();
+        let log = temp.path().join("latest.log");
+        assert!(has_stopped(&log).is_err());
+        assert_eq!(modified(&log), None);
+
+        fs::write(
+            &log,
+            "[12:00:00] [main/INFO]: Setting user: Player\n\
+             [12:00:05] [Render thread/INFO]: Sound engine started\n",
+        )
+        .unwrap();
+        assert!(!has_stopped(&log).unwrap());
+        assert!(modified(&log).is_some());
+
+        fs::write(
+            &log,
+            "[12:00:05] [Render thread/INFO]: Sound engine started\n\
+             [12:30:00] [Render thread/INFO]: Stopping!\n\
+             [12:30:01] [Server thread/INFO]: Saving worlds\n",
+        )
+        .unwrap();
+        assert!(has_stopped(&log).unwrap());
+
+        fs::write(
+            &log,
+            "[12:00:05] [Render thread/INFO]: Sound engine started\n\
+             [12:30:00] [Render thread/FATAL]: Unreported exception thrown!\n\
+             [12:30:00] [Render thread/INFO]: #@!@# Game crashed! Crash report saved to: #@!@# crash.txt\n",
+        )
+        .unwrap();
+        assert!(has_stopped(&log).unwrap());
+    }
+}
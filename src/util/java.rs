@@ -17,6 +17,131 @@ use winreg::{enums::HKEY_LOCAL_MACHINE, RegKey};
 
 pub type Manifest = HashMap<String, String>;
 
+/// A java installation found on the system
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Installation {
+    pub home: PathBuf,
+    pub version: String,
+    pub major_version: u64,
+    pub vendor: Option<String>,
+}
+
+impl Installation {
+    /// Read the installation from the release file in its home
+    pub fn from_home<P: Into<PathBuf>>(home: P) -> Option<Self> {
+        let home = home.into();
+        let release = std::fs::read_to_string(home.join("release")).ok()?;
+        let release = parse_release(&release);
+        let version = release.get("JAVA_VERSION")?.to_owned();
+        let major_version = major_version(&version)?;
+        let vendor = release.get("IMPLEMENTOR").cloned();
+
+        Some(Self {
+            home,
+            version,
+            major_version,
+            vendor,
+        })
+    }
+
+    pub fn exec(&self) -> PathBuf {
+        self.home
+            .join("bin")
+            .join(if cfg!(windows) { "java.exe" } else { "java" })
+    }
+}
+
+/// Parse the KEY="value" lines of a jdk's release file
+pub fn parse_release(release_str: &str) -> HashMap<String, String> {
+    release_str
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => Some((
+                    key.trim().to_owned(),
+                    value.trim().trim_matches('"').to_owned(),
+                )),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// 1.8.0_292 -> 8, 17.0.2 -> 17
+pub fn major_version(version: &str) -> Option<u64> {
+    let mut parts = version.split(|c| c == '.' || c == '_' || c == '-' || c == '+');
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
+
+/// Pick the installation to use for a given major version: an exact match if there is one,
+/// otherwise the oldest newer version
+pub fn best_match(installations: &[Installation], major_version: u64) -> Option<&Installation> {
+    installations
+        .iter()
+        .find(|i| i.major_version == major_version)
+        .or_else(|| {
+            installations
+                .iter()
+                .filter(|i| i.major_version > major_version)
+                .min_by_key(|i| i.major_version)
+        })
+}
+
+#[cfg(target_os = "linux")]
+fn candidate_homes() -> Vec<PathBuf> {
+    let mut parents = vec![PathBuf::from("/usr/lib/jvm"), PathBuf::from("/opt")];
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        parents.push(home.join(".sdkman/candidates/java"));
+        parents.push(home.join(".asdf/installs/java"));
+    }
+
+    let mut homes: Vec<PathBuf> = std::env::var_os("JAVA_HOME")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    for parent in parents {
+        if let Ok(entries) = std::fs::read_dir(parent) {
+            homes.extend(entries.filter_map(|e| e.ok()).map(|e| e.path()));
+        }
+    }
+    if let Ok(home) = find_home_in_path() {
+        homes.push(home);
+    }
+    homes
+}
+
+#[cfg(target_os = "windows")]
+fn candidate_homes() -> Vec<PathBuf> {
+    find_home().ok().into_iter().collect()
+}
+
+/// Find all java installations, sorted by major version with duplicates (symlinks) removed
+pub fn discover() -> Vec<Installation> {
+    let mut installations: Vec<Installation> = Vec::new();
+    for home in candidate_homes() {
+        let home = match home.canonicalize() {
+            Ok(home) => home,
+            Err(_) => continue,
+        };
+        if installations.iter().any(|i| i.home == home) {
+            continue;
+        }
+        if let Some(installation) = Installation::from_home(home) {
+            installations.push(installation);
+        }
+    }
+    installations.sort_by(|a, b| {
+        b.major_version
+            .cmp(&a.major_version)
+            .then_with(|| a.home.cmp(&b.home))
+    });
+    installations
+}
+
 fn find_home_in_path() -> ::anyhow::Result<PathBuf> {
     let java = which("java")
         .context("Failed to find 'java' in path")?
@@ -26,7 +151,13 @@ fn find_home_in_path() -> ::anyhow::Result<PathBuf> {
 
 #[cfg(target_os = "linux")]
 fn find_home() -> ::anyhow::Result<PathBuf> {
-    return find_home_in_path();
+    find_home_in_path().or_else(|e| {
+        discover()
+            .into_iter()
+            .next()
+            .map(|installation| installation.home)
+            .ok_or(e)
+    })
 }
 
 #[cfg(target_os = "windows")]
@@ -93,6 +224,32 @@ fn find_home() -> ::anyhow::Result<PathBuf> {
     Ok(PathBuf::from(home))
 }
 
+/// Like find_exec, but prefers an installed java matching the major version
+///
+/// The home is only used as is when no installation matches, so that e.g. a java 8 JAVA_HOME is not
+/// used for versions needing java 17 while one is installed. Among equal matches it comes first.
+pub fn find_exec_for<P: Into<PathBuf>>(
+    home: Option<P>,
+    major_version: u64,
+) -> ::anyhow::Result<PathBuf> {
+    let home: Option<PathBuf> = home.map(Into::into);
+    let mut installations: Vec<Installation> = home
+        .as_ref()
+        .and_then(|home| home.canonicalize().ok())
+        .and_then(Installation::from_home)
+        .into_iter()
+        .collect();
+    for installation in discover() {
+        if !installations.iter().any(|i| i.home == installation.home) {
+            installations.push(installation);
+        }
+    }
+    match best_match(&installations, major_version) {
+        Some(installation) => Ok(installation.exec()),
+        None => find_exec(home),
+    }
+}
+
 pub fn find_exec<P: Into<PathBuf>>(home: Option<P>) -> ::anyhow::Result<PathBuf> {
     let home = match home {
         Some(home) => home.into(),
@@ -194,6 +351,60 @@ Main-Class: test.test.testclass",
         assert_eq!(manifest["Main-Class"], "test.test.testclass");
     }
 
+    #[test]
+    fn test_parse_release() {
+        let release = parse_release(
+            "IMPLEMENTOR=\"Eclipse Adoptium\"
+JAVA_VERSION=\"17.0.2\"
+OS_NAME=\"Linux\"",
+        );
+        assert_eq!(release["IMPLEMENTOR"], "Eclipse Adoptium");
+        assert_eq!(release["JAVA_VERSION"], "17.0.2");
+    }
+
+    #[test]
+    fn test_major_version() {
+        assert_eq!(major_version("1.8.0_292"), Some(8));
+        assert_eq!(major_version("17.0.2"), Some(17));
+        assert_eq!(major_version("21"), Some(21));
+        assert_eq!(major_version("foo"), None);
+    }
+
+    #[test]
+    fn test_best_match() {
+        let installation = |major_version: u64| Installation {
+            home: PathBuf::from(format!("/jvm/{}", major_version)),
+            version: major_version.to_string(),
+            major_version,
+            vendor: None,
+        };
+        let installations = vec![installation(21), installation(11), installation(8)];
+        assert_eq!(best_match(&installations, 8).unwrap().major_version, 8);
+        assert_eq!(best_match(&installations, 17).unwrap().major_version, 21);
+        assert!(best_match(&installations, 25).is_none());
+    }
+
+    #[test]
+    fn test_find_exec_for() {
+        let temp = crate::util::fixture::TempDir::new();
+        let home = temp.path().join("jdk8");
+        std::fs::create_dir_all(home.join("bin")).unwrap();
+        std::fs::write(home.join("release"), "JAVA_VERSION=\"1.8.0_292\"").unwrap();
+        let exec = home
+            .canonicalize()
+            .unwrap()
+            .join("bin")
+            .join(if cfg!(windows) { "java.exe" } else { "java" });
+        std::fs::write(&exec, "").unwrap();
+
+        assert_eq!(find_exec_for(Some(&home), 8).unwrap(), exec);
+        // Nothing installed is that new, so the home is used as is
+        assert_eq!(
+            find_exec_for(Some(&home), 1000).unwrap(),
+            home.join("bin").join(exec.file_name().unwrap())
+        );
+    }
+
     #[test]
     fn test_parse_maven_with_ext_and_extra() {
         let path = parse_maven("de.oceanlabs.mcp:mcp_config:1.14.4-20190829.143755:mappings@txt");
@@ -1,21 +1,31 @@
 use anyhow::Context;
-use bytes::Buf;
 use data_encoding::HEXLOWER;
-use futures::stream::StreamExt;
 use sha1::{Digest, Sha1};
 use std::{
     fs,
     io::{BufRead, BufReader},
     path::{Path, PathBuf},
 };
-use tokio::io::AsyncWriteExt;
 use tui::layout::{Constraint, Direction, Layout, Rect};
 
+pub mod archive;
+mod cache;
+mod cancel;
+pub mod download;
 mod events;
+#[cfg(test)]
+pub mod fixture;
+mod fuzzy;
+pub mod game_log;
 pub mod java;
+mod partial;
 mod progress;
 
+pub use cache::CachePolicy;
+pub use cancel::{is_cancelled, Cancel};
 pub use events::{Event, Events, Key};
+pub use fuzzy::fuzzy_match;
+pub use partial::PartialDirectory;
 pub use progress::Progress;
 
 pub fn wrap_dec(cur: usize, max: usize) -> usize {
@@ -110,6 +120,7 @@ pub fn sha1_file<P: AsRef<Path>>(path: P) -> ::anyhow::Result<String> {
 pub async fn sha1_file_with_progress<P: AsRef<Path>>(
     pb: &Progress,
     path: P,
+    cancel: &Cancel,
 ) -> ::anyhow::Result<String> {
     let file = fs::File::open(path)?;
     let mut hasher = Sha1::new();
@@ -117,6 +128,7 @@ pub async fn sha1_file_with_progress<P: AsRef<Path>>(
     let mut reader = BufReader::new(file);
 
     loop {
+        cancel.check()?;
         let length = {
             let buffer = reader.fill_buf()?;
             hasher.input(buffer);
@@ -133,45 +145,112 @@ pub async fn sha1_file_with_progress<P: AsRef<Path>>(
     Ok(HEXLOWER.encode(result.as_ref()))
 }
 
-pub async fn download_file<P: Into<PathBuf>>(url: &str, path: P) -> ::anyhow::Result<()> {
-    let path = path.into();
-    fs::create_dir_all(path.parent().unwrap()).context("Couldn't create parent folder.")?;
-    let mut file = tokio::fs::File::create(&path).await?;
-    let response = reqwest::get(url).await?.error_for_status()?;
-    let mut stream = response.bytes_stream();
-    while let Some(v) = stream.next().await {
-        file.write_all(&v?).await?;
+pub fn directory_size(path: &Path, exclude: &[PathBuf]) -> ::anyhow::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let path = entry.path();
+        if exclude.contains(&path) {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += directory_size(&path, exclude)?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
     }
-    file.flush().await?;
+    Ok(size)
+}
 
-    Ok(())
+/// Human readable size, e.g. 12.3 MiB
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
 }
 
-pub async fn download_file_with_progress<P: Into<PathBuf>>(
+/// Human readable playtime, e.g. 3h 05m
+pub fn format_playtime(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes();
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+/// Recursively copy a directory, skipping the excluded paths
+///
+/// Symlinks are recreated rather than followed.
+pub async fn copy_directory_with_progress<P: AsRef<Path>>(
     pb: &Progress,
-    url: &str,
-    path: P,
+    from: P,
+    to: P,
+    exclude: &[PathBuf],
 ) -> ::anyhow::Result<()> {
-    let path = path.into();
-    fs::create_dir_all(path.parent().unwrap()).context("Couldn't create parent folder.")?;
-    let mut file = tokio::fs::File::create(&path).await?;
-    let response = reqwest::get(url).await?.error_for_status()?;
-    let content_length = response.content_length();
-
-    if let Some(content_length) = content_length {
-        pb.set_length(content_length).await;
-    }
+    pb.set_length(directory_size(from.as_ref(), exclude)?).await;
 
-    let mut stream = response.bytes_stream();
-    while let Some(v) = stream.next().await {
-        let mut v = v?;
-        while v.has_remaining() {
-            // Writes some prefix of the byte string, not necessarily
-            // all of it.
-            let written = file.write_buf(&mut v).await?;
-            pb.inc(written as u64).await;
+    let mut stack = vec![(from.as_ref().to_path_buf(), to.as_ref().to_path_buf())];
+    while let Some((from, to)) = stack.pop() {
+        fs::create_dir_all(&to).with_context(|| format!("Failed to create {:?}", to))?;
+        for entry in fs::read_dir(&from)? {
+            let entry = entry?;
+            let path = entry.path();
+            if exclude.contains(&path) {
+                continue;
+            }
+            let target = to.join(entry.file_name());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                stack.push((path, target));
+            } else if file_type.is_file() {
+                let copied = fs::copy(&path, &target)
+                    .with_context(|| format!("Failed to copy {:?}", path))?;
+                pb.inc(copied).await;
+            } else if file_type.is_symlink() {
+                copy_symlink(&path, &target)?;
+            }
         }
     }
-    file.flush().await?;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> ::anyhow::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> ::anyhow::Result<()> {
+    let target = fs::read_link(from)?;
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(target, to)?;
+    } else {
+        std::os::windows::fs::symlink_file(target, to)?;
+    }
     Ok(())
 }
+
+pub async fn download_file<P: Into<PathBuf>>(url: &str, path: P) -> ::anyhow::Result<()> {
+    download::download(None, url, &path.into(), &Default::default(), &Cancel::new()).await
+}
+
+pub async fn download_file_with_progress<P: Into<PathBuf>>(
+    pb: &Progress,
+    url: &str,
+    path: P,
+    cancel: &Cancel,
+) -> ::anyhow::Result<()> {
+    download::download(Some(pb), url, &path.into(), &Default::default(), cancel).await
+}
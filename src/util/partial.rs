@@ -0,0 +1,74 @@
+use ::anyhow::Context;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A directory filled next to where it belongs, so that nothing is left half done there
+///
+/// It is moved into place by [`PartialDirectory::finish`], and removed when dropped before that.
+#[derive(Debug)]
+pub struct PartialDirectory {
+    path: PathBuf,
+    target: PathBuf,
+    finished: bool,
+}
+
+impl PartialDirectory {
+    /// Reserve `<target>.partial`, removing what an earlier attempt may have left there
+    pub fn new(target: &Path) -> Self {
+        let mut path = target.as_os_str().to_owned();
+        path.push(".partial");
+        let path = PathBuf::from(path);
+        let _ = fs::remove_dir_all(&path);
+        Self {
+            path,
+            target: target.to_path_buf(),
+            finished: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Move the directory into place, which must not exist yet
+    pub fn finish(mut self) -> ::anyhow::Result<()> {
+        fs::rename(&self.path, &self.target)
+            .with_context(|| format!("Failed to move {:?} into place", self.target))?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl Drop for PartialDirectory {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+
+    #[test]
+    fn test_partial_directory() {
+        let temp = fixture::TempDir::new();
+        let target = temp.path().join("instance");
+
+        let partial = PartialDirectory::new(&target);
+        fs::create_dir_all(partial.path().join("mods")).unwrap();
+        drop(partial);
+        assert!(!temp.path().join("instance.partial").exists());
+        assert!(!target.exists());
+
+        let partial = PartialDirectory::new(&target);
+        fs::create_dir_all(partial.path().join("mods")).unwrap();
+        partial.finish().unwrap();
+        assert!(target.join("mods").is_dir());
+        assert!(!temp.path().join("instance.partial").exists());
+    }
+}
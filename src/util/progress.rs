@@ -32,6 +32,10 @@ impl Progress {
         self.state.write().await.length = length;
     }
 
+    pub async fn set_value(&self, value: u64) {
+        self.state.write().await.value = value;
+    }
+
     pub async fn inc(&self, val: u64) {
         self.state.write().await.value += val;
     }
@@ -0,0 +1,149 @@
+use ::anyhow::Context;
+use log::{debug, trace, warn};
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use uuid::Uuid;
+
+use crate::{Instances, IoEvent, Paths};
+
+/// How long notify waits for a path to settle before reporting it
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(500);
+
+/// How long we keep collecting events before dispatching them, so dropping many jars at once only
+/// results in one refresh per instance
+const BATCH_DELAY: Duration = Duration::from_millis(250);
+
+/// Watches instances.json and the mods directory of every instance for changes made outside
+/// polyblock, and dispatches io events to bring the app up to date.
+pub struct Watcher {
+    inner: RecommendedWatcher,
+    /// Watched mods directories and the instance they belong to, shared with the event thread
+    /// since instances kept in place can live anywhere
+    watched_mods_directories: Arc<Mutex<HashMap<PathBuf, Uuid>>>,
+}
+
+impl Watcher {
+    pub fn new(paths: &Paths, io_tx: Sender<IoEvent>) -> ::anyhow::Result<Self> {
+        let (tx, rx) = channel::<DebouncedEvent>();
+        let mut inner = notify::watcher(tx, DEBOUNCE_DELAY)?;
+
+        fs::create_dir_all(&paths.directory.data).context("Failed to create data directory!")?;
+
+        // Watch the directory rather than the file itself since editors (and serde) often replace
+        // the file, which would make us lose the watch
+        inner.watch(&paths.directory.data, RecursiveMode::NonRecursive)?;
+
+        let instances_file = paths.file.instances.clone();
+        let watched_mods_directories = Arc::new(Mutex::new(HashMap::new()));
+        let mods_directories = Arc::clone(&watched_mods_directories);
+
+        thread::Builder::new()
+            .name("watcher".into())
+            .spawn(move || {
+                while let Ok(event) = rx.recv() {
+                    let mut io_events = HashSet::new();
+                    let mut next = Some(event);
+                    while let Some(event) = next {
+                        trace!("Got watcher event: {:?}", event);
+                        let mapped =
+                            map_event(event, &instances_file, &mods_directories.lock().unwrap());
+                        if let Some(io_event) = mapped {
+                            io_events.insert(io_event);
+                        }
+                        next = match rx.recv_timeout(BATCH_DELAY) {
+                            Ok(event) => Some(event),
+                            Err(RecvTimeoutError::Timeout) => None,
+                            Err(RecvTimeoutError::Disconnected) => return,
+                        };
+                    }
+                    for io_event in io_events {
+                        debug!("Watcher dispatching {:?}", io_event);
+                        if io_tx.send(io_event).is_err() {
+                            return;
+                        }
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            inner,
+            watched_mods_directories,
+        })
+    }
+
+    /// Make sure exactly the mods directories of the given instances are watched
+    pub fn sync(&mut self, instances: &Instances) {
+        let wanted: HashMap<PathBuf, Uuid> = instances
+            .inner
+            .values()
+            .map(|instance| (instance.mods_directory(), instance.uuid))
+            .filter(|(directory, _)| directory.is_dir())
+            .collect();
+
+        let mut watched_mods_directories = self.watched_mods_directories.lock().unwrap();
+        for directory in watched_mods_directories.keys() {
+            if !wanted.contains_key(directory) {
+                debug!("Unwatching {:?}", directory);
+                let _ = self.inner.unwatch(directory);
+            }
+        }
+
+        let mut watched = HashMap::new();
+        for (directory, uuid) in wanted {
+            if !watched_mods_directories.contains_key(&directory) {
+                debug!("Watching {:?}", directory);
+                if let Err(e) = self.inner.watch(&directory, RecursiveMode::NonRecursive) {
+                    warn!("Failed to watch {:?}: {:?}", directory, e);
+                    continue;
+                }
+            }
+            watched.insert(directory, uuid);
+        }
+        *watched_mods_directories = watched;
+    }
+}
+
+fn map_event(
+    event: DebouncedEvent,
+    instances_file: &Path,
+    mods_directories: &HashMap<PathBuf, Uuid>,
+) -> Option<IoEvent> {
+    match event {
+        DebouncedEvent::Create(path)
+        | DebouncedEvent::Write(path)
+        | DebouncedEvent::Remove(path) => map_path(&path, instances_file, mods_directories),
+        DebouncedEvent::Rename(from, to) => map_path(&to, instances_file, mods_directories)
+            .or_else(|| map_path(&from, instances_file, mods_directories)),
+        DebouncedEvent::Error(e, path) => {
+            warn!("Watcher error for {:?}: {:?}", path, e);
+            None
+        }
+        _ => None,
+    }
+}
+
+fn map_path(
+    path: &Path,
+    instances_file: &Path,
+    mods_directories: &HashMap<PathBuf, Uuid>,
+) -> Option<IoEvent> {
+    if path == instances_file {
+        return Some(IoEvent::ReloadInstances);
+    }
+
+    // <mods directory>/<file>, or the mods directory itself
+    mods_directories
+        .get(path)
+        .or_else(|| mods_directories.get(path.parent()?))
+        .map(|uuid| IoEvent::RefreshMods(*uuid))
+}
@@ -0,0 +1,292 @@
+use ::anyhow::{anyhow, Context};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use log::warn;
+use std::{
+    cmp::Reverse,
+    fmt,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+use zip::{ZipArchive, ZipWriter};
+
+use crate::util;
+
+pub mod nbt;
+
+/// Held open by the game while the world is loaded, so it is never part of a backup
+const SESSION_LOCK: &str = "session.lock";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl GameMode {
+    fn from_id(id: i64) -> Option<Self> {
+        match id {
+            0 => Some(GameMode::Survival),
+            1 => Some(GameMode::Creative),
+            2 => Some(GameMode::Adventure),
+            3 => Some(GameMode::Spectator),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameMode::Survival => write!(f, "Survival"),
+            GameMode::Creative => write!(f, "Creative"),
+            GameMode::Adventure => write!(f, "Adventure"),
+            GameMode::Spectator => write!(f, "Spectator"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct World {
+    pub path: PathBuf,
+    pub name: String,
+    pub last_played: Option<DateTime<Local>>,
+    pub game_mode: Option<GameMode>,
+    pub hardcore: bool,
+    /// Only stored by 1.9 and newer
+    pub version: Option<String>,
+    pub size: u64,
+    /// Gone from the saves directory, only kept by its backups
+    pub deleted: bool,
+}
+
+impl World {
+    pub fn from_directory<P: Into<PathBuf>>(path: P) -> ::anyhow::Result<Self> {
+        let path = path.into();
+        let root = nbt::read_gzip_file(path.join("level.dat"))?;
+        let data = root
+            .get("Data")
+            .ok_or_else(|| anyhow!("level.dat of {:?} has no Data tag.", path))?;
+
+        let directory_name = path.file_name().unwrap().to_string_lossy().into_owned();
+
+        Ok(Self {
+            name: data
+                .get("LevelName")
+                .and_then(nbt::Tag::as_str)
+                .map(String::from)
+                .unwrap_or(directory_name),
+            last_played: data
+                .get("LastPlayed")
+                .and_then(nbt::Tag::as_i64)
+                .and_then(|ms| {
+                    Utc.timestamp_opt(ms / 1000, (ms % 1000 * 1_000_000) as u32)
+                        .single()
+                })
+                .map(|last_played| last_played.with_timezone(&Local)),
+            game_mode: data
+                .get("GameType")
+                .and_then(nbt::Tag::as_i64)
+                .and_then(GameMode::from_id),
+            hardcore: data.get("hardcore").and_then(nbt::Tag::as_i64) == Some(1),
+            version: data
+                .get("Version")
+                .and_then(|version| version.get("Name"))
+                .and_then(nbt::Tag::as_str)
+                .map(String::from),
+            size: util::directory_size(&path, &[])?,
+            path,
+            deleted: false,
+        })
+    }
+
+    /// A deleted world, which can be brought back from its backups into `path`
+    fn deleted(path: PathBuf) -> Self {
+        Self {
+            name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            last_played: None,
+            game_mode: None,
+            hardcore: false,
+            version: None,
+            size: 0,
+            path,
+            deleted: true,
+        }
+    }
+
+    pub fn directory_name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    pub fn game_mode_string(&self) -> String {
+        match (self.game_mode, self.hardcore) {
+            (_, true) => String::from("Hardcore"),
+            (Some(game_mode), false) => game_mode.to_string(),
+            (None, false) => String::from("Unknown"),
+        }
+    }
+}
+
+/// All worlds in a saves directory, most recently played first
+///
+/// Worlds that were deleted but still have backups are listed last, so they can be restored.
+pub fn list(saves_directory: &Path, backups_directory: &Path) -> ::anyhow::Result<Vec<World>> {
+    let mut worlds = Vec::new();
+    if saves_directory.is_dir() {
+        for entry in fs::read_dir(saves_directory).context("Failed to read saves directory.")? {
+            let path = entry?.path();
+            if !path.join("level.dat").is_file() {
+                continue;
+            }
+            match World::from_directory(&path) {
+                Ok(world) => worlds.push(world),
+                Err(e) => warn!("Skipping world {:?}: {:?}", path, e),
+            }
+        }
+    }
+    worlds.sort_by_key(|world| Reverse(world.last_played));
+
+    if backups_directory.is_dir() {
+        let mut deleted = Vec::new();
+        for entry in fs::read_dir(backups_directory).context("Failed to read backups directory.")? {
+            let entry = entry?;
+            let directory_name = entry.file_name().to_string_lossy().into_owned();
+            let path = saves_directory.join(&directory_name);
+            if entry.path().is_dir()
+                && !path.exists()
+                && !backups(backups_directory, &directory_name)?.is_empty()
+            {
+                deleted.push(World::deleted(path));
+            }
+        }
+        deleted.sort_by(|a, b| a.name.cmp(&b.name));
+        worlds.extend(deleted);
+    }
+    Ok(worlds)
+}
+
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub created: DateTime<Local>,
+    pub size: u64,
+}
+
+/// Backups of a world are stored as <backups_directory>/<world directory name>/<timestamp>.zip
+fn world_backups_directory(backups_directory: &Path, directory_name: &str) -> PathBuf {
+    backups_directory.join(directory_name)
+}
+
+/// All backups of a world, newest first
+pub fn backups(backups_directory: &Path, directory_name: &str) -> ::anyhow::Result<Vec<Backup>> {
+    let directory = world_backups_directory(backups_directory, directory_name);
+    if !directory.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(&directory).context("Failed to read backups directory.")? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("zip") {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        backups.push(Backup {
+            path,
+            created: metadata.modified()?.into(),
+            size: metadata.len(),
+        });
+    }
+    backups.sort_by_key(|backup| Reverse(backup.created));
+    Ok(backups)
+}
+
+/// Zip the world into a new backup, returning the path of the backup
+pub async fn backup(
+    pb: &util::Progress,
+    world: &World,
+    backups_directory: &Path,
+) -> ::anyhow::Result<PathBuf> {
+    let directory = world_backups_directory(backups_directory, &world.directory_name());
+    fs::create_dir_all(&directory).context("Failed to create backups directory!")?;
+
+    let path = directory.join(format!("{}.zip", Local::now().format("%Y-%m-%d_%H-%M-%S")));
+    let partial_path = path.with_extension("zip.part");
+
+    pb.set_length(world.size).await;
+    if let Err(e) = write_backup(pb, &world.path, &partial_path).await {
+        let _ = fs::remove_file(&partial_path);
+        return Err(e.context(format!("Failed to back up {}", world.name)));
+    }
+    fs::rename(&partial_path, &path).context("Failed to move backup into place!")?;
+
+    Ok(path)
+}
+
+async fn write_backup(
+    pb: &util::Progress,
+    world_directory: &Path,
+    path: &Path,
+) -> ::anyhow::Result<()> {
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(path)?));
+    util::archive::add_directory(
+        pb,
+        &mut zip,
+        world_directory,
+        "",
+        &[world_directory.join(SESSION_LOCK)],
+    )
+    .await?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// Replace the world (or create it if it was deleted) with the contents of a backup
+pub async fn restore(
+    pb: &util::Progress,
+    backup: &Backup,
+    world_directory: &Path,
+) -> ::anyhow::Result<()> {
+    let mut archive = ZipArchive::new(BufReader::new(
+        File::open(&backup.path).context("Failed to open backup!")?,
+    ))?;
+    util::archive::replace_directory(pb, &mut archive, "", world_directory, &[])
+        .await
+        .context("Failed to restore backup")
+}
+
+pub fn delete(world: &World) -> ::anyhow::Result<()> {
+    fs::remove_dir_all(&world.path).with_context(|| format!("Failed to delete {}", world.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture;
+
+    #[test]
+    fn test_list_deleted() {
+        let temp = fixture::TempDir::new();
+        let saves = temp.path().join("saves");
+        let backups_directory = temp.path().join("backups");
+        fs::create_dir_all(saves.join("Kept")).unwrap();
+        for name in &["Kept", "Gone", "Empty"] {
+            fs::create_dir_all(backups_directory.join(name)).unwrap();
+        }
+        fs::write(backups_directory.join("Kept/2020-05-01_12-00-00.zip"), "").unwrap();
+        fs::write(backups_directory.join("Gone/2020-05-01_12-00-00.zip"), "").unwrap();
+
+        let worlds = list(&saves, &backups_directory).unwrap();
+        assert_eq!(worlds.len(), 1);
+        assert_eq!(worlds[0].name, "Gone");
+        assert!(worlds[0].deleted);
+        assert_eq!(worlds[0].path, saves.join("Gone"));
+    }
+}
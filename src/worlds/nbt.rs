@@ -0,0 +1,196 @@
+/// Minimal reader for minecraft's named binary tag format
+///
+/// https://wiki.vg/NBT
+use ::anyhow::{anyhow, Context};
+use flate2::read::GzDecoder;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(HashMap<String, Tag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Any integer tag widened to i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(v) => Some(v as i64),
+            Tag::Short(v) => Some(v as i64),
+            Tag::Int(v) => Some(v as i64),
+            Tag::Long(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// Read a gzipped nbt file such as level.dat, returning the root tag
+pub fn read_gzip_file<P: AsRef<Path>>(path: P) -> ::anyhow::Result<Tag> {
+    let file =
+        File::open(path.as_ref()).with_context(|| format!("Failed to open {:?}", path.as_ref()))?;
+    let mut reader = GzDecoder::new(BufReader::new(file));
+    let (_, tag) =
+        read(&mut reader).with_context(|| format!("Failed to read {:?}", path.as_ref()))?;
+    Ok(tag)
+}
+
+/// Read a single named tag
+pub fn read<R: Read>(reader: &mut R) -> ::anyhow::Result<(String, Tag)> {
+    let id = read_u8(reader)?;
+    if id == 0 {
+        return Err(anyhow!("Unexpected end tag."));
+    }
+    let name = read_string(reader)?;
+    let tag = read_payload(reader, id)?;
+    Ok((name, tag))
+}
+
+fn read_payload<R: Read>(reader: &mut R, id: u8) -> ::anyhow::Result<Tag> {
+    Ok(match id {
+        1 => Tag::Byte(read_u8(reader)? as i8),
+        2 => Tag::Short(i16::from_be_bytes(read_array(reader)?)),
+        3 => Tag::Int(i32::from_be_bytes(read_array(reader)?)),
+        4 => Tag::Long(i64::from_be_bytes(read_array(reader)?)),
+        5 => Tag::Float(f32::from_be_bytes(read_array(reader)?)),
+        6 => Tag::Double(f64::from_be_bytes(read_array(reader)?)),
+        7 => {
+            let len = read_len(reader)?;
+            let mut buf = vec![0u8; len];
+            reader.read_exact(&mut buf)?;
+            Tag::ByteArray(buf.into_iter().map(|b| b as i8).collect())
+        }
+        8 => Tag::String(read_string(reader)?),
+        9 => {
+            let item_id = read_u8(reader)?;
+            let len = read_len(reader)?;
+            let mut items = Vec::with_capacity(len.min(1024));
+            for _ in 0..len {
+                items.push(read_payload(reader, item_id)?);
+            }
+            Tag::List(items)
+        }
+        10 => {
+            let mut map = HashMap::new();
+            loop {
+                let item_id = read_u8(reader)?;
+                if item_id == 0 {
+                    break;
+                }
+                let name = read_string(reader)?;
+                map.insert(name, read_payload(reader, item_id)?);
+            }
+            Tag::Compound(map)
+        }
+        11 => {
+            let len = read_len(reader)?;
+            let mut items = Vec::with_capacity(len.min(1024));
+            for _ in 0..len {
+                items.push(i32::from_be_bytes(read_array(reader)?));
+            }
+            Tag::IntArray(items)
+        }
+        12 => {
+            let len = read_len(reader)?;
+            let mut items = Vec::with_capacity(len.min(1024));
+            for _ in 0..len {
+                items.push(i64::from_be_bytes(read_array(reader)?));
+            }
+            Tag::LongArray(items)
+        }
+        id => return Err(anyhow!("Unknown tag id {}.", id)),
+    })
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> ::anyhow::Result<u8> {
+    Ok(read_array::<R, [u8; 1]>(reader)?[0])
+}
+
+fn read_array<R: Read, A: Default + AsMut<[u8]>>(reader: &mut R) -> ::anyhow::Result<A> {
+    let mut buf = A::default();
+    reader.read_exact(buf.as_mut())?;
+    Ok(buf)
+}
+
+fn read_len<R: Read>(reader: &mut R) -> ::anyhow::Result<usize> {
+    let len = i32::from_be_bytes(read_array(reader)?);
+    if len < 0 {
+        return Err(anyhow!("Negative length {}.", len));
+    }
+    Ok(len as usize)
+}
+
+fn read_string<R: Read>(reader: &mut R) -> ::anyhow::Result<String> {
+    let len = u16::from_be_bytes(read_array(reader)?);
+    let mut buf = vec![0u8; len as usize];
+    reader.read_exact(&mut buf)?;
+    // Strings are "modified utf-8" which only differs from utf-8 for null and supplementary chars
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            10, 0, 0, // root compound with empty name
+                10, 0, 4, b'D', b'a', b't', b'a', // compound "Data"
+                    8, 0, 9, b'L', b'e', b'v', b'e', b'l', b'N', b'a', b'm', b'e', 0, 4, b'T', b'e', b's', b't',
+                    3, 0, 8, b'G', b'a', b'm', b'e', b'T', b'y', b'p', b'e', 0, 0, 0, 1,
+                    4, 0, 10, b'L', b'a', b's', b't', b'P', b'l', b'a', b'y', b'e', b'd', 0, 0, 1, 0x71, 0x8c, 0x2f, 0x4a, 0x00,
+                    9, 0, 1, b'L', 1, 0, 0, 0, 2, 5, 6,
+                0,
+            0,
+        ];
+        let (name, root) = read(&mut &data[..]).unwrap();
+        assert_eq!(name, "");
+        let level = root.get("Data").unwrap();
+        assert_eq!(level.get("LevelName").unwrap().as_str(), Some("Test"));
+        assert_eq!(level.get("GameType").unwrap().as_i64(), Some(1));
+        assert_eq!(
+            level.get("LastPlayed").unwrap().as_i64(),
+            Some(1587194841600)
+        );
+        assert_eq!(
+            level.get("L"),
+            Some(&Tag::List(vec![Tag::Byte(5), Tag::Byte(6)]))
+        );
+    }
+
+    #[test]
+    fn test_read_truncated() {
+        let data: &[u8] = &[10, 0, 0, 8, 0, 1];
+        assert!(read(&mut &data[..]).is_err());
+    }
+}